@@ -0,0 +1,155 @@
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_server::tls_rustls::RustlsConfig;
+use lazy_static::lazy_static;
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use rustls::{
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use std::{net::SocketAddr, sync::Arc};
+use tracing::{info, warn};
+
+use crate::config::Config;
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref ADMIN_ACTIONS: IntCounterVec = register_int_counter_vec!(
+        "admin_actions_total",
+        "Admin/simulate requests by route template and outcome (ok, denied, failed)",
+        &["action", "outcome"]
+    ).unwrap();
+}
+
+// ---------- Config ----------
+/// How the admin and simulate routes are protected.
+pub struct AdminAuth {
+    /// Bearer token from `admin_token` or `admin_token_file`.
+    token: Option<String>,
+    /// Client certificates are verified during the TLS handshake (`admin_tls_client_ca`).
+    mtls: bool,
+}
+
+impl AdminAuth {
+    pub fn new(token: Option<String>, mtls: bool) -> Self {
+        Self { token, mtls }
+    }
+
+    pub fn from_config(cfg: &Config) -> anyhow::Result<Self> {
+        let token = match (&cfg.admin_token, &cfg.admin_token_file) {
+            (Some(t), _) => Some(t.clone()),
+            (None, Some(path)) => Some(std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("cannot read admin_token_file {path:?}: {e}"))?
+                .trim()
+                .to_string()),
+            (None, None) => None,
+        };
+        if token.as_deref() == Some("") {
+            anyhow::bail!("admin token is empty");
+        }
+        let auth = Self::new(token, cfg.admin_tls_client_ca.is_some());
+        match (&auth.token, auth.mtls) {
+            (Some(_), true) => info!("Admin: bearer token and client certificate required"),
+            (Some(_), false) => info!("Admin: bearer token required"),
+            (None, true) => info!("Admin: client certificate required"),
+            (None, false) => warn!("Admin: no admin token or mTLS configured; admin routes only answer to loopback clients"),
+        }
+        Ok(auth)
+    }
+
+    /// Who is calling, or why the request is refused.
+    fn authenticate(&self, req: &Request) -> Result<&'static str, &'static str> {
+        if let Some(expected) = &self.token {
+            let presented = req.headers().get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "));
+            return match presented {
+                Some(t) if constant_time_eq(t.as_bytes(), expected.as_bytes()) => {
+                    Ok(if self.mtls { "bearer+mtls" } else { "bearer" })
+                }
+                Some(_) => Err("invalid bearer token"),
+                None => Err("missing bearer token"),
+            };
+        }
+        if self.mtls {
+            // the handshake already rejected clients without a trusted certificate
+            return Ok("mtls");
+        }
+        match peer(req) {
+            Some(addr) if addr.ip().is_loopback() => Ok("loopback"),
+            _ => Err("admin API is restricted to loopback without an admin token"),
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn peer(req: &Request) -> Option<SocketAddr> {
+    req.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0)
+}
+
+/// TLS for the admin listener; with `client_ca` set, clients must present a certificate it signed.
+pub fn tls_config(cert: &str, key: &str, client_ca: Option<&str>) -> anyhow::Result<RustlsConfig> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|it| it.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("cannot read admin_tls_cert {cert:?}: {e}"))?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| anyhow::anyhow!("cannot read admin_tls_key {key:?}: {e}"))?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+    let mut config = match client_ca {
+        Some(ca) => {
+            let mut roots = RootCertStore::empty();
+            for c in CertificateDer::pem_file_iter(ca).map_err(|e| anyhow::anyhow!("cannot read admin_tls_client_ca {ca:?}: {e}"))? {
+                roots.add(c?)?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier).with_single_cert(certs, key)?
+        }
+        None => builder.with_no_client_auth().with_single_cert(certs, key)?,
+    };
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+// ---------- Middleware ----------
+/// Authenticates admin/simulate requests and writes one audit line per mutation or denial.
+pub async fn guard(State(auth): State<Arc<AdminAuth>>, req: Request, next: Next) -> Response {
+    let action = req.extensions().get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+    let method = req.method().clone();
+    let query = req.uri().query().unwrap_or("").to_string();
+    let remote = peer(&req).map(|a| a.to_string()).unwrap_or_else(|| "unknown".to_string());
+
+    let principal = match auth.authenticate(&req) {
+        Ok(p) => p,
+        Err(reason) => {
+            ADMIN_ACTIONS.with_label_values(&[&action, "denied"]).inc();
+            warn!(target: "audit", %method, %action, %query, %remote, reason, outcome = "denied", "admin action");
+            let status = if auth.token.is_some() { StatusCode::UNAUTHORIZED } else { StatusCode::FORBIDDEN };
+            let mut res = (status, reason).into_response();
+            if status == StatusCode::UNAUTHORIZED {
+                res.headers_mut().insert(header::WWW_AUTHENTICATE, header::HeaderValue::from_static("Bearer"));
+            }
+            return res;
+        }
+    };
+
+    let res = next.run(req).await;
+    // reads (status pages, listings) are not audited
+    if method != Method::GET {
+        let status = res.status().as_u16();
+        let outcome = if res.status().is_success() { "ok" } else { "failed" };
+        ADMIN_ACTIONS.with_label_values(&[&action, outcome]).inc();
+        info!(target: "audit", %method, %action, %query, %remote, principal, status, outcome, "admin action");
+    }
+    res
+}
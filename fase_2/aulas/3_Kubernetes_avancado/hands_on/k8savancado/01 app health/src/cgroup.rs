@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+// Readers for the cgroup v2 files of the cgroup this process lives in. Every
+// function returns `None` when the file is missing (cgroup v1, not on Linux)
+// or the value is unlimited (`max`).
+
+/// `/sys/fs/cgroup/<path from /proc/self/cgroup>`; plain `/sys/fs/cgroup` inside a cgroup namespace.
+fn dir() -> PathBuf {
+    let root = PathBuf::from(std::env::var("CGROUP_ROOT").unwrap_or_else(|_| "/sys/fs/cgroup".to_string()));
+    let own = std::fs::read_to_string("/proc/self/cgroup").ok()
+        .and_then(|s| s.lines().find_map(|l| l.strip_prefix("0::").map(str::to_string)));
+    match own {
+        Some(p) if p != "/" && root.join(p.trim_start_matches('/')).exists() => root.join(p.trim_start_matches('/')),
+        _ => root,
+    }
+}
+
+fn read(file: &str) -> Option<String> {
+    std::fs::read_to_string(dir().join(file)).ok().map(|s| s.trim().to_string())
+}
+
+fn keyed(file: &str, key: &str) -> Option<u64> {
+    read(file)?.lines().find_map(|l| {
+        let (k, v) = l.split_once(' ')?;
+        if k == key { v.trim().parse().ok() } else { None }
+    })
+}
+
+/// `memory.max` in bytes.
+pub fn memory_max() -> Option<u64> {
+    read("memory.max")?.parse().ok()
+}
+
+/// `memory.current` in bytes (RSS + page cache charged to the cgroup).
+pub fn memory_current() -> Option<u64> {
+    read("memory.current")?.parse().ok()
+}
+
+/// `oom_kill` from `memory.events`.
+pub fn oom_kills() -> Option<u64> {
+    keyed("memory.events", "oom_kill")
+}
+
+/// CPU limit in cores from `cpu.max` (`quota period`).
+pub fn cpu_limit_cores() -> Option<f64> {
+    let raw = read("cpu.max")?;
+    let (quota, period) = raw.split_once(' ')?;
+    let quota: f64 = quota.parse().ok()?;
+    let period: f64 = period.parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Throttling counters from `cpu.stat`.
+pub struct CpuStat {
+    pub nr_periods: u64,
+    pub nr_throttled: u64,
+    pub throttled_usec: u64,
+}
+
+pub fn cpu_stat() -> Option<CpuStat> {
+    Some(CpuStat {
+        nr_periods: keyed("cpu.stat", "nr_periods")?,
+        nr_throttled: keyed("cpu.stat", "nr_throttled")?,
+        throttled_usec: keyed("cpu.stat", "throttled_usec")?,
+    })
+}
@@ -0,0 +1,487 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use lazy_static::lazy_static;
+use parking_lot::{Mutex, RwLock};
+use prometheus::{register_int_counter_vec, IntCounterVec};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use tracing::{error, info, warn};
+
+use crate::{faults::FaultRule, limiter::LimiterConfig, scenario::de_duration, slo::SloConfig, AppState};
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref RELOADS: IntCounterVec = register_int_counter_vec!(
+        "config_reloads_total",
+        "Config file reloads by result (success, error)",
+        &["result"]
+    ).unwrap();
+}
+
+// ---------- Config ----------
+/// Every setting can come from the config file (key = env var name in lowercase)
+/// and be overridden by the environment variable of the same name.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub port: u16,
+    /// Separate listener for the admin/simulate routes.
+    pub admin_port: Option<u16>,
+    /// `grpc.health.v1.Health` listener for `grpc:` probes; off when unset.
+    pub grpc_port: Option<u16>,
+    pub startup_delay_ms: u64,
+    pub ready_after_ms: u64,
+    #[serde(deserialize_with = "de_duration", serialize_with = "ser_duration")]
+    pub pre_stop_delay: Duration,
+    #[serde(deserialize_with = "de_duration", serialize_with = "ser_duration")]
+    pub drain_timeout: Duration,
+    /// `kind:name=target,…`, see `health::HealthRegistry::register_from_spec`.
+    pub health_checks: Option<String>,
+    pub health_check_interval_ms: u64,
+    pub health_check_timeout_ms: u64,
+    pub ready_max_leak_mb: Option<usize>,
+    pub resource_metrics_interval_ms: u64,
+    pub scenario_file: Option<String>,
+    pub scenario_autostart: bool,
+    /// `EnvFilter` directives such as `info` or `info,app_health_demo=debug`; `RUST_LOG` when unset.
+    pub log_level: Option<String>,
+    /// Fault rules owned by the config file (file only; the admin API manages its own).
+    pub faults: Vec<FaultRule>,
+    /// How often the config file is polled for changes.
+    #[serde(deserialize_with = "de_duration", serialize_with = "ser_duration")]
+    pub config_reload_interval: Duration,
+    /// `http_request_duration_seconds` buckets, in seconds (`0.01,0.05,0.1` in the env var).
+    pub http_duration_buckets: Vec<f64>,
+    /// Distinct (method, path, status) label sets before new ones are dropped.
+    pub metrics_max_series: usize,
+    /// Bearer token for the admin/simulate routes; wins over `admin_token_file`.
+    #[serde(serialize_with = "ser_secret")]
+    pub admin_token: Option<String>,
+    pub admin_token_file: Option<String>,
+    /// PEM files for HTTPS on the admin listener; with `admin_tls_client_ca`, clients need a certificate (mTLS).
+    pub admin_tls_cert: Option<String>,
+    pub admin_tls_key: Option<String>,
+    pub admin_tls_client_ca: Option<String>,
+    /// `off` or `aimd`; the other `concurrency_limit_*` settings tune AIMD, see `limiter::LimiterConfig`.
+    pub concurrency_limit: String,
+    pub concurrency_limit_initial: usize,
+    pub concurrency_limit_min: usize,
+    pub concurrency_limit_max: usize,
+    #[serde(deserialize_with = "de_duration", serialize_with = "ser_duration")]
+    pub concurrency_limit_latency_target: Duration,
+    pub concurrency_limit_backoff: f64,
+    #[serde(deserialize_with = "de_duration", serialize_with = "ser_duration")]
+    pub concurrency_limit_queue_timeout: Duration,
+    pub concurrency_limit_max_queue: usize,
+    /// Tracing: no spans at all when set.
+    pub otel_sdk_disabled: bool,
+    /// `app-health-demo` when unset.
+    pub otel_service_name: Option<String>,
+    /// `key=value,…` added to the trace resource.
+    pub otel_resource_attributes: Option<String>,
+    /// OTLP collector; spans are only exported when one of the endpoints is set.
+    /// The traces endpoint is used as is, the generic one gets `/v1/traces` appended over HTTP.
+    pub otel_exporter_otlp_endpoint: Option<String>,
+    pub otel_exporter_otlp_traces_endpoint: Option<String>,
+    /// `grpc` or `http/protobuf` (`OTEL_EXPORTER_OTLP_TRACES_PROTOCOL` overrides it too).
+    pub otel_exporter_otlp_protocol: String,
+    /// `always_on`, `always_off`, `traceidratio` or their `parentbased_` variants; SDK default when unset.
+    pub otel_traces_sampler: Option<String>,
+    /// Ratio for the `traceidratio` samplers.
+    pub otel_traces_sampler_arg: Option<f64>,
+    /// Objectives behind `/slo`; `SLO_AVAILABILITY` etc. override the keys of this section.
+    pub slo: SloConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let limiter = LimiterConfig::default();
+        Self {
+            port: 8080,
+            admin_port: None,
+            grpc_port: None,
+            startup_delay_ms: 3000,
+            ready_after_ms: 5000,
+            pre_stop_delay: Duration::from_secs(2),
+            drain_timeout: Duration::from_secs(15),
+            health_checks: None,
+            health_check_interval_ms: 5000,
+            health_check_timeout_ms: 1000,
+            ready_max_leak_mb: None,
+            resource_metrics_interval_ms: 5000,
+            scenario_file: None,
+            scenario_autostart: false,
+            log_level: None,
+            faults: Vec::new(),
+            config_reload_interval: Duration::from_secs(5),
+            http_duration_buckets: prometheus::DEFAULT_BUCKETS.to_vec(),
+            metrics_max_series: 500,
+            admin_token: None,
+            admin_token_file: None,
+            admin_tls_cert: None,
+            admin_tls_key: None,
+            admin_tls_client_ca: None,
+            concurrency_limit: "off".to_string(),
+            concurrency_limit_initial: limiter.initial,
+            concurrency_limit_min: limiter.min,
+            concurrency_limit_max: limiter.max,
+            concurrency_limit_latency_target: limiter.latency_target,
+            concurrency_limit_backoff: limiter.backoff,
+            concurrency_limit_queue_timeout: limiter.queue_timeout,
+            concurrency_limit_max_queue: limiter.max_queue,
+            otel_sdk_disabled: false,
+            otel_service_name: None,
+            otel_resource_attributes: None,
+            otel_exporter_otlp_endpoint: None,
+            otel_exporter_otlp_traces_endpoint: None,
+            otel_exporter_otlp_protocol: "grpc".to_string(),
+            otel_traces_sampler: None,
+            otel_traces_sampler_arg: None,
+            slo: SloConfig::default(),
+        }
+    }
+}
+
+pub(crate) fn ser_duration<S: Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&format!("{}ms", d.as_millis()))
+}
+
+// keeps the admin token out of /admin/config
+fn ser_secret<S: Serializer>(v: &Option<String>, s: S) -> Result<S::Ok, S::Error> {
+    match v {
+        Some(_) => s.serialize_some("<redacted>"),
+        None => s.serialize_none(),
+    }
+}
+
+/// Settings that a file reload applies without a restart.
+pub const RELOADABLE: [&str; 4] = ["log_level", "pre_stop_delay", "drain_timeout", "faults"];
+
+impl Config {
+    /// Defaults, then `CONFIG_FILE` (if set), then environment variables; validated.
+    pub fn load() -> anyhow::Result<Self> {
+        let file = std::env::var("CONFIG_FILE").ok();
+        let contents = match &file {
+            Some(path) => Some(std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("cannot read CONFIG_FILE={path:?}: {e}"))?),
+            None => None,
+        };
+        Self::from_sources(contents.as_deref(), |name| std::env::var(name).ok()).map_err(|errors| {
+            let source = file.map(|f| format!(" (CONFIG_FILE={f:?})")).unwrap_or_default();
+            anyhow::anyhow!("invalid configuration{source}:\n  - {}", errors.join("\n  - "))
+        })
+    }
+
+    /// Builds a config from file contents (YAML or JSON) plus `env` overrides, collecting every problem.
+    pub fn from_sources(file: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Result<Self, Vec<String>> {
+        let mut cfg = match file {
+            Some(text) if !text.trim().is_empty() => serde_yaml::from_str(text).map_err(|e| vec![format!("config file: {e}")])?,
+            _ => Config::default(),
+        };
+        let mut errors = Vec::new();
+        cfg.apply_env(&env, &mut errors);
+        cfg.validate(&mut errors);
+        if errors.is_empty() { Ok(cfg) } else { Err(errors) }
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>, errors: &mut Vec<String>) {
+        fn parse<T: FromStr>(env: &impl Fn(&str) -> Option<String>, name: &str, what: &str, errors: &mut Vec<String>) -> Option<T> {
+            let raw = env(name)?;
+            match raw.trim().parse() {
+                Ok(v) => Some(v),
+                Err(_) => { errors.push(format!("{name}={raw:?}: expected {what}")); None }
+            }
+        }
+        fn duration(env: &impl Fn(&str) -> Option<String>, name: &str, errors: &mut Vec<String>) -> Option<Duration> {
+            let raw = env(name)?;
+            let d = crate::scenario::parse_duration(&raw);
+            if d.is_none() {
+                errors.push(format!("{name}={raw:?}: expected a duration such as 5s or 500ms"));
+            }
+            d
+        }
+        let int = "an integer";
+        if let Some(v) = parse(env, "PORT", "a port number", errors) { self.port = v; }
+        if let Some(v) = parse(env, "ADMIN_PORT", "a port number", errors) { self.admin_port = Some(v); }
+        if let Some(v) = parse(env, "GRPC_PORT", "a port number", errors) { self.grpc_port = Some(v); }
+        if let Some(v) = parse(env, "STARTUP_DELAY_MS", int, errors) { self.startup_delay_ms = v; }
+        if let Some(v) = parse(env, "READY_AFTER_MS", int, errors) { self.ready_after_ms = v; }
+        if let Some(v) = duration(env, "PRE_STOP_DELAY", errors) { self.pre_stop_delay = v; }
+        if let Some(v) = duration(env, "DRAIN_TIMEOUT", errors) { self.drain_timeout = v; }
+        if let Some(v) = env("HEALTH_CHECKS") { self.health_checks = Some(v); }
+        if let Some(v) = parse(env, "HEALTH_CHECK_INTERVAL_MS", int, errors) { self.health_check_interval_ms = v; }
+        if let Some(v) = parse(env, "HEALTH_CHECK_TIMEOUT_MS", int, errors) { self.health_check_timeout_ms = v; }
+        if let Some(v) = parse(env, "READY_MAX_LEAK_MB", int, errors) { self.ready_max_leak_mb = Some(v); }
+        if let Some(v) = parse(env, "RESOURCE_METRICS_INTERVAL_MS", int, errors) { self.resource_metrics_interval_ms = v; }
+        if let Some(v) = env("SCENARIO_FILE") { self.scenario_file = Some(v); }
+        if let Some(v) = env("SCENARIO_AUTOSTART") {
+            match v.as_str() {
+                "true" | "1" => self.scenario_autostart = true,
+                "false" | "0" => self.scenario_autostart = false,
+                _ => errors.push(format!("SCENARIO_AUTOSTART={v:?}: expected true or false")),
+            }
+        }
+        if let Some(v) = env("LOG_LEVEL") { self.log_level = Some(v); }
+        if let Some(v) = duration(env, "CONFIG_RELOAD_INTERVAL", errors) { self.config_reload_interval = v; }
+        if let Some(raw) = env("HTTP_DURATION_BUCKETS") {
+            match raw.split(',').map(|b| b.trim().parse()).collect() {
+                Ok(buckets) => self.http_duration_buckets = buckets,
+                Err(_) => errors.push(format!("HTTP_DURATION_BUCKETS={raw:?}: expected comma-separated seconds")),
+            }
+        }
+        if let Some(v) = parse(env, "METRICS_MAX_SERIES", int, errors) { self.metrics_max_series = v; }
+        if let Some(v) = env("ADMIN_TOKEN") { self.admin_token = Some(v); }
+        if let Some(v) = env("ADMIN_TOKEN_FILE") { self.admin_token_file = Some(v); }
+        if let Some(v) = env("ADMIN_TLS_CERT") { self.admin_tls_cert = Some(v); }
+        if let Some(v) = env("ADMIN_TLS_KEY") { self.admin_tls_key = Some(v); }
+        if let Some(v) = env("ADMIN_TLS_CLIENT_CA") { self.admin_tls_client_ca = Some(v); }
+        if let Some(v) = env("CONCURRENCY_LIMIT") { self.concurrency_limit = v; }
+        if let Some(v) = parse(env, "CONCURRENCY_LIMIT_INITIAL", int, errors) { self.concurrency_limit_initial = v; }
+        if let Some(v) = parse(env, "CONCURRENCY_LIMIT_MIN", int, errors) { self.concurrency_limit_min = v; }
+        if let Some(v) = parse(env, "CONCURRENCY_LIMIT_MAX", int, errors) { self.concurrency_limit_max = v; }
+        if let Some(v) = duration(env, "CONCURRENCY_LIMIT_LATENCY_TARGET", errors) { self.concurrency_limit_latency_target = v; }
+        if let Some(v) = parse(env, "CONCURRENCY_LIMIT_BACKOFF", "a number", errors) { self.concurrency_limit_backoff = v; }
+        if let Some(v) = duration(env, "CONCURRENCY_LIMIT_QUEUE_TIMEOUT", errors) { self.concurrency_limit_queue_timeout = v; }
+        if let Some(v) = parse(env, "CONCURRENCY_LIMIT_MAX_QUEUE", int, errors) { self.concurrency_limit_max_queue = v; }
+        if let Some(v) = env("OTEL_SDK_DISABLED") { self.otel_sdk_disabled = v.eq_ignore_ascii_case("true") || v == "1"; }
+        if let Some(v) = env("OTEL_SERVICE_NAME") { self.otel_service_name = Some(v); }
+        if let Some(v) = env("OTEL_RESOURCE_ATTRIBUTES") { self.otel_resource_attributes = Some(v); }
+        if let Some(v) = env("OTEL_EXPORTER_OTLP_ENDPOINT") { self.otel_exporter_otlp_endpoint = Some(v); }
+        if let Some(v) = env("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") { self.otel_exporter_otlp_traces_endpoint = Some(v); }
+        if let Some(v) = env("OTEL_EXPORTER_OTLP_PROTOCOL") { self.otel_exporter_otlp_protocol = v; }
+        if let Some(v) = env("OTEL_EXPORTER_OTLP_TRACES_PROTOCOL") { self.otel_exporter_otlp_protocol = v; }
+        if let Some(v) = env("OTEL_TRACES_SAMPLER") { self.otel_traces_sampler = Some(v); }
+        if let Some(v) = parse(env, "OTEL_TRACES_SAMPLER_ARG", "a number", errors) { self.otel_traces_sampler_arg = Some(v); }
+        if let Some(v) = parse(env, "SLO_AVAILABILITY", "a ratio", errors) { self.slo.availability = v; }
+        if let Some(v) = parse(env, "SLO_LATENCY_THRESHOLD", "seconds", errors) { self.slo.latency_threshold = v; }
+        if let Some(v) = parse(env, "SLO_LATENCY_TARGET", "a ratio", errors) { self.slo.latency_target = v; }
+        if let Some(v) = duration(env, "SLO_SAMPLE_INTERVAL", errors) { self.slo.sample_interval = v; }
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        if self.admin_port.is_some_and(|p| p == self.port && p != 0) {
+            errors.push(format!("admin_port must differ from port ({})", self.port));
+        }
+        if let Some(grpc) = self.grpc_port.filter(|p| *p != 0) {
+            if grpc == self.port || self.admin_port == Some(grpc) {
+                errors.push(format!("grpc_port ({grpc}) must differ from port and admin_port"));
+            }
+        }
+        if self.health_check_interval_ms == 0 {
+            errors.push("health_check_interval_ms must be > 0".to_string());
+        }
+        if self.health_check_timeout_ms == 0 || self.health_check_timeout_ms > self.health_check_interval_ms {
+            errors.push(format!(
+                "health_check_timeout_ms ({}) must be > 0 and <= health_check_interval_ms ({})",
+                self.health_check_timeout_ms, self.health_check_interval_ms
+            ));
+        }
+        if self.resource_metrics_interval_ms == 0 {
+            errors.push("resource_metrics_interval_ms must be > 0".to_string());
+        }
+        if let Some(level) = &self.log_level {
+            if let Err(e) = tracing_subscriber::EnvFilter::try_new(level) {
+                errors.push(format!("log_level {level:?}: {e}"));
+            }
+        }
+        if self.scenario_autostart && self.scenario_file.is_none() {
+            errors.push("scenario_autostart needs scenario_file".to_string());
+        }
+        for (i, rule) in self.faults.iter().enumerate() {
+            if let Err(e) = crate::faults::validate(rule) {
+                errors.push(format!("faults[{i}]: {e}"));
+            }
+        }
+        if self.config_reload_interval.is_zero() {
+            errors.push("config_reload_interval must be > 0".to_string());
+        }
+        let buckets = &self.http_duration_buckets;
+        if buckets.is_empty() || buckets.iter().any(|b| !b.is_finite()) || buckets.windows(2).any(|w| w[0] >= w[1]) {
+            errors.push(format!("http_duration_buckets {buckets:?}: expected increasing, finite seconds"));
+        }
+        if self.metrics_max_series == 0 {
+            errors.push("metrics_max_series must be > 0".to_string());
+        }
+        if self.admin_token.as_deref() == Some("") {
+            errors.push("admin_token is empty".to_string());
+        }
+        match (&self.admin_tls_cert, &self.admin_tls_key) {
+            (Some(_), Some(_)) if self.admin_port.is_none() => {
+                errors.push("admin_tls_* needs admin_port: TLS is only served on the admin listener".to_string());
+            }
+            (Some(_), None) | (None, Some(_)) => errors.push("admin_tls_cert and admin_tls_key must be set together".to_string()),
+            (None, None) if self.admin_tls_client_ca.is_some() => {
+                errors.push("admin_tls_client_ca needs admin_tls_cert and admin_tls_key".to_string());
+            }
+            _ => {}
+        }
+        match self.concurrency_limit.as_str() {
+            "off" => {}
+            "aimd" => {
+                let (min, initial, max) = (self.concurrency_limit_min, self.concurrency_limit_initial, self.concurrency_limit_max);
+                if min == 0 || min > initial || initial > max {
+                    errors.push(format!(
+                        "concurrency limits must satisfy 0 < min ({min}) <= initial ({initial}) <= max ({max})"
+                    ));
+                }
+                if !(self.concurrency_limit_backoff > 0.0 && self.concurrency_limit_backoff < 1.0) {
+                    errors.push(format!("concurrency_limit_backoff ({}) must be > 0 and < 1", self.concurrency_limit_backoff));
+                }
+            }
+            other => errors.push(format!("concurrency_limit {other:?}: expected aimd or off")),
+        }
+        if let Some(attrs) = &self.otel_resource_attributes {
+            if attrs.split(',').any(|kv| !kv.contains('=')) {
+                errors.push(format!("otel_resource_attributes {attrs:?}: expected key=value,…"));
+            }
+        }
+        if !matches!(self.otel_exporter_otlp_protocol.as_str(), "grpc" | "http/protobuf") {
+            errors.push(format!("otel_exporter_otlp_protocol {:?}: expected grpc or http/protobuf", self.otel_exporter_otlp_protocol));
+        }
+        if let Some(sampler) = &self.otel_traces_sampler {
+            if crate::telemetry::sampler(sampler, self.otel_traces_sampler_arg).is_none() {
+                errors.push(format!("otel_traces_sampler {sampler:?}: unknown sampler"));
+            }
+        }
+        if self.otel_traces_sampler_arg.is_some_and(|r| !(0.0..=1.0).contains(&r)) {
+            errors.push("otel_traces_sampler_arg must be between 0 and 1".to_string());
+        }
+        self.slo.validate(&self.http_duration_buckets, errors);
+    }
+
+    /// Names of non-reloadable settings that differ from `other`.
+    fn restart_required(&self, other: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! check { ($($f:ident),*) => { $( if self.$f != other.$f { changed.push(stringify!($f)); } )* } }
+        check!(port, admin_port, grpc_port, startup_delay_ms, ready_after_ms, health_checks, health_check_interval_ms,
+            health_check_timeout_ms, ready_max_leak_mb, resource_metrics_interval_ms, scenario_file, scenario_autostart,
+            config_reload_interval, http_duration_buckets, metrics_max_series, admin_token, admin_token_file, admin_tls_cert,
+            admin_tls_key, admin_tls_client_ca, concurrency_limit, concurrency_limit_initial, concurrency_limit_min,
+            concurrency_limit_max, concurrency_limit_latency_target, concurrency_limit_backoff, concurrency_limit_queue_timeout,
+            concurrency_limit_max_queue, otel_sdk_disabled, otel_service_name, otel_resource_attributes,
+            otel_exporter_otlp_endpoint, otel_exporter_otlp_traces_endpoint, otel_exporter_otlp_protocol,
+            otel_traces_sampler, otel_traces_sampler_arg, slo);
+        changed
+    }
+}
+
+// ---------- Live config ----------
+/// The effective configuration, updated in place by file reloads.
+#[derive(Default)]
+pub struct ConfigStore {
+    current: RwLock<Config>,
+    file: Option<PathBuf>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl ConfigStore {
+    pub fn new(config: Config, file: Option<PathBuf>) -> Self {
+        Self { current: RwLock::new(config), file, last_error: Mutex::new(None) }
+    }
+
+    pub fn get(&self) -> Config {
+        self.current.read().clone()
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+}
+
+/// Applies the file-owned fault rules and the log level of the current config.
+pub fn apply_runtime(state: &AppState) -> anyhow::Result<()> {
+    let cfg = state.config.get();
+    state.faults.replace_from_config(cfg.faults).map_err(anyhow::Error::msg)?;
+    crate::telemetry::set_log_level(cfg.log_level.as_deref()).map_err(anyhow::Error::msg)?;
+    Ok(())
+}
+
+/// Re-reads the config file; on success applies the reloadable settings and returns their names if any changed.
+pub fn reload(state: &AppState) -> Result<Vec<&'static str>, String> {
+    let Some(path) = state.config.file() else { return Err("no CONFIG_FILE to reload".to_string()) };
+    let result = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))
+        .and_then(|text| Config::from_sources(Some(&text), |name| std::env::var(name).ok()).map_err(|e| e.join("; ")))
+        .and_then(|new| apply(state, new));
+    match &result {
+        Ok(_) => {
+            RELOADS.with_label_values(&["success"]).inc();
+            *state.config.last_error.lock() = None;
+        }
+        Err(e) => {
+            RELOADS.with_label_values(&["error"]).inc();
+            error!("Config: reload of {} rejected, keeping the previous settings: {}", path.display(), e);
+            *state.config.last_error.lock() = Some(e.clone());
+        }
+    }
+    result
+}
+
+fn apply(state: &AppState, new: Config) -> Result<Vec<&'static str>, String> {
+    let old = state.config.get();
+    let pending = old.restart_required(&new);
+    if !pending.is_empty() {
+        warn!("Config: {} changed; restart the pod to apply", pending.join(", "));
+    }
+    let mut changed = Vec::new();
+    if new.faults != old.faults {
+        state.faults.replace_from_config(new.faults.clone())?;
+        changed.push("faults");
+    }
+    if new.log_level != old.log_level {
+        // dropped from the file: back to RUST_LOG, as at startup
+        crate::telemetry::set_log_level(new.log_level.as_deref())?;
+        changed.push("log_level");
+    }
+    if new.pre_stop_delay != old.pre_stop_delay { changed.push("pre_stop_delay"); }
+    if new.drain_timeout != old.drain_timeout { changed.push("drain_timeout"); }
+    let mut cur = state.config.current.write();
+    cur.faults = new.faults;
+    cur.log_level = new.log_level;
+    cur.pre_stop_delay = new.pre_stop_delay;
+    cur.drain_timeout = new.drain_timeout;
+    if !changed.is_empty() {
+        info!("Config: reloaded {}", changed.join(", "));
+    }
+    Ok(changed)
+}
+
+/// Polls the config file every `interval` and reloads it when its contents change.
+/// Polling (rather than inotify) also catches the symlink swap kubelet does for ConfigMap volumes.
+pub fn spawn_watcher(state: AppState, interval: Duration) {
+    let Some(path) = state.config.file().map(Path::to_path_buf) else { return };
+    tokio::spawn(async move {
+        let mut last = std::fs::read(&path).ok();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let now = tokio::fs::read(&path).await.ok();
+            if now.is_some() && now != last {
+                last = now;
+                let _ = reload(&state);
+            }
+        }
+    });
+}
+
+// ---------- Handlers ----------
+pub async fn reload_now(State(state): State<AppState>) -> impl IntoResponse {
+    match reload(&state) {
+        Ok(changed) => (StatusCode::OK, Json(serde_json::json!({ "reloaded": changed }))).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+/// Effective configuration, which settings reload live and the outcome of the last reload.
+pub async fn show(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "config": state.config.get(),
+        "file": state.config.file(),
+        "reloadable": RELOADABLE,
+        "last_reload_error": *state.config.last_error.lock(),
+    }))
+}
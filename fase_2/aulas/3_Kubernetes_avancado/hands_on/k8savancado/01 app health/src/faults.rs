@@ -0,0 +1,295 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use prometheus::{register_gauge_vec, register_int_counter_vec, GaugeVec, IntCounterVec};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{atomic::{AtomicU64, Ordering}, Arc},
+    time::Duration,
+};
+use tower::Layer;
+use tracing::{info, warn};
+
+use crate::AppState;
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref FAULT_RULES: GaugeVec = register_gauge_vec!(
+        "fault_injection_rule_probability",
+        "Active fault injection rules (value = probability)",
+        &["id", "path", "fault"]
+    ).unwrap();
+
+    static ref FAULTS_INJECTED: IntCounterVec = register_int_counter_vec!(
+        "fault_injections_total",
+        "Number of requests that had a fault injected",
+        &["fault"]
+    ).unwrap();
+}
+
+// ---------- Rules ----------
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "fault", rename_all = "snake_case")]
+pub enum Fault {
+    /// Delay the request before it reaches the handler.
+    Latency { ms: u64 },
+    /// Answer with `status` without calling the handler.
+    Error { #[serde(default = "default_status")] status: u16 },
+    /// Drop the connection before a complete response is written.
+    Reset,
+    /// Send only the first `bytes` of the real body, then drop the connection.
+    Truncate { #[serde(default)] bytes: usize },
+}
+
+fn default_status() -> u16 { 503 }
+
+impl Fault {
+    fn name(&self) -> &'static str {
+        match self {
+            Fault::Latency { .. } => "latency",
+            Fault::Error { .. } => "error",
+            Fault::Reset => "reset",
+            Fault::Truncate { .. } => "truncate",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct FaultRule {
+    #[serde(default)]
+    pub id: u64,
+    /// Path pattern; `*` matches any sequence of characters (e.g. `/simulate/*`).
+    pub path: String,
+    #[serde(default = "default_probability")]
+    pub probability: f64,
+    #[serde(flatten)]
+    pub fault: Fault,
+}
+
+fn default_probability() -> f64 { 1.0 }
+
+impl FaultRule {
+    fn labels(&self) -> [String; 3] {
+        [self.id.to_string(), self.path.clone(), self.fault.name().to_string()]
+    }
+}
+
+pub fn validate(rule: &FaultRule) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&rule.probability) {
+        return Err(format!("probability must be between 0 and 1, got {}", rule.probability));
+    }
+    if !rule.path.starts_with('/') && !rule.path.starts_with('*') {
+        return Err(format!("path pattern must start with '/' or '*', got '{}'", rule.path));
+    }
+    if let Fault::Error { status } = rule.fault {
+        if StatusCode::from_u16(status).is_err() {
+            return Err(format!("invalid status code {status}"));
+        }
+    }
+    Ok(())
+}
+
+/// Runtime-configurable set of fault rules, shared by the layer and the admin API.
+#[derive(Default)]
+pub struct FaultRegistry {
+    rules: RwLock<Vec<FaultRule>>,
+    next_id: AtomicU64,
+    // ids of the rules that came from the config file
+    from_config: parking_lot::Mutex<Vec<u64>>,
+}
+
+impl FaultRegistry {
+    pub fn add(&self, mut rule: FaultRule) -> Result<FaultRule, String> {
+        validate(&rule)?;
+        rule.id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let l = rule.labels();
+        FAULT_RULES.with_label_values(&[&l[0], &l[1], &l[2]]).set(rule.probability);
+        info!("Faults: added rule {} ({} on {} p={})", rule.id, rule.fault.name(), rule.path, rule.probability);
+        self.rules.write().push(rule.clone());
+        Ok(rule)
+    }
+
+    pub fn remove(&self, id: u64) -> Option<FaultRule> {
+        let mut rules = self.rules.write();
+        let idx = rules.iter().position(|r| r.id == id)?;
+        let rule = rules.remove(idx);
+        let l = rule.labels();
+        let _ = FAULT_RULES.remove_label_values(&[&l[0], &l[1], &l[2]]);
+        info!("Faults: removed rule {}", id);
+        Some(rule)
+    }
+
+    /// Swaps the rules owned by the config file for `rules`; admin-added rules are kept.
+    pub fn replace_from_config(&self, rules: Vec<FaultRule>) -> Result<(), String> {
+        rules.iter().try_for_each(validate)?;
+        let mut owned = self.from_config.lock();
+        for id in owned.drain(..) {
+            self.remove(id);
+        }
+        for rule in rules {
+            owned.push(self.add(rule)?.id);
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self) -> usize {
+        let ids: Vec<u64> = self.rules.read().iter().map(|r| r.id).collect();
+        ids.iter().filter(|id| self.remove(**id).is_some()).count()
+    }
+
+    pub fn list(&self) -> Vec<FaultRule> {
+        self.rules.read().clone()
+    }
+
+    /// Rolls the dice for every rule matching `path`; the first one that fires wins.
+    fn pick(&self, path: &str) -> Option<Fault> {
+        // never lock ourselves out of the API that removes faults
+        if path.starts_with("/admin/") {
+            return None;
+        }
+        self.rules.read().iter()
+            .filter(|r| glob_match(&r.path, path))
+            .find(|r| rand::random::<f64>() < r.probability)
+            .map(|r| r.fault.clone())
+    }
+}
+
+/// Minimal glob: `*` matches any (possibly empty) sequence of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for mid in &parts[1..parts.len() - 1] {
+        match rest.find(mid) {
+            Some(i) => rest = &rest[i + mid.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+// A body that yields `prefix` and then fails, which makes hyper abort the connection.
+fn broken_body(prefix: axum::body::Bytes) -> Body {
+    use futures_util::StreamExt;
+    let partial = !prefix.is_empty();
+    let head = futures_util::stream::iter(partial.then_some(Ok(prefix)));
+    let fail = futures_util::stream::once(async move {
+        // let hyper flush the partial body before the connection drops;
+        // without one, failing right away means not even the headers go out
+        if partial {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Err::<axum::body::Bytes, _>(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "injected fault"))
+    });
+    Body::from_stream(head.chain(fail))
+}
+
+// ---------- Middleware ----------
+#[derive(Clone)]
+pub struct FaultLayer {
+    faults: Arc<FaultRegistry>,
+}
+
+impl FaultLayer {
+    pub fn new(faults: Arc<FaultRegistry>) -> Self { Self { faults } }
+}
+
+impl<S> Layer<S> for FaultLayer {
+    type Service = FaultService<S>;
+    fn layer(&self, inner: S) -> Self::Service { FaultService { inner, faults: self.faults.clone() } }
+}
+
+#[derive(Clone)]
+pub struct FaultService<S> {
+    inner: S,
+    faults: Arc<FaultRegistry>,
+}
+
+impl<S, ReqBody> tower::Service<axum::http::Request<ReqBody>> for FaultService<S>
+where
+    S: tower::Service<axum::http::Request<ReqBody>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        tower::Service::<axum::http::Request<ReqBody>>::poll_ready(&mut self.inner, cx)
+    }
+
+    fn call(&mut self, req: axum::http::Request<ReqBody>) -> Self::Future {
+        let mut svc = self.inner.clone();
+        let fault = self.faults.pick(req.uri().path());
+        Box::pin(async move {
+            let Some(fault) = fault else { return svc.call(req).await };
+            FAULTS_INJECTED.with_label_values(&[fault.name()]).inc();
+            info!(fault = fault.name(), "fault injected");
+            match fault {
+                Fault::Latency { ms } => {
+                    tokio::time::sleep(Duration::from_millis(ms)).await;
+                    svc.call(req).await
+                }
+                Fault::Error { status } => {
+                    let code = StatusCode::from_u16(status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+                    Ok((code, "injected fault").into_response())
+                }
+                Fault::Reset => Ok(Response::new(broken_body(axum::body::Bytes::new()))),
+                Fault::Truncate { bytes } => {
+                    let res = svc.call(req).await?;
+                    let (mut parts, body) = res.into_parts();
+                    let full = match axum::body::to_bytes(body, usize::MAX).await {
+                        Ok(b) => b,
+                        Err(e) => {
+                            warn!("Faults: could not buffer body to truncate: {}", e);
+                            return Ok(Response::from_parts(parts, broken_body(axum::body::Bytes::new())));
+                        }
+                    };
+                    // Advertise the full length so clients notice the short read
+                    parts.headers.insert(header::CONTENT_LENGTH, full.len().into());
+                    let cut = full.slice(..bytes.min(full.len().saturating_sub(1)));
+                    Ok(Response::from_parts(parts, broken_body(cut)))
+                }
+            }
+        })
+    }
+}
+
+// ---------- Handlers ----------
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.faults.list())
+}
+
+pub async fn add(State(state): State<AppState>, Json(rule): Json<FaultRule>) -> impl IntoResponse {
+    match state.faults.add(rule) {
+        Ok(rule) => (StatusCode::CREATED, Json(rule)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+pub async fn remove(State(state): State<AppState>, Path(id): Path<u64>) -> impl IntoResponse {
+    match state.faults.remove(id) {
+        Some(rule) => (StatusCode::OK, Json(rule)).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("no fault rule {id}")).into_response(),
+    }
+}
+
+pub async fn clear(State(state): State<AppState>) -> impl IntoResponse {
+    format!("removed {} fault rules", state.faults.clear())
+}
@@ -0,0 +1,84 @@
+use std::{sync::atomic::Ordering, time::Duration};
+use tokio::net::TcpListener;
+use tonic::transport::server::TcpIncoming;
+use tonic_health::{server::HealthReporter, ServingStatus};
+use tracing::info;
+
+use crate::{shutdown::Phase, AppState};
+
+// ---------- gRPC health ----------
+/// Which flag a `grpc.health.v1` service name reports.
+#[derive(Clone, Copy, Debug)]
+pub enum Probe {
+    Live,
+    Ready,
+    Startup,
+}
+
+/// Service names for `grpc:` probes (`service:` in the pod spec). Per the gRPC spec the
+/// empty name is the overall server status, which follows readiness.
+pub const SERVICES: [(&str, Probe); 4] = [
+    ("", Probe::Ready),
+    ("liveness", Probe::Live),
+    ("readiness", Probe::Ready),
+    ("startup", Probe::Startup),
+];
+
+/// How often the flags are copied into the health service; bounds how late a Watch sees a flip.
+const SYNC_INTERVAL: Duration = Duration::from_millis(100);
+
+fn status(state: &AppState, probe: Probe) -> ServingStatus {
+    let up = match probe {
+        Probe::Live => state.live.load(Ordering::SeqCst),
+        Probe::Ready => state.is_ready(),
+        Probe::Startup => state.started.load(Ordering::SeqCst),
+    };
+    if up { ServingStatus::Serving } else { ServingStatus::NotServing }
+}
+
+type Published = [Option<ServingStatus>; SERVICES.len()];
+
+async fn publish(state: &AppState, reporter: &HealthReporter, last: &mut Published) {
+    for ((name, probe), last) in SERVICES.iter().zip(last.iter_mut()) {
+        let now = status(state, *probe);
+        // every send wakes the watchers, so only send changes
+        if *last != Some(now) {
+            reporter.set_service_status(name, now).await;
+            *last = Some(now);
+        }
+    }
+}
+
+/// Keeps the reporter in step with the flags until draining, then unregisters every service
+/// so open Watch streams end and the server can finish its graceful shutdown.
+async fn sync(state: AppState, mut reporter: HealthReporter, mut last: Published) {
+    let mut ticker = tokio::time::interval(SYNC_INTERVAL);
+    let draining = state.shutdown.reached(Phase::Draining);
+    tokio::pin!(draining);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => publish(&state, &reporter, &mut last).await,
+            _ = &mut draining => break,
+        }
+    }
+    for (name, _) in SERVICES {
+        reporter.set_service_status(name, ServingStatus::NotServing).await;
+        reporter.clear_service_status(name).await;
+    }
+}
+
+/// Serves `grpc.health.v1.Health` (Check and Watch) on `listener` until the shutdown reaches draining.
+pub async fn serve(state: AppState, listener: TcpListener) -> std::io::Result<()> {
+    let (reporter, service) = tonic_health::server::health_reporter();
+    // register every name before the first connection, so none is briefly NOT_FOUND
+    let mut last = [None; SERVICES.len()];
+    publish(&state, &reporter, &mut last).await;
+    info!("gRPC health listening on {} (services: {:?})", listener.local_addr()?, SERVICES.map(|(n, _)| n));
+    tokio::spawn(sync(state.clone(), reporter, last));
+    let drain = state.shutdown.clone();
+    tonic::transport::Server::builder()
+        .add_service(service)
+        .serve_with_incoming_shutdown(TcpIncoming::from(listener), async move { drain.reached(Phase::Draining).await })
+        .await
+        .map_err(std::io::Error::other)
+}
@@ -0,0 +1,289 @@
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use prometheus::{register_histogram_vec, register_int_gauge_vec, HistogramVec, IntGaugeVec};
+use serde::Serialize;
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{info, warn};
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref CHECK_UP: IntGaugeVec = register_int_gauge_vec!(
+        "health_check_up",
+        "Result of the last dependency health check (1 = up, 0 = down)",
+        &["check", "kind"]
+    ).unwrap();
+
+    static ref CHECK_DURATION: HistogramVec = register_histogram_vec!(
+        "health_check_duration_seconds",
+        "Dependency health check latencies in seconds",
+        &["check", "kind"]
+    ).unwrap();
+}
+
+pub type CheckFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// A dependency the readiness probe depends on.
+pub trait HealthCheck: Send + Sync {
+    fn name(&self) -> &str;
+    fn kind(&self) -> &'static str;
+    fn check(&self) -> CheckFuture<'_>;
+}
+
+// ---------- Built-in checks ----------
+/// Passes when a TCP connection to `addr` can be established.
+pub struct TcpCheck {
+    name: String,
+    addr: String,
+}
+
+impl TcpCheck {
+    pub fn new(name: impl Into<String>, addr: impl Into<String>) -> Self {
+        Self { name: name.into(), addr: addr.into() }
+    }
+}
+
+impl HealthCheck for TcpCheck {
+    fn name(&self) -> &str { &self.name }
+    fn kind(&self) -> &'static str { "tcp" }
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            tokio::net::TcpStream::connect(&self.addr).await
+                .map(|_| ())
+                .map_err(|e| format!("connect {}: {e}", self.addr))
+        })
+    }
+}
+
+/// Passes when `GET url` answers with a 2xx/3xx status. Plain `http://` only.
+pub struct HttpCheck {
+    name: String,
+    url: String,
+}
+
+impl HttpCheck {
+    pub fn new(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self { name: name.into(), url: url.into() }
+    }
+}
+
+impl HealthCheck for HttpCheck {
+    fn name(&self) -> &str { &self.name }
+    fn kind(&self) -> &'static str { "http" }
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let rest = self.url.strip_prefix("http://")
+                .ok_or_else(|| format!("unsupported url {} (only http:// is supported)", self.url))?;
+            let (authority, path) = match rest.find('/') {
+                Some(i) => (&rest[..i], &rest[i..]),
+                None => (rest, "/"),
+            };
+            // an IPv6 literal (`[::1]`) has colons of its own; only one after the `]` is a port
+            let has_port = match authority.rfind(']') {
+                Some(i) => authority[i + 1..].starts_with(':'),
+                None => authority.contains(':'),
+            };
+            let addr = if has_port { authority.to_string() } else { format!("{authority}:80") };
+            let mut stream = tokio::net::TcpStream::connect(&addr).await
+                .map_err(|e| format!("connect {addr}: {e}"))?;
+            let request = format!("GET {path} HTTP/1.1\r\nHost: {authority}\r\nConnection: close\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.map_err(|e| format!("write: {e}"))?;
+
+            // Only the status line matters; read until we have it.
+            let mut buf = Vec::with_capacity(256);
+            let mut chunk = [0u8; 256];
+            while !buf.windows(2).any(|w| w == b"\r\n") {
+                let n = stream.read(&mut chunk).await.map_err(|e| format!("read: {e}"))?;
+                if n == 0 { break; }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+            let line = String::from_utf8_lossy(&buf);
+            let status: u16 = line.split_whitespace().nth(1)
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| "malformed HTTP response".to_string())?;
+            if (200..400).contains(&status) { Ok(()) } else { Err(format!("GET {} returned {status}", self.url)) }
+        })
+    }
+}
+
+/// Passes when `path` exists (e.g. a mounted secret or a lock file).
+pub struct FileExistsCheck {
+    name: String,
+    path: PathBuf,
+}
+
+impl FileExistsCheck {
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self { name: name.into(), path: path.into() }
+    }
+}
+
+impl HealthCheck for FileExistsCheck {
+    fn name(&self) -> &str { &self.name }
+    fn kind(&self) -> &'static str { "file" }
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            match tokio::fs::try_exists(&self.path).await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(format!("{} does not exist", self.path.display())),
+                Err(e) => Err(format!("{}: {e}", self.path.display())),
+            }
+        })
+    }
+}
+
+/// Wraps an arbitrary closure, for checks that need application state.
+pub struct FnCheck {
+    name: String,
+    f: Box<dyn Fn() -> CheckFuture<'static> + Send + Sync>,
+}
+
+impl FnCheck {
+    pub fn new<F>(name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn() -> CheckFuture<'static> + Send + Sync + 'static,
+    {
+        Self { name: name.into(), f: Box::new(f) }
+    }
+}
+
+impl HealthCheck for FnCheck {
+    fn name(&self) -> &str { &self.name }
+    fn kind(&self) -> &'static str { "custom" }
+    fn check(&self) -> CheckFuture<'_> { (self.f)() }
+}
+
+// ---------- Registry ----------
+#[derive(Clone, Serialize)]
+pub struct CheckStatus {
+    pub name: String,
+    pub kind: &'static str,
+    /// "unknown" until the first evaluation, then "up" or "down".
+    pub status: &'static str,
+    pub latency_ms: Option<f64>,
+    pub last_error: Option<String>,
+    /// Unix time (ms) of the last evaluation.
+    pub checked_at_ms: Option<u64>,
+}
+
+struct Entry {
+    check: Arc<dyn HealthCheck>,
+    timeout: Duration,
+    status: CheckStatus,
+}
+
+/// Dependency checks evaluated in the background and consulted by `/health/ready`.
+#[derive(Default)]
+pub struct HealthRegistry {
+    entries: RwLock<Vec<Entry>>,
+}
+
+impl HealthRegistry {
+    pub fn register(&self, check: impl HealthCheck + 'static, timeout: Duration) {
+        let status = CheckStatus {
+            name: check.name().to_string(),
+            kind: check.kind(),
+            status: "unknown",
+            latency_ms: None,
+            last_error: None,
+            checked_at_ms: None,
+        };
+        info!("Health: registered {} check '{}' (timeout {:?})", status.kind, status.name, timeout);
+        self.entries.write().push(Entry { check: Arc::new(check), timeout, status });
+    }
+
+    /// Snapshot of every check, in registration order.
+    pub fn statuses(&self) -> Vec<CheckStatus> {
+        self.entries.read().iter().map(|e| e.status.clone()).collect()
+    }
+
+    /// True when every registered check passed its last evaluation.
+    pub fn all_up(&self) -> bool {
+        self.entries.read().iter().all(|e| e.status.status == "up")
+    }
+
+    /// Runs all checks concurrently, each bounded by its own timeout.
+    pub async fn evaluate(&self) {
+        let checks: Vec<(Arc<dyn HealthCheck>, Duration)> = self.entries.read().iter()
+            .map(|e| (e.check.clone(), e.timeout))
+            .collect();
+
+        let mut handles = Vec::with_capacity(checks.len());
+        for (check, timeout) in checks {
+            handles.push(tokio::spawn(async move {
+                let start = Instant::now();
+                let res = match tokio::time::timeout(timeout, check.check()).await {
+                    Ok(r) => r,
+                    Err(_) => Err(format!("timed out after {} ms", timeout.as_millis())),
+                };
+                (check, res, start.elapsed())
+            }));
+        }
+
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        for h in handles {
+            let Ok((check, res, elapsed)) = h.await else { continue };
+            let labels = [check.name(), check.kind()];
+            CHECK_DURATION.with_label_values(&labels).observe(elapsed.as_secs_f64());
+            CHECK_UP.with_label_values(&labels).set(res.is_ok() as i64);
+
+            let mut entries = self.entries.write();
+            let Some(entry) = entries.iter_mut().find(|e| Arc::ptr_eq(&e.check, &check)) else { continue };
+            let was = entry.status.status;
+            entry.status.latency_ms = Some(elapsed.as_secs_f64() * 1000.0);
+            entry.status.checked_at_ms = Some(now_ms);
+            match res {
+                Ok(()) => {
+                    entry.status.status = "up";
+                    entry.status.last_error = None;
+                }
+                Err(e) => {
+                    if was != "down" {
+                        warn!("Health: check '{}' failed: {}", entry.status.name, e);
+                    }
+                    entry.status.status = "down";
+                    entry.status.last_error = Some(e);
+                }
+            }
+            if was == "down" && entry.status.status == "up" {
+                info!("Health: check '{}' recovered", entry.status.name);
+            }
+        }
+    }
+
+    /// Builds checks from `HEALTH_CHECKS`, a comma-separated list of
+    /// `kind:name=target` (e.g. `tcp:db=postgres:5432,http:api=http://api:8080/health,file:cfg=/etc/app/cfg`).
+    pub fn register_from_spec(&self, spec: &str, timeout: Duration) -> anyhow::Result<()> {
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (kind, rest) = item.split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("invalid health check '{item}': expected kind:name=target"))?;
+            let (name, target) = rest.split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("invalid health check '{item}': expected kind:name=target"))?;
+            match kind {
+                "tcp" => self.register(TcpCheck::new(name, target), timeout),
+                "http" => self.register(HttpCheck::new(name, target), timeout),
+                "file" => self.register(FileExistsCheck::new(name, target), timeout),
+                other => anyhow::bail!("invalid health check '{item}': unknown kind '{other}' (tcp, http, file)"),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Re-evaluates the registry every `interval` for the lifetime of the process.
+pub fn spawn_evaluator(registry: Arc<HealthRegistry>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            registry.evaluate().await;
+        }
+    });
+}
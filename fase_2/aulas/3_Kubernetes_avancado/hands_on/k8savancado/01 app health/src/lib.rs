@@ -0,0 +1,502 @@
+
+use axum::{
+    extract::{MatchedPath, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Router,
+};
+use prometheus::{Encoder, TextEncoder, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge, register_int_gauge_vec};
+use std::{collections::HashSet, future::Future, pin::Pin, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{Duration, Instant}};
+use tracing::{info, warn};
+use tower_http::trace::TraceLayer;
+use tower::{ServiceBuilder, Layer};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+#[allow(unused_imports)]
+use axum::response::Response;
+
+pub mod admin;
+mod cgroup;
+pub mod config;
+pub mod faults;
+pub mod grpc;
+pub mod health;
+pub mod limiter;
+pub mod memory;
+pub mod resources;
+pub mod scenario;
+pub mod shutdown;
+pub mod slo;
+pub mod telemetry;
+use admin::AdminAuth;
+use faults::{FaultLayer, FaultRegistry};
+use health::HealthRegistry;
+use scenario::ScenarioEngine;
+use shutdown::Shutdown;
+
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref REQS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "http_requests_total",
+        "Number of HTTP requests",
+        &["method", "path", "status"]
+    ).unwrap();
+
+    static ref REQ_INFLIGHT: IntGauge = register_int_gauge!(
+        "http_requests_in_flight",
+        "Number of in-flight HTTP requests"
+    ).unwrap();
+
+    static ref REQ_DURATION: HistogramVec = register_histogram_vec!(
+        "http_request_duration_seconds",
+        "HTTP request latencies in seconds",
+        &["method", "path", "status"],
+        DURATION_BUCKETS.clone()
+    ).unwrap();
+
+    static ref SERIES_OVERFLOW: IntCounter = register_int_counter!(
+        "http_metrics_series_overflow_total",
+        "HTTP observations dropped because metrics_max_series label sets were already in use"
+    ).unwrap();
+
+    // Histogram buckets, shared with the exemplar store
+    static ref DURATION_BUCKETS: Vec<f64> = METRICS_SETTINGS.get()
+        .map_or_else(|| prometheus::DEFAULT_BUCKETS.to_vec(), |(buckets, _)| buckets.clone());
+
+    // Distinct (method, path, status) label sets seen so far
+    static ref SERIES: parking_lot::Mutex<HashSet<(String, String, u16)>> = parking_lot::Mutex::new(HashSet::new());
+
+    static ref MAX_SERIES: usize = METRICS_SETTINGS.get().map_or(500, |(_, max)| *max);
+
+    static ref ERR_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "http_errors_total",
+        "Number of HTTP 5xx errors",
+        &["path", "status"]
+    ).unwrap();
+
+    static ref SATURATION_GAUGE: IntGaugeVec = register_int_gauge_vec!(
+        "saturation_gauge",
+        "A toy gauge for saturation (e.g., number of concurrent work units)",
+        &["type"]
+    ).unwrap();
+}
+
+static METRICS_SETTINGS: once_cell::sync::OnceCell<(Vec<f64>, usize)> = once_cell::sync::OnceCell::new();
+
+/// Sets `http_duration_buckets` and `metrics_max_series`; call before the first request is measured.
+pub fn init_metrics(cfg: &config::Config) {
+    if METRICS_SETTINGS.set((cfg.http_duration_buckets.clone(), cfg.metrics_max_series)).is_err() {
+        warn!("Metrics: settings already initialised, keeping the first ones");
+    }
+}
+
+// Only well-known methods become label values; anything else is "OTHER"
+fn method_label(method: &axum::http::Method) -> &'static str {
+    use axum::http::Method;
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::PATCH => "PATCH",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        _ => "OTHER",
+    }
+}
+
+// Admits a label set unless metrics_max_series distinct ones are already in use
+fn admit_series(method: &str, path: &str, status: u16) -> bool {
+    let mut seen = SERIES.lock();
+    let key = (method.to_string(), path.to_string(), status);
+    if seen.contains(&key) {
+        return true;
+    }
+    if seen.len() >= *MAX_SERIES {
+        SERIES_OVERFLOW.inc();
+        return false;
+    }
+    seen.insert(key);
+    true
+}
+
+
+// ---------- App State ----------
+#[derive(Clone)]
+pub struct AppState {
+    pub ready: Arc<AtomicBool>,
+    pub live: Arc<AtomicBool>,
+    pub started: Arc<AtomicBool>,
+    // leaked memory store for demo
+    mem_leak: Arc<parking_lot::RwLock<Vec<Vec<u8>>>>,
+    // background /simulate/mem/ramp, if any
+    mem_ramp: Arc<parking_lot::Mutex<Option<memory::Ramp>>>,
+    // dependency checks feeding readiness
+    pub health: Arc<HealthRegistry>,
+    // chaos scenario playback
+    pub scenario: Arc<ScenarioEngine>,
+    // runtime fault injection rules
+    pub faults: Arc<FaultRegistry>,
+    // SIGTERM / drain coordination
+    pub shutdown: Arc<Shutdown>,
+    // effective configuration (env + optional file, hot-reloaded)
+    pub config: Arc<config::ConfigStore>,
+    // availability/latency SLIs and burn rates
+    pub slo: Arc<slo::SloTracker>,
+    // adaptive concurrency limit for non-probe routes (off when `None`)
+    pub limiter: Option<Arc<limiter::AdaptiveLimiter>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            ready: Arc::new(AtomicBool::new(false)),
+            live: Arc::new(AtomicBool::new(true)),
+            started: Arc::new(AtomicBool::new(false)),
+            mem_leak: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            mem_ramp: Arc::new(parking_lot::Mutex::new(None)),
+            health: Arc::new(HealthRegistry::default()),
+            scenario: Arc::new(ScenarioEngine::default()),
+            faults: Arc::new(FaultRegistry::default()),
+            shutdown: Arc::new(Shutdown::default()),
+            config: Arc::new(config::ConfigStore::default()),
+            slo: Arc::new(slo::SloTracker::default()),
+            limiter: None,
+        }
+    }
+}
+
+impl AppState {
+    /// What `/health/ready` and the gRPC readiness service report: started, flagged ready and dependencies up.
+    pub fn is_ready(&self) -> bool {
+        self.started.load(Ordering::SeqCst) && self.ready.load(Ordering::SeqCst) && self.health.all_up()
+    }
+}
+
+// ---------- Query structs ----------
+#[derive(Deserialize)]
+struct CpuParams { seconds: Option<u64> }
+
+#[derive(Deserialize)]
+struct MemParams { mb: Option<usize>, leak: Option<bool>, touch: Option<bool> }
+
+#[derive(Deserialize)]
+struct LatencyParams { ms: Option<u64> }
+
+#[derive(Deserialize)]
+struct ErrorParams { rate: Option<f64> }
+
+#[derive(Deserialize)]
+struct FlipParams { value: Option<bool> }
+
+
+// ---------- Handlers ----------
+async fn metrics(headers: axum::http::HeaderMap) -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let metric_families = prometheus::gather();
+    let mut buffer = Vec::new();
+    encoder.encode(&metric_families, &mut buffer).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    // Exemplars only exist in OpenMetrics; plain scrapes get the classic format
+    let accept = headers.get(axum::http::header::ACCEPT).and_then(|v| v.to_str().ok());
+    if telemetry::wants_openmetrics(accept) {
+        let ct = [(axum::http::header::CONTENT_TYPE, telemetry::OPENMETRICS_CONTENT_TYPE)];
+        return (StatusCode::OK, ct, telemetry::to_openmetrics(&text)).into_response();
+    }
+    (StatusCode::OK, text).into_response()
+}
+
+async fn live(State(state): State<AppState>) -> impl IntoResponse {
+    if state.live.load(Ordering::SeqCst) { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }
+}
+
+async fn ready(State(state): State<AppState>) -> impl IntoResponse {
+    let started = state.started.load(Ordering::SeqCst);
+    let flag = state.ready.load(Ordering::SeqCst);
+    let ok = state.is_ready();
+    let body = serde_json::json!({
+        "status": if ok { "ready" } else { "not_ready" },
+        "started": started,
+        "ready": flag,
+        "checks": state.health.statuses(),
+    });
+    let code = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, axum::Json(body))
+}
+
+async fn startup(State(state): State<AppState>) -> impl IntoResponse {
+    if state.started.load(Ordering::SeqCst) { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }
+}
+
+async fn info() -> impl IntoResponse {
+    let body = serde_json::json!({
+        "name": "app-health-demo",
+        "version": env!("CARGO_PKG_VERSION"),
+        "description": env!("CARGO_PKG_DESCRIPTION"),
+    });
+    (StatusCode::OK, axum::Json(body))
+}
+
+async fn simulate_cpu(Query(params): Query<CpuParams>) -> impl IntoResponse {
+    let seconds = params.seconds.unwrap_or(5);
+    burn_cpu(seconds).await;
+    format!("CPU busy loop completed in ~{}s", seconds)
+}
+
+async fn simulate_mem(State(state): State<AppState>, Query(params): Query<MemParams>) -> impl IntoResponse {
+    let mb = params.mb.unwrap_or(64);
+    let leak = params.leak.unwrap_or(true);
+    let touch = params.touch.unwrap_or(true);
+    match memory::allocate(&state, mb, leak, touch) {
+        Ok(msg) => msg.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+// Busy loop to burn CPU
+#[tracing::instrument(name = "simulate.cpu")]
+async fn burn_cpu(seconds: u64) {
+    let until = Instant::now() + std::time::Duration::from_secs(seconds);
+    let mut n: u64 = 0;
+    while Instant::now() < until {
+        // simple math to keep CPU busy
+        n = n.wrapping_mul(1664525).wrapping_add(1013904223);
+        if n.is_multiple_of(1_000_003) {
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+#[tracing::instrument(name = "simulate.latency", skip_all, fields(ms))]
+async fn simulate_latency(Query(params): Query<LatencyParams>) -> impl IntoResponse {
+    let ms = params.ms.unwrap_or(500);
+    tracing::Span::current().record("ms", ms);
+    tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+    format!("Slept {} ms", ms)
+}
+
+#[tracing::instrument(name = "simulate.error", skip_all, fields(rate, error.injected))]
+async fn simulate_error(Query(params): Query<ErrorParams>) -> impl IntoResponse {
+    let rate = params.rate.unwrap_or(0.5).clamp(0.0, 1.0);
+    let rnd: f64 = rand::random();
+    let span = tracing::Span::current();
+    span.record("rate", rate);
+    span.record("error.injected", rnd < rate);
+    if rnd < rate {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Injected error").into_response();
+    }
+    "OK".into_response()
+}
+
+async fn unstable(State(state): State<AppState>) -> impl IntoResponse {
+    use rand::Rng;
+    // A running scenario may override the default error rate / add latency
+    if let Some(o) = state.scenario.unstable_override() {
+        if let Some(ms) = o.latency_ms {
+            tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+        }
+        if let Some(rate) = o.error_rate {
+            if rand::random::<f64>() < rate {
+                return (StatusCode::INTERNAL_SERVER_ERROR, "scenario 500").into_response();
+            }
+            return "scenario path".into_response();
+        }
+    }
+    let choice: u8 = rand::thread_rng().gen_range(0..100);
+    if choice < 10 {
+        (StatusCode::INTERNAL_SERVER_ERROR, "flaky 500").into_response()
+    } else if choice < 40 {
+        let delay = rand::thread_rng().gen_range(300..1500);
+        SATURATION_GAUGE.with_label_values(&["inflight"]).inc();
+        tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+        SATURATION_GAUGE.with_label_values(&["inflight"]).dec();
+        format!("slow path {} ms", delay).into_response()
+    } else {
+        "fast path".into_response()
+    }
+}
+
+// Admin flips to demonstrate probes
+async fn flip_ready(State(state): State<AppState>, Query(p): Query<FlipParams>) -> impl IntoResponse {
+    let v = p.value.unwrap_or(true);
+    state.ready.store(v, Ordering::SeqCst);
+    format!("readiness = {v}")
+}
+async fn flip_live(State(state): State<AppState>, Query(p): Query<FlipParams>) -> impl IntoResponse {
+    let v = p.value.unwrap_or(true);
+    state.live.store(v, Ordering::SeqCst);
+    format!("liveness = {v}")
+}
+
+// ---------- Middleware for metrics ----------
+/// Counts a request in `http_requests_in_flight`, and in the state's drain count, until it completes
+/// or its future is dropped (client gone).
+struct InFlight(Arc<Shutdown>);
+
+impl InFlight {
+    fn start(shutdown: Arc<Shutdown>) -> Self {
+        REQ_INFLIGHT.inc();
+        shutdown.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(shutdown)
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        REQ_INFLIGHT.dec();
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Clone)]
+struct MetricsLayer {
+    shutdown: Arc<Shutdown>,
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+    fn layer(&self, inner: S) -> Self::Service { MetricsService { inner, shutdown: self.shutdown.clone() } }
+}
+
+#[derive(Clone)]
+struct MetricsService<S> {
+    inner: S,
+    shutdown: Arc<Shutdown>,
+}
+
+impl<S, ReqBody> tower::Service<axum::http::Request<ReqBody>> for MetricsService<S>
+where
+    S: tower::Service<axum::http::Request<ReqBody>, Response = axum::response::Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        tower::Service::<axum::http::Request<ReqBody>>::poll_ready(&mut self.inner, cx)
+    }
+
+    fn call(&mut self, req: axum::http::Request<ReqBody>) -> Self::Future {
+        let mut svc = self.inner.clone();
+        let method = method_label(req.method());
+        // Route template (e.g. /admin/faults/:id), never the raw path
+        let path = req.extensions().get::<MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| "unmatched".to_string());
+        let start = Instant::now();
+        let shutdown = self.shutdown.clone();
+        Box::pin(async move {
+            let _in_flight = InFlight::start(shutdown);
+            let res = svc.call(req).await;
+            let status = match &res {
+                Ok(r) => r.status(),
+                Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            let status_str = status.as_u16().to_string();
+            let elapsed = start.elapsed().as_secs_f64();
+            tracing::Span::current().record("http.response.status_code", status.as_u16());
+            if admit_series(method, &path, status.as_u16()) {
+                REQS_TOTAL.with_label_values(&[method, &path, &status_str]).inc();
+                REQ_DURATION.with_label_values(&[method, &path, &status_str]).observe(elapsed);
+                if let Some(trace_id) = telemetry::current_trace_id() {
+                    let labels = [("method", method), ("path", path.as_str()), ("status", status_str.as_str())];
+                    telemetry::record_exemplar("http_request_duration_seconds", &labels, &DURATION_BUCKETS, elapsed, trace_id);
+                }
+                if status.is_server_error() {
+                    ERR_TOTAL.with_label_values(&[&path, &status_str]).inc();
+                }
+            }
+            res
+        })
+    }
+}
+
+// ---------- Router ----------
+/// Probes, metrics and the read-only demo routes; nothing here injects failures.
+pub fn public_routes() -> Router<AppState> {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+        .route("/health/startup", get(startup))
+        .route("/info", get(info))
+        .route("/slo", get(slo::show))
+        .route("/slo/rules", get(slo::rules))
+        .route("/unstable", get(unstable))
+}
+
+/// Everything that changes state or injects failures: authenticated, and mutations are POST-only and audited.
+/// `/simulate/latency` and `/simulate/error` stay GET: they only slow down or fail their own response.
+pub fn admin_routes(auth: Arc<AdminAuth>) -> Router<AppState> {
+    Router::new()
+        .route("/simulate/latency", get(simulate_latency))
+        .route("/simulate/error", get(simulate_error))
+        .route("/simulate/cpu", post(simulate_cpu))
+        .route("/simulate/mem", post(simulate_mem))
+        .route("/simulate/mem/free", post(memory::free))
+        .route("/simulate/mem/fill", post(memory::fill))
+        .route("/simulate/mem/ramp", post(memory::ramp))
+        .route("/simulate/mem/ramp/stop", post(memory::ramp_stop))
+        .route("/simulate/mem/status", get(memory::status))
+        .route("/admin/flip_ready", post(flip_ready))
+        .route("/admin/flip_live", post(flip_live))
+        .route("/admin/scenario", get(scenario::status))
+        .route("/admin/scenario/start", post(scenario::start))
+        .route("/admin/scenario/stop", post(scenario::stop))
+        .route("/admin/faults", get(faults::list).post(faults::add).delete(faults::clear))
+        .route("/admin/faults/:id", delete(faults::remove))
+        .route("/admin/shutdown", post(shutdown::trigger))
+        .route("/admin/config", get(config::show))
+        .route("/admin/config/reload", post(config::reload_now))
+        .route_layer(axum::middleware::from_fn_with_state(auth, admin::guard))
+}
+
+/// Attaches state plus the tracing, metrics and fault-injection layers.
+pub fn with_layers(router: Router<AppState>, state: &AppState) -> Router {
+    router.with_state(state.clone()).layer(ServiceBuilder::new()
+        .layer(TraceLayer::new_for_http().make_span_with(telemetry::make_span))
+        .layer(MetricsLayer { shutdown: state.shutdown.clone() })
+        .layer(limiter::LimitLayer::new(state.limiter.clone()))
+        .layer(FaultLayer::new(state.faults.clone()))
+    )
+}
+
+/// Public and admin routes on a single listener (the default without `ADMIN_PORT`).
+pub fn app(state: &AppState, auth: Arc<AdminAuth>) -> Router {
+    with_layers(public_routes().merge(admin_routes(auth)), state)
+}
+
+// ---------- Startup ----------
+/// Simulated boot: `started` after `startup_delay`, `ready` another `ready_after` later.
+#[derive(Clone, Copy, Debug)]
+pub struct StartupTiming {
+    pub startup_delay: Duration,
+    pub ready_after: Duration,
+}
+
+impl From<&config::Config> for StartupTiming {
+    fn from(cfg: &config::Config) -> Self {
+        Self {
+            startup_delay: Duration::from_millis(cfg.startup_delay_ms),
+            ready_after: Duration::from_millis(cfg.ready_after_ms),
+        }
+    }
+}
+
+pub fn spawn_startup(state: AppState, timing: StartupTiming) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        info!("Startup: sleeping {:?} before startupProbe passes", timing.startup_delay);
+        tokio::time::sleep(timing.startup_delay).await;
+        state.started.store(true, Ordering::SeqCst);
+        info!("Startup: started=true");
+        tokio::time::sleep(timing.ready_after).await;
+        state.ready.store(true, Ordering::SeqCst);
+        info!("Readiness: ready=true");
+    })
+}
@@ -0,0 +1,287 @@
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_gauge, register_histogram, register_int_counter_vec, register_int_gauge, Gauge, Histogram,
+    IntCounterVec, IntGauge,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{atomic::{AtomicUsize, Ordering}, Arc},
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+use tower::Layer;
+use tracing::info;
+
+use crate::config::Config;
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref LIMIT: Gauge = register_gauge!(
+        "concurrency_limit",
+        "Current adaptive concurrency limit for non-priority routes"
+    ).unwrap();
+
+    static ref LIMITED_INFLIGHT: IntGauge = register_int_gauge!(
+        "concurrency_limit_inflight",
+        "Requests currently holding a concurrency slot"
+    ).unwrap();
+
+    static ref QUEUED: IntGauge = register_int_gauge!(
+        "concurrency_limit_queued",
+        "Requests waiting for a concurrency slot"
+    ).unwrap();
+
+    static ref REJECTIONS: IntCounterVec = register_int_counter_vec!(
+        "concurrency_limit_rejections_total",
+        "Requests shed with 503 by the concurrency limiter",
+        &["reason"]
+    ).unwrap();
+
+    static ref QUEUE_SECONDS: Histogram = register_histogram!(
+        "concurrency_limit_queue_seconds",
+        "Time spent waiting for a concurrency slot (admitted or shed)",
+        vec![0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]
+    ).unwrap();
+}
+
+// ---------- Limiter ----------
+/// AIMD tuning. The limit grows by ~1 per "round trip" while latency stays under
+/// `latency_target`, and is multiplied by `backoff` when a request takes longer.
+#[derive(Clone, Debug)]
+pub struct LimiterConfig {
+    pub initial: usize,
+    pub min: usize,
+    pub max: usize,
+    pub latency_target: Duration,
+    pub backoff: f64,
+    /// How long a request may wait for a slot before it is shed.
+    pub queue_timeout: Duration,
+    /// Requests beyond this many waiters are shed immediately.
+    pub max_queue: usize,
+}
+
+impl Default for LimiterConfig {
+    fn default() -> Self {
+        Self {
+            initial: 20,
+            min: 2,
+            max: 200,
+            latency_target: Duration::from_millis(500),
+            backoff: 0.9,
+            queue_timeout: Duration::from_millis(100),
+            max_queue: 50,
+        }
+    }
+}
+
+impl LimiterConfig {
+    /// `None` unless `concurrency_limit` is `aimd`; validated with the rest of the config.
+    pub fn from_config(cfg: &Config) -> Option<Self> {
+        (cfg.concurrency_limit == "aimd").then_some(Self {
+            initial: cfg.concurrency_limit_initial,
+            min: cfg.concurrency_limit_min,
+            max: cfg.concurrency_limit_max,
+            latency_target: cfg.concurrency_limit_latency_target,
+            backoff: cfg.concurrency_limit_backoff,
+            queue_timeout: cfg.concurrency_limit_queue_timeout,
+            max_queue: cfg.concurrency_limit_max_queue,
+        })
+    }
+}
+
+struct Aimd {
+    limit: f64,
+    last_decrease: Instant,
+}
+
+pub struct AdaptiveLimiter {
+    cfg: LimiterConfig,
+    aimd: parking_lot::Mutex<Aimd>,
+    inflight: AtomicUsize,
+    waiting: AtomicUsize,
+    released: Notify,
+}
+
+#[derive(Clone, Copy)]
+enum Shed {
+    QueueFull,
+    QueueTimeout,
+}
+
+/// A concurrency slot; released on drop.
+struct Permit {
+    limiter: Arc<AdaptiveLimiter>,
+    // utilisation when admitted: the limit only grows if it was actually being used
+    saturated: bool,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        LIMITED_INFLIGHT.set(self.limiter.inflight.fetch_sub(1, Ordering::SeqCst) as i64 - 1);
+        self.limiter.released.notify_one();
+    }
+}
+
+/// A request's place in the queue; leaving it, also by being dropped (client gone), frees the place.
+struct Queued<'a>(&'a AdaptiveLimiter);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.waiting.fetch_sub(1, Ordering::SeqCst);
+        QUEUED.dec();
+        // A release wakes a single waiter. If that one timed out or went away instead, or the limit grew and
+        // more than one slot is free, the others would sit until the next release: hand the wakeup on.
+        self.0.wake_next();
+    }
+}
+
+impl AdaptiveLimiter {
+    pub fn new(cfg: LimiterConfig) -> Self {
+        info!("Concurrency: AIMD limit {} (min {}, max {}), latency target {:?}", cfg.initial, cfg.min, cfg.max, cfg.latency_target);
+        LIMIT.set(cfg.initial as f64);
+        Self {
+            aimd: parking_lot::Mutex::new(Aimd { limit: cfg.initial as f64, last_decrease: Instant::now() }),
+            cfg,
+            inflight: AtomicUsize::new(0),
+            waiting: AtomicUsize::new(0),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.aimd.lock().limit as usize
+    }
+
+    fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let limit = self.limit();
+        let prev = self.inflight.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < limit).then_some(n + 1)).ok()?;
+        LIMITED_INFLIGHT.set(prev as i64 + 1);
+        Some(Permit { limiter: self.clone(), saturated: 2 * (prev + 1) >= limit })
+    }
+
+    async fn acquire(self: &Arc<Self>) -> Result<Permit, Shed> {
+        let start = Instant::now();
+        if let Some(p) = self.try_acquire() {
+            QUEUE_SECONDS.observe(0.0);
+            return Ok(p);
+        }
+        if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.cfg.max_queue {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            return Err(Shed::QueueFull);
+        }
+        QUEUED.inc();
+        let queued = Queued(self);
+        let deadline = tokio::time::Instant::from_std(start + self.cfg.queue_timeout);
+        let result = loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            if let Some(p) = self.try_acquire() {
+                break Ok(p);
+            }
+            if tokio::time::timeout_at(deadline, released).await.is_err() {
+                break Err(Shed::QueueTimeout);
+            }
+        };
+        drop(queued);
+        QUEUE_SECONDS.observe(start.elapsed().as_secs_f64());
+        result
+    }
+
+    fn wake_next(&self) {
+        if self.waiting.load(Ordering::SeqCst) > 0 && self.inflight.load(Ordering::SeqCst) < self.limit() {
+            self.released.notify_one();
+        }
+    }
+
+    /// AIMD step after a request finished in `latency`.
+    fn record(&self, latency: Duration, saturated: bool) {
+        let mut a = self.aimd.lock();
+        if latency > self.cfg.latency_target {
+            // concurrent slow requests are one congestion signal, not many
+            if a.last_decrease.elapsed() >= self.cfg.latency_target {
+                a.limit = (a.limit * self.cfg.backoff).max(self.cfg.min as f64);
+                a.last_decrease = Instant::now();
+            }
+        } else if saturated {
+            a.limit = (a.limit + 1.0 / a.limit).min(self.cfg.max as f64);
+        }
+        LIMIT.set(a.limit.floor());
+    }
+}
+
+/// Probes, scrapes and operator actions must never queue behind user traffic.
+fn priority(path: &str) -> bool {
+    path.starts_with("/health/") || path == "/metrics" || path.starts_with("/admin/")
+}
+
+fn shed_response(reason: Shed) -> Response {
+    let reason = match reason {
+        Shed::QueueFull => "queue_full",
+        Shed::QueueTimeout => "queue_timeout",
+    };
+    REJECTIONS.with_label_values(&[reason]).inc();
+    let mut res = (StatusCode::SERVICE_UNAVAILABLE, "overloaded: concurrency limit reached").into_response();
+    res.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    res
+}
+
+// ---------- Middleware ----------
+#[derive(Clone)]
+pub struct LimitLayer {
+    limiter: Option<Arc<AdaptiveLimiter>>,
+}
+
+impl LimitLayer {
+    /// Passes everything through when `limiter` is `None`.
+    pub fn new(limiter: Option<Arc<AdaptiveLimiter>>) -> Self { Self { limiter } }
+}
+
+impl<S> Layer<S> for LimitLayer {
+    type Service = LimitService<S>;
+    fn layer(&self, inner: S) -> Self::Service { LimitService { inner, limiter: self.limiter.clone() } }
+}
+
+#[derive(Clone)]
+pub struct LimitService<S> {
+    inner: S,
+    limiter: Option<Arc<AdaptiveLimiter>>,
+}
+
+impl<S, ReqBody> tower::Service<axum::http::Request<ReqBody>> for LimitService<S>
+where
+    S: tower::Service<axum::http::Request<ReqBody>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Send,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Self::Error>> {
+        tower::Service::<axum::http::Request<ReqBody>>::poll_ready(&mut self.inner, cx)
+    }
+
+    fn call(&mut self, req: axum::http::Request<ReqBody>) -> Self::Future {
+        let mut svc = self.inner.clone();
+        let limiter = self.limiter.clone().filter(|_| !priority(req.uri().path()));
+        Box::pin(async move {
+            let Some(limiter) = limiter else { return svc.call(req).await };
+            let permit = match limiter.acquire().await {
+                Ok(p) => p,
+                Err(reason) => return Ok(shed_response(reason)),
+            };
+            let start = Instant::now();
+            let res = svc.call(req).await;
+            limiter.record(start.elapsed(), permit.saturated);
+            drop(permit);
+            res
+        })
+    }
+}
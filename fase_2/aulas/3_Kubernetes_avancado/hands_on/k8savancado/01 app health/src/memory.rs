@@ -0,0 +1,256 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{register_int_gauge, IntGauge};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::{cgroup, AppState};
+
+const MIB: usize = 1024 * 1024;
+const PAGE: usize = 4096;
+// fill/ramp allocate in blocks of at most this size so `free?blocks=` stays useful
+const CHUNK_MIB: usize = 16;
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref HELD_BYTES: IntGauge = register_int_gauge!(
+        "simulate_mem_held_bytes",
+        "Bytes currently held by /simulate/mem"
+    ).unwrap();
+}
+
+// ---------- Allocation ----------
+/// `mb` MiB in bytes, or why that cannot be allocated at all.
+pub fn mib_to_bytes(mb: usize) -> Result<usize, String> {
+    mb.checked_mul(MIB)
+        .filter(|b| *b <= isize::MAX as usize)
+        .ok_or_else(|| format!("{mb} MiB is more than this process can address"))
+}
+
+/// Allocates `mb` MiB (callers keep it small or checked); with `touch` every page is written so it really counts towards RSS.
+fn block(mb: usize, touch: bool) -> Vec<u8> {
+    let mut v = vec![0u8; mb * MIB];
+    if touch {
+        for i in (0..v.len()).step_by(PAGE) {
+            v[i] = 1;
+        }
+    }
+    v
+}
+
+/// Bytes held by `/simulate/mem` blocks that were not freed.
+pub fn held_bytes(state: &AppState) -> usize {
+    state.mem_leak.read().iter().map(Vec::len).sum()
+}
+
+fn hold(state: &AppState, v: Vec<u8>) {
+    let mut guard = state.mem_leak.write();
+    guard.push(v);
+    HELD_BYTES.set(guard.iter().map(Vec::len).sum::<usize>() as i64);
+}
+
+#[tracing::instrument(name = "simulate.mem", skip(state))]
+pub fn allocate(state: &AppState, mb: usize, leak: bool, touch: bool) -> Result<String, String> {
+    mib_to_bytes(mb)?;
+    let v = block(mb, touch);
+    Ok(if leak {
+        hold(state, v);
+        format!("Leaked ~{} MiB (total blocks: {})", mb, state.mem_leak.read().len())
+    } else {
+        format!("Allocated ~{} MiB and dropped", mb)
+    })
+}
+
+/// Bytes still missing to reach `percent` of `memory.max`, or `None` without a cgroup limit.
+fn bytes_to_percent(percent: f64) -> Option<usize> {
+    let max = cgroup::memory_max()? as f64;
+    let current = cgroup::memory_current()? as f64;
+    Some((max * percent / 100.0 - current).max(0.0) as usize)
+}
+
+// ---------- Ramp ----------
+pub struct Ramp {
+    mb_per_sec: usize,
+    target: RampTarget,
+    started: Instant,
+    added_mib: usize,
+    task: JoinHandle<()>,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RampTarget {
+    Mib(usize),
+    Percent(f64),
+    Unbounded,
+}
+
+impl RampTarget {
+    /// MiB still to add, or `None` once reached; a `percent` ramp also stops when `memory.max` goes away.
+    fn room(self, added_mib: usize) -> Option<usize> {
+        match self {
+            RampTarget::Mib(mb) => mb.checked_sub(added_mib).filter(|left| *left > 0),
+            RampTarget::Percent(p) => bytes_to_percent(p).filter(|b| *b > 0).map(|b| b.div_ceil(MIB)),
+            RampTarget::Unbounded => Some(usize::MAX),
+        }
+    }
+}
+
+fn stop_ramp(state: &AppState) -> bool {
+    match state.mem_ramp.lock().take() {
+        Some(r) => { r.task.abort(); true }
+        None => false,
+    }
+}
+
+// ---------- Handlers ----------
+#[derive(Deserialize)]
+pub struct FreeParams { blocks: Option<usize> }
+
+/// Drops the `blocks` most recent blocks (all when omitted) and stops any ramp.
+pub async fn free(State(state): State<AppState>, Query(p): Query<FreeParams>) -> impl IntoResponse {
+    let ramp_stopped = stop_ramp(&state);
+    let freed: Vec<Vec<u8>> = {
+        let mut guard = state.mem_leak.write();
+        let n = p.blocks.unwrap_or(guard.len()).min(guard.len());
+        let at = guard.len() - n;
+        guard.split_off(at)
+    };
+    let freed_bytes: usize = freed.iter().map(Vec::len).sum();
+    // drop outside the lock; large blocks are munmap'ed so RSS goes down right away
+    drop(freed);
+    HELD_BYTES.set(held_bytes(&state) as i64);
+    info!("Memory: freed {} MiB", freed_bytes / MIB);
+    Json(serde_json::json!({
+        "freed_bytes": freed_bytes,
+        "held_bytes": held_bytes(&state),
+        "blocks": state.mem_leak.read().len(),
+        "ramp_stopped": ramp_stopped,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct FillParams { percent: f64, touch: Option<bool> }
+
+/// Allocates until the cgroup uses `percent`% of `memory.max`.
+pub async fn fill(State(state): State<AppState>, Query(p): Query<FillParams>) -> impl IntoResponse {
+    if !(0.0..=100.0).contains(&p.percent) {
+        return (StatusCode::BAD_REQUEST, "percent must be between 0 and 100".to_string()).into_response();
+    }
+    let Some(missing) = bytes_to_percent(p.percent) else {
+        return (StatusCode::BAD_REQUEST, "no cgroup v2 memory.max limit found".to_string()).into_response();
+    };
+    let touch = p.touch.unwrap_or(true);
+    let target_mib = missing / MIB;
+    let st = state.clone();
+    let added = tokio::task::spawn_blocking(move || {
+        let mut added = 0;
+        while added < target_mib {
+            let mb = CHUNK_MIB.min(target_mib - added);
+            hold(&st, block(mb, touch));
+            added += mb;
+        }
+        added
+    }).await.unwrap_or(0);
+    info!("Memory: filled {} MiB to reach {}% of memory.max", added, p.percent);
+    Json(serde_json::json!({
+        "added_mib": added,
+        "held_bytes": held_bytes(&state),
+        "memory_current_bytes": cgroup::memory_current(),
+        "memory_max_bytes": cgroup::memory_max(),
+    })).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct RampParams { mb_per_sec: usize, mb: Option<usize>, percent: Option<f64>, touch: Option<bool> }
+
+/// Grows memory by `mb_per_sec` until `mb` MiB were added, the cgroup reaches `percent`%,
+/// or (with neither) until freed or OOMKilled.
+pub async fn ramp(State(state): State<AppState>, Query(p): Query<RampParams>) -> impl IntoResponse {
+    if p.mb_per_sec == 0 {
+        return (StatusCode::BAD_REQUEST, "mb_per_sec must be > 0").into_response();
+    }
+    let target = match (p.mb, p.percent) {
+        (Some(_), Some(_)) => return (StatusCode::BAD_REQUEST, "use either mb or percent").into_response(),
+        (Some(mb), None) => RampTarget::Mib(mb),
+        (None, Some(pct)) if !(0.0..=100.0).contains(&pct) => {
+            return (StatusCode::BAD_REQUEST, "percent must be between 0 and 100").into_response();
+        }
+        (None, Some(_)) if cgroup::memory_max().is_none() => {
+            return (StatusCode::BAD_REQUEST, "no cgroup v2 memory.max limit found").into_response();
+        }
+        (None, Some(pct)) => RampTarget::Percent(pct),
+        (None, None) => RampTarget::Unbounded,
+    };
+    let mut slot = state.mem_ramp.lock();
+    if slot.is_some() {
+        return (StatusCode::CONFLICT, "a ramp is already running; free or stop it first").into_response();
+    }
+    let touch = p.touch.unwrap_or(true);
+    let rate = p.mb_per_sec;
+    let st = state.clone();
+    let task = tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        'ramp: loop {
+            ticker.tick().await;
+            // `rate` MiB per tick, in chunks, re-checking the target before each one
+            let mut left = rate;
+            while left > 0 {
+                let added = st.mem_ramp.lock().as_ref().map(|r| r.added_mib).unwrap_or(0);
+                let Some(room) = target.room(added) else { break 'ramp };
+                let mb = CHUNK_MIB.min(left).min(room);
+                let s = st.clone();
+                if tokio::task::spawn_blocking(move || hold(&s, block(mb, touch))).await.is_err() {
+                    warn!("Memory: ramp allocation failed");
+                    break 'ramp;
+                }
+                if let Some(r) = st.mem_ramp.lock().as_mut() {
+                    r.added_mib += mb;
+                }
+                left -= mb;
+            }
+        }
+        info!("Memory: ramp finished");
+        st.mem_ramp.lock().take();
+    });
+    info!("Memory: ramping {} MiB/s", rate);
+    *slot = Some(Ramp { mb_per_sec: rate, target, started: Instant::now(), added_mib: 0, task });
+    (StatusCode::ACCEPTED, "ramp started").into_response()
+}
+
+pub async fn ramp_stop(State(state): State<AppState>) -> impl IntoResponse {
+    if stop_ramp(&state) { (StatusCode::OK, "ramp stopped") } else { (StatusCode::NOT_FOUND, "no ramp running") }
+}
+
+pub async fn status(State(state): State<AppState>) -> impl IntoResponse {
+    let (blocks, held) = {
+        let guard = state.mem_leak.read();
+        (guard.len(), guard.iter().map(Vec::len).sum::<usize>())
+    };
+    let max = cgroup::memory_max();
+    let current = cgroup::memory_current();
+    let ramp = state.mem_ramp.lock().as_ref().map(|r| serde_json::json!({
+        "mb_per_sec": r.mb_per_sec,
+        "target": r.target,
+        "added_mib": r.added_mib,
+        "elapsed_s": r.started.elapsed().as_secs_f64(),
+    }));
+    Json(serde_json::json!({
+        "blocks": blocks,
+        "held_bytes": held,
+        "held_mib": held / MIB,
+        "cgroup": {
+            "memory_max_bytes": max,
+            "memory_current_bytes": current,
+            "usage_ratio": max.zip(current).map(|(m, c)| c as f64 / m as f64),
+        },
+        "ramp": ramp,
+    }))
+}
@@ -0,0 +1,177 @@
+use lazy_static::lazy_static;
+use prometheus::{
+    core::{Atomic, GenericGaugeVec},
+    register_counter, register_gauge_vec, register_int_counter, register_int_gauge, register_int_gauge_vec,
+    Counter, GaugeVec, IntCounter, IntGauge, IntGaugeVec,
+};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::cgroup;
+
+// ---------- Metrics ----------
+lazy_static! {
+    // tokio runtime
+    static ref TOKIO_WORKERS: IntGauge = register_int_gauge!(
+        "tokio_workers",
+        "Number of tokio worker threads"
+    ).unwrap();
+
+    static ref TOKIO_ALIVE_TASKS: IntGauge = register_int_gauge!(
+        "tokio_alive_tasks",
+        "Number of tasks currently alive in the runtime"
+    ).unwrap();
+
+    static ref TOKIO_GLOBAL_QUEUE: IntGauge = register_int_gauge!(
+        "tokio_global_queue_depth",
+        "Tasks waiting in the runtime's global (injection) queue"
+    ).unwrap();
+
+    static ref TOKIO_BUSY_RATIO: GaugeVec = register_gauge_vec!(
+        "tokio_worker_busy_ratio",
+        "Fraction of the last sampling interval each worker spent running tasks",
+        &["worker"]
+    ).unwrap();
+
+    // cgroup v2; the limits are label-less vecs so they can have no series at all when unlimited
+    static ref CG_MEM_MAX: IntGaugeVec = register_int_gauge_vec!(
+        "cgroup_memory_max_bytes",
+        "cgroup memory.max (absent when unlimited)",
+        &[]
+    ).unwrap();
+
+    static ref CG_MEM_CURRENT: IntGauge = register_int_gauge!(
+        "cgroup_memory_current_bytes",
+        "cgroup memory.current"
+    ).unwrap();
+
+    static ref CG_MEM_RATIO: GaugeVec = register_gauge_vec!(
+        "cgroup_memory_usage_ratio",
+        "memory.current / memory.max; the OOM killer fires near 1 (absent when unlimited)",
+        &[]
+    ).unwrap();
+
+    static ref CG_OOM_KILLS: IntCounter = register_int_counter!(
+        "cgroup_memory_oom_kills_total",
+        "oom_kill events from memory.events"
+    ).unwrap();
+
+    static ref CG_CPU_LIMIT: GaugeVec = register_gauge_vec!(
+        "cgroup_cpu_limit_cores",
+        "CPU limit from cpu.max, in cores (absent when unlimited)",
+        &[]
+    ).unwrap();
+
+    static ref CG_CPU_PERIODS: IntCounter = register_int_counter!(
+        "cgroup_cpu_periods_total",
+        "CFS enforcement periods elapsed (cpu.stat nr_periods)"
+    ).unwrap();
+
+    static ref CG_CPU_THROTTLED_PERIODS: IntCounter = register_int_counter!(
+        "cgroup_cpu_throttled_periods_total",
+        "CFS periods in which the cgroup was throttled (cpu.stat nr_throttled)"
+    ).unwrap();
+
+    static ref CG_CPU_THROTTLED_SECONDS: Counter = register_counter!(
+        "cgroup_cpu_throttled_seconds_total",
+        "Time the cgroup spent throttled (cpu.stat throttled_usec)"
+    ).unwrap();
+}
+
+#[cfg(tokio_unstable)]
+lazy_static! {
+    static ref TOKIO_BLOCKING_THREADS: IntGauge = register_int_gauge!(
+        "tokio_blocking_threads",
+        "Threads spawned for spawn_blocking"
+    ).unwrap();
+
+    static ref TOKIO_IDLE_BLOCKING_THREADS: IntGauge = register_int_gauge!(
+        "tokio_idle_blocking_threads",
+        "Blocking threads currently idle"
+    ).unwrap();
+
+    static ref TOKIO_BLOCKING_QUEUE: IntGauge = register_int_gauge!(
+        "tokio_blocking_queue_depth",
+        "Tasks waiting for a blocking thread"
+    ).unwrap();
+}
+
+// Last cumulative values, to turn kernel/runtime totals into deltas
+#[derive(Default)]
+struct Previous {
+    busy: Vec<Duration>,
+    oom_kills: u64,
+    nr_periods: u64,
+    nr_throttled: u64,
+    throttled_usec: u64,
+}
+
+fn sample(rt: &tokio::runtime::RuntimeMetrics, prev: &mut Previous, elapsed: Duration) {
+    let workers = rt.num_workers();
+    TOKIO_WORKERS.set(workers as i64);
+    TOKIO_ALIVE_TASKS.set(rt.num_alive_tasks() as i64);
+    TOKIO_GLOBAL_QUEUE.set(rt.global_queue_depth() as i64);
+    // the first sample only establishes the baseline for the ratio
+    let first = prev.busy.len() < workers;
+    prev.busy.resize(workers, Duration::ZERO);
+    for w in 0..workers {
+        let busy = rt.worker_total_busy_duration(w);
+        if !first {
+            let ratio = (busy.saturating_sub(prev.busy[w])).as_secs_f64() / elapsed.as_secs_f64().max(1e-9);
+            TOKIO_BUSY_RATIO.with_label_values(&[&w.to_string()]).set(ratio.min(1.0));
+        }
+        prev.busy[w] = busy;
+    }
+    #[cfg(tokio_unstable)]
+    {
+        TOKIO_BLOCKING_THREADS.set(rt.num_blocking_threads() as i64);
+        TOKIO_IDLE_BLOCKING_THREADS.set(rt.num_idle_blocking_threads() as i64);
+        TOKIO_BLOCKING_QUEUE.set(rt.blocking_queue_depth() as i64);
+    }
+
+    let max = cgroup::memory_max();
+    let current = cgroup::memory_current();
+    set_or_remove(&CG_MEM_MAX, max.map(|m| m as i64));
+    if let Some(c) = current { CG_MEM_CURRENT.set(c as i64); }
+    set_or_remove(&CG_MEM_RATIO, max.zip(current).map(|(m, c)| c as f64 / m as f64));
+    if let Some(k) = cgroup::oom_kills() {
+        CG_OOM_KILLS.inc_by(k.saturating_sub(prev.oom_kills));
+        prev.oom_kills = k;
+    }
+    set_or_remove(&CG_CPU_LIMIT, cgroup::cpu_limit_cores());
+    if let Some(st) = cgroup::cpu_stat() {
+        CG_CPU_PERIODS.inc_by(st.nr_periods.saturating_sub(prev.nr_periods));
+        CG_CPU_THROTTLED_PERIODS.inc_by(st.nr_throttled.saturating_sub(prev.nr_throttled));
+        CG_CPU_THROTTLED_SECONDS.inc_by(st.throttled_usec.saturating_sub(prev.throttled_usec) as f64 / 1e6);
+        prev.nr_periods = st.nr_periods;
+        prev.nr_throttled = st.nr_throttled;
+        prev.throttled_usec = st.throttled_usec;
+    }
+}
+
+/// Sets the single series of a label-less gauge vec, or drops it (a limit can be lifted in place).
+fn set_or_remove<P: Atomic>(gauge: &GenericGaugeVec<P>, value: Option<P::T>) {
+    match value {
+        Some(v) => gauge.with_label_values(&[]).set(v),
+        None => { let _ = gauge.remove_label_values(&[]); }
+    }
+}
+
+/// Registers the `/proc` process collector and refreshes runtime and cgroup gauges every `interval`.
+pub fn spawn_collector(interval: Duration) {
+    #[cfg(target_os = "linux")]
+    if let Err(e) = prometheus::register(Box::new(prometheus::process_collector::ProcessCollector::for_self())) {
+        warn!("cannot register process metrics: {}", e);
+    }
+    let rt = tokio::runtime::Handle::current().metrics();
+    tokio::spawn(async move {
+        let mut prev = Previous::default();
+        let mut last = Instant::now();
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            sample(&rt, &mut prev, last.elapsed());
+            last = Instant::now();
+        }
+    });
+}
@@ -0,0 +1,368 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use prometheus::{register_int_counter_vec, register_int_gauge, IntCounterVec, IntGauge};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::AppState;
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref SCENARIO_RUNNING: IntGauge = register_int_gauge!(
+        "scenario_running",
+        "1 while a chaos scenario is being played back"
+    ).unwrap();
+
+    static ref SCENARIO_STEPS: IntCounterVec = register_int_counter_vec!(
+        "scenario_steps_total",
+        "Number of scenario steps executed",
+        &["action"]
+    ).unwrap();
+}
+
+// ---------- Scenario file ----------
+/// A timeline of chaos actions, loaded from YAML or JSON.
+///
+/// ```yaml
+/// name: hpa-rehearsal
+/// steps:
+///   - { at: 30s, action: unstable, error_rate: 0.4, for: 2m }
+///   - { at: 60s, action: mem, mb: 256 }
+///   - { at: 90s, action: live, value: false }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scenario {
+    #[serde(default = "default_name")]
+    pub name: String,
+    pub steps: Vec<Step>,
+}
+
+fn default_name() -> String { "unnamed".to_string() }
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Step {
+    /// Offset from scenario start.
+    #[serde(deserialize_with = "de_duration")]
+    pub at: Duration,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Same as `/simulate/cpu?seconds=`.
+    Cpu { seconds: u64 },
+    /// Same as `/simulate/mem?mb=&leak=&touch=`.
+    Mem {
+        mb: usize,
+        #[serde(default = "default_true")] leak: bool,
+        #[serde(default = "default_true")] touch: bool,
+    },
+    /// Overrides `/unstable` behaviour for a while.
+    Unstable {
+        error_rate: Option<f64>,
+        latency_ms: Option<u64>,
+        #[serde(rename = "for", deserialize_with = "de_duration")]
+        duration: Duration,
+    },
+    /// Same as `/admin/flip_ready?value=`.
+    Ready { value: bool },
+    /// Same as `/admin/flip_live?value=`.
+    Live { value: bool },
+}
+
+fn default_true() -> bool { true }
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Cpu { .. } => "cpu",
+            Action::Mem { .. } => "mem",
+            Action::Unstable { .. } => "unstable",
+            Action::Ready { .. } => "ready",
+            Action::Live { .. } => "live",
+        }
+    }
+
+    /// How long the action keeps affecting the service after it fires.
+    fn duration(&self) -> Duration {
+        match self {
+            Action::Cpu { seconds } => Duration::from_secs(*seconds),
+            Action::Unstable { duration, .. } => *duration,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let raw = std::fs::read(path).map_err(|e| anyhow::anyhow!("reading scenario {path}: {e}"))?;
+        Self::parse(&raw).map_err(|e| anyhow::anyhow!("{path}: {e}"))
+    }
+
+    /// Parses YAML (and therefore JSON) and sorts steps by time.
+    pub fn parse(raw: &[u8]) -> anyhow::Result<Self> {
+        let mut sc: Scenario = serde_yaml::from_slice(raw)?;
+        if sc.steps.is_empty() {
+            anyhow::bail!("scenario '{}' has no steps", sc.name);
+        }
+        for step in &sc.steps {
+            match step.action {
+                Action::Unstable { error_rate: Some(r), .. } if !(0.0..=1.0).contains(&r) => {
+                    anyhow::bail!("error_rate must be between 0 and 1, got {r}");
+                }
+                Action::Mem { mb, .. } => {
+                    crate::memory::mib_to_bytes(mb).map_err(anyhow::Error::msg)?;
+                }
+                _ => {}
+            }
+        }
+        if sc.total().is_none() {
+            anyhow::bail!("scenario '{}' is too long", sc.name);
+        }
+        sc.steps.sort_by_key(|s| s.at);
+        Ok(sc)
+    }
+
+    /// `None` when a step ends beyond what a `Duration` can hold.
+    fn total(&self) -> Option<Duration> {
+        self.steps.iter().map(|s| s.at.checked_add(s.action.duration())).try_fold(Duration::ZERO, |max, end| Some(max.max(end?)))
+    }
+}
+
+/// Accepts `500ms`, `30s`, `2m`, `1h` or a bare number of seconds.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(i) => (&s[..i], &s[i..]),
+        None => (s, "s"),
+    };
+    let n: f64 = num.parse().ok()?;
+    let secs = match unit {
+        "ms" => n / 1000.0,
+        "s" => n,
+        "m" => n * 60.0,
+        "h" => n * 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+pub(crate) fn de_duration<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw { Secs(f64), Text(String) }
+    match Raw::deserialize(d)? {
+        Raw::Secs(n) => Duration::try_from_secs_f64(n)
+            .map_err(|_| serde::de::Error::custom(format!("invalid duration {n} (must be a non-negative number of seconds)"))),
+        Raw::Text(t) => parse_duration(&t)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid duration '{t}' (use e.g. 500ms, 30s, 2m)"))),
+    }
+}
+
+// ---------- Engine ----------
+#[derive(Clone, Copy)]
+pub struct UnstableOverride {
+    pub error_rate: Option<f64>,
+    pub latency_ms: Option<u64>,
+    until: Instant,
+}
+
+struct Run {
+    scenario: Scenario,
+    started: Instant,
+    fired: usize,
+    finished: bool,
+    // ready/live before the first step, restored by `stop`
+    flags: (bool, bool),
+    task: JoinHandle<()>,
+    effects: Vec<JoinHandle<()>>,
+}
+
+/// Plays one scenario at a time against the existing simulators.
+#[derive(Default)]
+pub struct ScenarioEngine {
+    run: Mutex<Option<Run>>,
+    unstable: Mutex<Option<UnstableOverride>>,
+}
+
+impl ScenarioEngine {
+    pub fn unstable_override(&self) -> Option<UnstableOverride> {
+        let mut guard = self.unstable.lock();
+        match *guard {
+            Some(o) if o.until > Instant::now() => Some(o),
+            Some(_) => { *guard = None; None }
+            None => None,
+        }
+    }
+
+    pub fn start(&self, state: AppState, scenario: Scenario) -> anyhow::Result<()> {
+        let mut run = self.run.lock();
+        if run.as_ref().is_some_and(|r| !r.finished) {
+            anyhow::bail!("scenario '{}' is already running", run.as_ref().unwrap().scenario.name);
+        }
+        let started = Instant::now();
+        let too_long = || anyhow::anyhow!("scenario '{}' is too long", scenario.name);
+        let end = scenario.total().and_then(|t| started.checked_add(t)).ok_or_else(too_long)?;
+        // every step fires before `end`, so these cannot overflow once `end` did not
+        let steps: Vec<(Instant, Action)> =
+            scenario.steps.iter().map(|s| Some((started.checked_add(s.at)?, s.action.clone()))).collect::<Option<_>>().ok_or_else(too_long)?;
+        info!("Scenario '{}': starting ({} steps, ~{}s)", scenario.name, steps.len(), (end - started).as_secs());
+        let flags = (state.ready.load(Ordering::SeqCst), state.live.load(Ordering::SeqCst));
+        let task = tokio::spawn(async move {
+            for (at, action) in steps {
+                tokio::time::sleep_until(at.into()).await;
+                state.scenario.fire(&state, &action);
+            }
+            tokio::time::sleep_until(end.into()).await;
+            state.scenario.finish();
+        });
+        *run = Some(Run { scenario, started, fired: 0, finished: false, flags, task, effects: Vec::new() });
+        SCENARIO_RUNNING.set(1);
+        Ok(())
+    }
+
+    /// Aborts the timeline and any running effects and puts ready/live back as they were
+    /// when the scenario started. Leaked memory is kept.
+    pub fn stop(&self, state: &AppState) -> Option<String> {
+        let mut run = self.run.lock();
+        let r = run.as_mut().filter(|r| !r.finished)?;
+        r.task.abort();
+        for e in r.effects.drain(..) { e.abort(); }
+        r.finished = true;
+        *self.unstable.lock() = None;
+        state.ready.store(r.flags.0, Ordering::SeqCst);
+        state.live.store(r.flags.1, Ordering::SeqCst);
+        SCENARIO_RUNNING.set(0);
+        warn!("Scenario '{}': stopped", r.scenario.name);
+        Some(r.scenario.name.clone())
+    }
+
+    fn finish(&self) {
+        if let Some(r) = self.run.lock().as_mut() {
+            r.finished = true;
+            info!("Scenario '{}': finished", r.scenario.name);
+        }
+        SCENARIO_RUNNING.set(0);
+    }
+
+    fn fire(&self, state: &AppState, action: &Action) {
+        info!("Scenario: {:?}", action);
+        SCENARIO_STEPS.with_label_values(&[action.name()]).inc();
+        let effect = match action.clone() {
+            Action::Cpu { seconds } => Some(tokio::spawn(crate::burn_cpu(seconds))),
+            Action::Mem { mb, leak, touch } => {
+                let state = state.clone();
+                Some(tokio::task::spawn_blocking(move || {
+                    if let Err(e) = crate::memory::allocate(&state, mb, leak, touch) {
+                        warn!("Scenario: {}", e);
+                    }
+                }))
+            }
+            Action::Unstable { error_rate, latency_ms, duration } => {
+                // `start` already checked that the scenario end fits in an Instant
+                if let Some(until) = Instant::now().checked_add(duration) {
+                    *self.unstable.lock() = Some(UnstableOverride { error_rate, latency_ms, until });
+                }
+                None
+            }
+            Action::Ready { value } => { state.ready.store(value, Ordering::SeqCst); None }
+            Action::Live { value } => { state.live.store(value, Ordering::SeqCst); None }
+        };
+        if let Some(r) = self.run.lock().as_mut() {
+            r.fired += 1;
+            if let Some(e) = effect {
+                r.effects.retain(|h| !h.is_finished());
+                r.effects.push(e);
+            }
+        }
+    }
+
+    fn progress(&self) -> Progress {
+        let run = self.run.lock();
+        let Some(r) = run.as_ref() else {
+            return Progress { name: None, running: false, elapsed_s: 0.0, total_s: 0.0, steps: Vec::new() };
+        };
+        let elapsed = r.started.elapsed();
+        let total = r.scenario.total().unwrap_or(Duration::MAX).as_secs_f64();
+        let steps = r.scenario.steps.iter().enumerate().map(|(i, s)| {
+            let state = if i >= r.fired {
+                if r.finished { "skipped" } else { "pending" }
+            } else if !r.finished && elapsed < s.at.saturating_add(s.action.duration()) {
+                "active"
+            } else {
+                "done"
+            };
+            StepProgress { at_s: s.at.as_secs_f64(), action: s.action.name(), detail: format!("{:?}", s.action), state }
+        }).collect();
+        Progress {
+            name: Some(r.scenario.name.clone()),
+            running: !r.finished,
+            elapsed_s: if r.finished { total.min(elapsed.as_secs_f64()) } else { elapsed.as_secs_f64() },
+            total_s: total,
+            steps,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Progress {
+    name: Option<String>,
+    running: bool,
+    elapsed_s: f64,
+    total_s: f64,
+    steps: Vec<StepProgress>,
+}
+
+#[derive(Serialize)]
+struct StepProgress {
+    at_s: f64,
+    action: &'static str,
+    detail: String,
+    state: &'static str,
+}
+
+// ---------- Handlers ----------
+pub async fn status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.scenario.progress())
+}
+
+/// Starts the scenario sent as the request body (YAML or JSON), or `scenario_file` if the body is empty.
+pub async fn start(State(state): State<AppState>, body: Bytes) -> impl IntoResponse {
+    let parsed = if body.is_empty() {
+        match state.config.get().scenario_file {
+            Some(path) => Scenario::load(&path),
+            None => Err(anyhow::anyhow!("empty body and SCENARIO_FILE is not set")),
+        }
+    } else {
+        Scenario::parse(&body)
+    };
+    let scenario = match parsed {
+        Ok(s) => s,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    match state.scenario.start(state.clone(), scenario) {
+        Ok(()) => (StatusCode::ACCEPTED, Json(state.scenario.progress())).into_response(),
+        Err(e) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+    }
+}
+
+pub async fn stop(State(state): State<AppState>) -> impl IntoResponse {
+    match state.scenario.stop(&state) {
+        Some(name) => (StatusCode::OK, format!("scenario '{name}' stopped")),
+        None => (StatusCode::NOT_FOUND, "no scenario running".to_string()),
+    }
+}
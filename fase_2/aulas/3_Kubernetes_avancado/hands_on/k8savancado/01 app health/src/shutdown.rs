@@ -0,0 +1,166 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use lazy_static::lazy_static;
+use prometheus::{register_gauge_vec, register_int_gauge, register_int_gauge_vec, GaugeVec, IntGauge, IntGaugeVec};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+use tokio::sync::{watch, Notify};
+use tracing::{info, warn};
+
+use crate::AppState;
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref PHASE: IntGaugeVec = register_int_gauge_vec!(
+        "shutdown_phase",
+        "Current lifecycle phase (1 = active)",
+        &["phase"]
+    ).unwrap();
+
+    static ref PHASE_SECONDS: GaugeVec = register_gauge_vec!(
+        "shutdown_phase_duration_seconds",
+        "How long each completed shutdown phase took",
+        &["phase"]
+    ).unwrap();
+
+    static ref INFLIGHT_AT_DRAIN: IntGauge = register_int_gauge!(
+        "shutdown_inflight_at_drain",
+        "In-flight requests when the listener stopped accepting connections"
+    ).unwrap();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// Serving normally.
+    Running,
+    /// Readiness is false, but the listener still accepts while endpoints propagate.
+    PreStop,
+    /// Listener closed; waiting for in-flight requests.
+    Draining,
+    /// Done; the process is about to exit.
+    Stopped,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] = [Phase::Running, Phase::PreStop, Phase::Draining, Phase::Stopped];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Running => "running",
+            Phase::PreStop => "pre_stop",
+            Phase::Draining => "draining",
+            Phase::Stopped => "stopped",
+        }
+    }
+}
+
+/// Shutdown coordination shared by the signal handler, the admin trigger and the server.
+pub struct Shutdown {
+    phase: watch::Sender<Phase>,
+    trigger: Notify,
+    /// Requests in flight on the routers built from this state (what draining waits for).
+    pub(crate) in_flight: AtomicUsize,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        let (phase, _) = watch::channel(Phase::Running);
+        PHASE.with_label_values(&[Phase::Running.as_str()]).set(1);
+        Self { phase, trigger: Notify::new(), in_flight: AtomicUsize::new(0) }
+    }
+}
+
+impl Shutdown {
+    pub fn phase(&self) -> Phase { *self.phase.borrow() }
+
+    pub fn in_flight(&self) -> usize { self.in_flight.load(Ordering::SeqCst) }
+
+    /// Starts the shutdown sequence as if SIGTERM had been received.
+    pub fn trigger(&self) { self.trigger.notify_one(); }
+
+    /// Resolves once the sequence reaches `phase` (used as axum's graceful shutdown signal).
+    pub async fn reached(&self, phase: Phase) {
+        let mut rx = self.phase.subscribe();
+        let _ = rx.wait_for(|p| *p >= phase).await;
+    }
+
+    fn enter(&self, next: Phase, since: Instant) {
+        let prev = self.phase();
+        PHASE_SECONDS.with_label_values(&[prev.as_str()]).set(since.elapsed().as_secs_f64());
+        for p in Phase::ALL {
+            PHASE.with_label_values(&[p.as_str()]).set((p == next) as i64);
+        }
+        info!("Shutdown: {} -> {} (in flight: {})", prev.as_str(), next.as_str(), self.in_flight());
+        self.phase.send_replace(next);
+    }
+
+    // SIGTERM (what the kubelet sends), SIGINT (Ctrl-C) or /admin/shutdown
+    async fn signalled(&self) -> &'static str {
+        #[cfg(unix)]
+        let term = async {
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                Ok(mut s) => { s.recv().await; }
+                Err(e) => { warn!("cannot listen for SIGTERM: {}", e); std::future::pending::<()>().await }
+            }
+        };
+        #[cfg(not(unix))]
+        let term = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = term => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = self.trigger.notified() => "/admin/shutdown",
+        }
+    }
+}
+
+/// Waits for a shutdown request and walks through the phases:
+/// readiness off → `pre_stop` delay → stop accepting → drain in-flight requests (bounded) → stopped.
+/// Returns when the process may exit; `server` should be the task running `axum::serve`.
+/// The delays are read from the live config when the signal arrives, so a reload still applies.
+pub async fn run(state: AppState, server: tokio::task::JoinHandle<std::io::Result<()>>) -> anyhow::Result<()> {
+    let shutdown = state.shutdown.clone();
+    let started = Instant::now();
+    let source = shutdown.signalled().await;
+    let cfg = state.config.get();
+    let (pre_stop_delay, drain_timeout) = (cfg.pre_stop_delay, cfg.drain_timeout);
+    warn!("Received {}; setting ready=false (pre-stop {:?}, drain timeout {:?})", source, pre_stop_delay, drain_timeout);
+    state.ready.store(false, Ordering::SeqCst);
+
+    let t = Instant::now();
+    shutdown.enter(Phase::PreStop, started);
+    tokio::time::sleep(pre_stop_delay).await;
+
+    let t_drain = Instant::now();
+    shutdown.enter(Phase::Draining, t);
+    INFLIGHT_AT_DRAIN.set(shutdown.in_flight() as i64);
+    let deadline = tokio::time::Instant::now() + drain_timeout;
+    while shutdown.in_flight() > 0 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let remaining = shutdown.in_flight();
+    if remaining > 0 {
+        warn!("Shutdown: drain timeout ({:?}) with {} requests still in flight", drain_timeout, remaining);
+    } else {
+        info!("Shutdown: all in-flight requests drained in {:?}", t_drain.elapsed());
+    }
+
+    // Idle keep-alive connections are closed by axum; don't wait past the deadline for them
+    let result = match tokio::time::timeout_at(deadline, server).await {
+        Ok(joined) => joined?.map_err(anyhow::Error::from),
+        Err(_) => Ok(()),
+    };
+    shutdown.enter(Phase::Stopped, t_drain);
+    info!("Shutdown: complete in {:?}", t.elapsed());
+    result
+}
+
+// ---------- Handlers ----------
+pub async fn trigger(State(state): State<AppState>) -> impl IntoResponse {
+    if state.shutdown.phase() != Phase::Running {
+        return (StatusCode::CONFLICT, "shutdown already in progress");
+    }
+    state.shutdown.trigger();
+    (StatusCode::ACCEPTED, "shutdown triggered")
+}
@@ -0,0 +1,351 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::{core::Collector, register_gauge_vec, GaugeVec};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{config::ser_duration, scenario::de_duration, AppState, ERR_TOTAL, REQS_TOTAL, REQ_DURATION};
+
+// ---------- Metrics ----------
+lazy_static! {
+    static ref SLI: GaugeVec = register_gauge_vec!(
+        "slo_sli_ratio",
+        "Fraction of good requests over the window (1 when there was no traffic)",
+        &["slo", "window"]
+    ).unwrap();
+
+    static ref BURN_RATE: GaugeVec = register_gauge_vec!(
+        "slo_burn_rate",
+        "Error budget burn rate over the window (1 = spending exactly the budget)",
+        &["slo", "window"]
+    ).unwrap();
+
+    static ref OBJECTIVE: GaugeVec = register_gauge_vec!(
+        "slo_objective_ratio",
+        "Configured objective (target fraction of good requests)",
+        &["slo"]
+    ).unwrap();
+
+    static ref ALERT: GaugeVec = register_gauge_vec!(
+        "slo_alert_firing",
+        "1 while the multi-window burn-rate condition holds",
+        &["slo", "severity"]
+    ).unwrap();
+}
+
+/// Rolling windows the SLIs are computed over.
+pub const WINDOWS: [(&str, Duration); 3] = [
+    ("5m", Duration::from_secs(5 * 60)),
+    ("1h", Duration::from_secs(60 * 60)),
+    ("6h", Duration::from_secs(6 * 60 * 60)),
+];
+
+/// Multi-window burn-rate alerts (SRE workbook): both windows must exceed the factor.
+/// 14.4x for 1h spends 2% of a 30-day budget; 6x for 6h spends 5%.
+const ALERTS: [Alert; 2] = [
+    Alert { severity: "page", long: "1h", short: "5m", factor: 14.4 },
+    Alert { severity: "ticket", long: "6h", short: "1h", factor: 6.0 },
+];
+
+struct Alert {
+    severity: &'static str,
+    long: &'static str,
+    short: &'static str,
+    factor: f64,
+}
+
+/// Probes, scrapes, operator actions and this endpoint are not user traffic.
+/// Used as-is in the rules' `path!~`, so keep to literals and a trailing `.*`, which `counted` understands too.
+const EXCLUDED_PATHS: &str = "/health/.*|/metrics|/slo.*|/admin/.*|unmatched";
+
+fn counted(path: &str) -> bool {
+    // PromQL regexes are anchored: a literal matches the whole path, `x.*` any path starting with `x`
+    !EXCLUDED_PATHS.split('|').any(|alt| match alt.strip_suffix(".*") {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == alt,
+    })
+}
+
+// ---------- Config ----------
+/// The `slo` section of the config file; `SLO_*` variables override its keys.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SloConfig {
+    /// Target fraction of requests that are not 5xx.
+    pub availability: f64,
+    /// A request is fast when it completes within this many seconds (a histogram bucket bound).
+    pub latency_threshold: f64,
+    /// Target fraction of fast requests.
+    pub latency_target: f64,
+    /// How often the request counters are sampled into the windows.
+    #[serde(deserialize_with = "de_duration", serialize_with = "ser_duration")]
+    pub sample_interval: Duration,
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            availability: 0.99,
+            latency_threshold: 0.5,
+            latency_target: 0.95,
+            sample_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl SloConfig {
+    /// `buckets` are the `http_request_duration_seconds` bounds the threshold must be one of.
+    pub(crate) fn validate(&self, buckets: &[f64], errors: &mut Vec<String>) {
+        for (name, ratio) in [("availability", self.availability), ("latency_target", self.latency_target)] {
+            if !(ratio > 0.0 && ratio < 1.0) {
+                errors.push(format!("slo.{name} ({ratio}) must be > 0 and < 1"));
+            }
+        }
+        // only a bucket bound can be read back exactly, in-process and in PromQL
+        if !buckets.contains(&self.latency_threshold) {
+            errors.push(format!(
+                "slo.latency_threshold ({}) must be one of the histogram buckets {buckets:?} (see http_duration_buckets)",
+                self.latency_threshold
+            ));
+        }
+        if self.sample_interval.is_zero() {
+            errors.push("slo.sample_interval must be > 0".to_string());
+        }
+    }
+}
+
+// ---------- Tracker ----------
+/// Cumulative request counts for the routes the SLOs cover.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Counts {
+    pub total: f64,
+    pub errors: f64,
+    /// Requests that completed within the latency threshold.
+    pub fast: f64,
+}
+
+impl Counts {
+    /// Reads the current values of `http_requests_total`, `http_errors_total` and `http_request_duration_seconds`.
+    pub fn current(latency_threshold: f64) -> Self {
+        let mut c = Counts::default();
+        let paths = |family: &prometheus::proto::MetricFamily| -> Vec<prometheus::proto::Metric> {
+            family.get_metric().iter()
+                .filter(|m| m.get_label().iter().any(|l| l.get_name() == "path" && counted(l.get_value())))
+                .cloned()
+                .collect()
+        };
+        for family in REQS_TOTAL.collect() {
+            c.total += paths(&family).iter().map(|m| m.get_counter().get_value()).sum::<f64>();
+        }
+        for family in ERR_TOTAL.collect() {
+            c.errors += paths(&family).iter().map(|m| m.get_counter().get_value()).sum::<f64>();
+        }
+        for family in REQ_DURATION.collect() {
+            for m in paths(&family) {
+                c.fast += m.get_histogram().get_bucket().iter()
+                    .find(|b| b.get_upper_bound() == latency_threshold)
+                    .map_or(0, |b| b.get_cumulative_count()) as f64;
+            }
+        }
+        c
+    }
+}
+
+/// Keeps periodic samples of [`Counts`] for the longest window and derives SLIs and burn rates from them.
+pub struct SloTracker {
+    cfg: SloConfig,
+    samples: parking_lot::Mutex<VecDeque<(Instant, Counts)>>,
+}
+
+impl Default for SloTracker {
+    fn default() -> Self {
+        Self::new(SloConfig::default())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WindowReport {
+    pub window: &'static str,
+    /// Good / total over the window; 1 without traffic.
+    pub sli: f64,
+    pub burn_rate: f64,
+    pub requests: f64,
+    pub bad: f64,
+    /// How much of the window there is data for (less than the window right after startup).
+    pub coverage_seconds: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AlertReport {
+    pub severity: &'static str,
+    pub windows: [&'static str; 2],
+    pub factor: f64,
+    pub firing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SloReport {
+    pub objective: f64,
+    /// Allowed fraction of bad requests (1 - objective).
+    pub error_budget: f64,
+    /// In [`WINDOWS`] order.
+    pub windows: Vec<WindowReport>,
+    pub alerts: Vec<AlertReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub config: SloConfig,
+    pub availability: SloReport,
+    pub latency: SloReport,
+}
+
+impl SloTracker {
+    pub fn new(cfg: SloConfig) -> Self {
+        OBJECTIVE.with_label_values(&["availability"]).set(cfg.availability);
+        OBJECTIVE.with_label_values(&["latency"]).set(cfg.latency_target);
+        // the counters start at zero with the process
+        Self { cfg, samples: parking_lot::Mutex::new(VecDeque::from([(Instant::now(), Counts::default())])) }
+    }
+
+    pub fn config(&self) -> &SloConfig {
+        &self.cfg
+    }
+
+    /// Adds a sample taken at `at`, dropping those no window reaches any more.
+    pub fn record(&self, at: Instant, counts: Counts) {
+        let longest = WINDOWS[WINDOWS.len() - 1].1;
+        let mut samples = self.samples.lock();
+        samples.push_back((at, counts));
+        // keep one sample at or before the start of the longest window as its baseline
+        while samples.len() > 1 && at.duration_since(samples[1].0) >= longest {
+            samples.pop_front();
+        }
+    }
+
+    /// SLIs, burn rates and alert states at `now`, given the counts at `now`.
+    pub fn report(&self, now: Instant, counts: Counts) -> Report {
+        let samples = self.samples.lock();
+        let windows: Vec<(&'static str, Counts, Duration)> = WINDOWS.iter().map(|(name, len)| {
+            // the latest sample at or before the window start, else the oldest one we have
+            let start = now.checked_sub(*len);
+            let base = samples.iter().rev()
+                .find(|(t, _)| start.is_some_and(|s| *t <= s))
+                .or(samples.front())
+                .copied()
+                .unwrap_or((now, counts));
+            let delta = Counts {
+                total: counts.total - base.1.total,
+                errors: counts.errors - base.1.errors,
+                fast: counts.fast - base.1.fast,
+            };
+            (*name, delta, now.saturating_duration_since(base.0).min(*len))
+        }).collect();
+        Report {
+            config: self.cfg.clone(),
+            availability: slo_report(self.cfg.availability, &windows, |d| d.errors),
+            latency: slo_report(self.cfg.latency_target, &windows, |d| d.total - d.fast),
+        }
+    }
+}
+
+fn slo_report(objective: f64, windows: &[(&'static str, Counts, Duration)], bad: impl Fn(&Counts) -> f64) -> SloReport {
+    let budget = 1.0 - objective;
+    let windows: Vec<_> = windows.iter().map(|(name, delta, coverage)| {
+        let bad = bad(delta).max(0.0);
+        let bad_ratio = if delta.total > 0.0 { bad / delta.total } else { 0.0 };
+        WindowReport {
+            window: name,
+            sli: 1.0 - bad_ratio,
+            burn_rate: bad_ratio / budget,
+            requests: delta.total,
+            bad,
+            coverage_seconds: coverage.as_secs(),
+        }
+    }).collect();
+    let burn = |window: &str| windows.iter().find(|w| w.window == window).map_or(0.0, |w| w.burn_rate);
+    let alerts = ALERTS.iter().map(|a| AlertReport {
+        severity: a.severity,
+        windows: [a.long, a.short],
+        factor: a.factor,
+        firing: burn(a.long) > a.factor && burn(a.short) > a.factor,
+    }).collect();
+    SloReport { objective, error_budget: budget, windows, alerts }
+}
+
+fn set_gauges(report: &Report) {
+    for (slo, r) in [("availability", &report.availability), ("latency", &report.latency)] {
+        for w in &r.windows {
+            SLI.with_label_values(&[slo, w.window]).set(w.sli);
+            BURN_RATE.with_label_values(&[slo, w.window]).set(w.burn_rate);
+        }
+        for a in &r.alerts {
+            ALERT.with_label_values(&[slo, a.severity]).set(if a.firing { 1.0 } else { 0.0 });
+        }
+    }
+}
+
+/// Samples the request counters every `sample_interval` and refreshes the `slo_*` gauges.
+pub fn spawn_sampler(tracker: Arc<SloTracker>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(tracker.cfg.sample_interval);
+        loop {
+            ticker.tick().await;
+            let (now, counts) = (Instant::now(), Counts::current(tracker.cfg.latency_threshold));
+            tracker.record(now, counts);
+            set_gauges(&tracker.report(now, counts));
+        }
+    });
+}
+
+// ---------- Prometheus rules ----------
+/// Recording and alerting rules computing the same SLIs and burn rates in Prometheus.
+pub fn rules_yaml(cfg: &SloConfig) -> String {
+    let sel = format!(r#"job="app-health-demo",path!~"{EXCLUDED_PATHS}""#);
+    let mut y = String::from("groups:\n  - name: app-health-demo-slo\n    rules:\n");
+    for (w, _) in WINDOWS {
+        let _ = write!(y, "      - record: slo:http_error_ratio:rate{w}\n        expr: \
+            sum(rate(http_errors_total{{{sel}}}[{w}])) / sum(rate(http_requests_total{{{sel}}}[{w}]))\n");
+        let _ = write!(y, "      - record: slo:http_slow_ratio:rate{w}\n        expr: \
+            1 - sum(rate(http_request_duration_seconds_bucket{{{sel},le=\"{}\"}}[{w}])) \
+            / sum(rate(http_request_duration_seconds_count{{{sel}}}[{w}]))\n", cfg.latency_threshold);
+    }
+    let slos = [
+        ("Availability", "error", cfg.availability, "5xx responses"),
+        ("Latency", "slow", cfg.latency_target, &*format!("requests slower than {}s", cfg.latency_threshold)),
+    ];
+    for (name, ratio, objective, what) in slos {
+        for a in &ALERTS {
+            let (long, short, factor, severity) = (a.long, a.short, a.factor, a.severity);
+            let burn = if severity == "page" { "Fast" } else { "Slow" };
+            let _ = write!(y, "      - alert: AppHealth{name}{burn}Burn\n        expr: \
+                slo:http_{ratio}_ratio:rate{long} > ({factor} * (1 - {objective})) \
+                and slo:http_{ratio}_ratio:rate{short} > ({factor} * (1 - {objective}))\n        \
+                labels:\n          severity: {severity}\n          slo: {}\n        annotations:\n          \
+                summary: \"{what} burning the {objective} objective's error budget at more than {factor}x over {long} and {short}\"\n",
+                name.to_lowercase());
+        }
+    }
+    y
+}
+
+// ---------- Handlers ----------
+pub async fn show(State(state): State<AppState>) -> impl IntoResponse {
+    let report = state.slo.report(Instant::now(), Counts::current(state.slo.cfg.latency_threshold));
+    set_gauges(&report);
+    Json(report)
+}
+
+pub async fn rules(State(state): State<AppState>) -> impl IntoResponse {
+    (StatusCode::OK, [(header::CONTENT_TYPE, "application/yaml")], rules_yaml(state.slo.config()))
+}
@@ -0,0 +1,223 @@
+use axum::{extract::MatchedPath, http::Request};
+use lazy_static::lazy_static;
+use opentelemetry::{
+    trace::{TraceContextExt, TracerProvider as _},
+    KeyValue,
+};
+use opentelemetry_http::HeaderExtractor;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracerProvider},
+    Resource,
+};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+use crate::config::Config;
+
+// ---------- Setup ----------
+/// Installs fmt logging plus an OpenTelemetry layer.
+///
+/// Spans are always created (so `traceparent` is honoured and exemplars carry trace IDs);
+/// they are exported over OTLP only when `otel_exporter_otlp_endpoint` or
+/// `otel_exporter_otlp_traces_endpoint` is set. `otel_exporter_otlp_protocol`
+/// (`grpc` or `http/protobuf`) picks the transport; the service name, resource attributes,
+/// sampler and `otel_sdk_disabled` come from the config as well (the `OTEL_*` variables override them).
+///
+/// `log_level` (EnvFilter directives) wins over `RUST_LOG` and can be changed later with [`set_log_level`].
+pub fn init(cfg: &Config) -> anyhow::Result<Option<SdkTracerProvider>> {
+    let fmt = tracing_subscriber::fmt::layer().with_target(false);
+    let (filter, handle) = reload::Layer::new(log_filter(cfg.log_level.as_deref()).map_err(anyhow::Error::msg)?);
+    let _ = LOG_FILTER.set(handle);
+
+    if cfg.otel_sdk_disabled {
+        tracing_subscriber::registry().with(filter).with(fmt).init();
+        return Ok(None);
+    }
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let mut resource = Resource::builder()
+        .with_service_name(cfg.otel_service_name.clone().unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string()));
+    for (key, value) in cfg.otel_resource_attributes.iter().flat_map(|a| a.split(',')).filter_map(|kv| kv.split_once('=')) {
+        resource = resource.with_attribute(KeyValue::new(key.trim().to_string(), value.trim().to_string()));
+    }
+    let mut provider = SdkTracerProvider::builder().with_resource(resource.build());
+    if let Some(name) = &cfg.otel_traces_sampler {
+        let sampler = sampler(name, cfg.otel_traces_sampler_arg)
+            .ok_or_else(|| anyhow::anyhow!("unknown otel_traces_sampler {name:?}"))?;
+        provider = provider.with_sampler(sampler);
+    }
+
+    let protocol = cfg.otel_exporter_otlp_protocol.as_str();
+    let endpoint = match (&cfg.otel_exporter_otlp_traces_endpoint, &cfg.otel_exporter_otlp_endpoint) {
+        (Some(ep), _) => Some(ep.clone()),
+        (None, Some(ep)) if protocol == "http/protobuf" => Some(format!("{}/v1/traces", ep.trim_end_matches('/'))),
+        (None, ep) => ep.clone(),
+    };
+    if let Some(ep) = &endpoint {
+        use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+        provider = match protocol {
+            "grpc" => provider.with_batch_exporter(SpanExporter::builder().with_tonic().with_endpoint(ep).build()?),
+            "http/protobuf" => provider.with_batch_exporter(SpanExporter::builder().with_http().with_endpoint(ep).build()?),
+            other => anyhow::bail!("unsupported otel_exporter_otlp_protocol {other:?} (use grpc or http/protobuf)"),
+        };
+    }
+    let provider = provider.build();
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+    opentelemetry::global::set_tracer_provider(provider.clone());
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .init();
+    if let Some(ep) = endpoint {
+        info!("Tracing: exporting spans over OTLP ({}) to {}", protocol, ep);
+    }
+    Ok(Some(provider))
+}
+
+/// The sampler named like `OTEL_TRACES_SAMPLER`; `None` for an unknown name.
+pub fn sampler(name: &str, arg: Option<f64>) -> Option<Sampler> {
+    let ratio = || Sampler::TraceIdRatioBased(arg.unwrap_or(1.0));
+    Some(match name {
+        "always_on" => Sampler::AlwaysOn,
+        "always_off" => Sampler::AlwaysOff,
+        "traceidratio" => ratio(),
+        "parentbased_always_on" => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+        "parentbased_always_off" => Sampler::ParentBased(Box::new(Sampler::AlwaysOff)),
+        "parentbased_traceidratio" => Sampler::ParentBased(Box::new(ratio())),
+        _ => return None,
+    })
+}
+
+static LOG_FILTER: once_cell::sync::OnceCell<reload::Handle<EnvFilter, Registry>> = once_cell::sync::OnceCell::new();
+
+// `RUST_LOG` (or the EnvFilter default) when no level is configured
+fn log_filter(level: Option<&str>) -> Result<EnvFilter, String> {
+    match level {
+        Some(level) => EnvFilter::try_new(level).map_err(|e| format!("log_level {level:?}: {e}")),
+        None => Ok(EnvFilter::from_default_env()),
+    }
+}
+
+/// Swaps the log filter at runtime (config reload); `None` goes back to `RUST_LOG`.
+pub fn set_log_level(level: Option<&str>) -> Result<(), String> {
+    let filter = log_filter(level)?;
+    match LOG_FILTER.get() {
+        Some(handle) => handle.reload(filter).map_err(|e| e.to_string()),
+        // tests and tools that never called `init`
+        None => Ok(()),
+    }
+}
+
+/// Request span for `TraceLayer`, continuing the caller's trace from `traceparent`.
+/// Named after the route template, like the metrics, so IDs in paths don't make one span name each.
+pub fn make_span<B>(req: &Request<B>) -> Span {
+    let route = req.extensions().get::<MatchedPath>().map_or("unmatched", |p| p.as_str());
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %format!("{} {}", req.method(), route),
+        otel.kind = "server",
+        http.request.method = %req.method(),
+        http.route = route,
+        url.path = %req.uri().path(),
+        http.response.status_code = tracing::field::Empty,
+    );
+    let parent = opentelemetry::global::get_text_map_propagator(|p| p.extract(&HeaderExtractor(req.headers())));
+    let _ = span.set_parent(parent);
+    span
+}
+
+/// Trace ID of the current span, if it is sampled.
+pub fn current_trace_id() -> Option<String> {
+    let cx = Span::current().context();
+    let span = cx.span();
+    let sc = span.span_context();
+    (sc.is_valid() && sc.is_sampled()).then(|| sc.trace_id().to_string())
+}
+
+// ---------- Exemplars ----------
+struct Exemplar {
+    trace_id: String,
+    value: f64,
+    timestamp: f64,
+}
+
+lazy_static! {
+    // Latest exemplar per histogram bucket, keyed by the bucket's rendered label set
+    static ref EXEMPLARS: parking_lot::Mutex<HashMap<String, Exemplar>> = parking_lot::Mutex::new(HashMap::new());
+}
+
+/// Remembers `trace_id` as the exemplar of the bucket `value` falls into.
+/// `labels` must be sorted by name, as the Prometheus encoder renders them.
+pub fn record_exemplar(metric: &str, labels: &[(&str, &str)], buckets: &[f64], value: f64, trace_id: String) {
+    let le = buckets.iter().find(|b| value <= **b).map(|b| b.to_string()).unwrap_or_else(|| "+Inf".to_string());
+    let mut key = format!("{metric}_bucket{{");
+    for (k, v) in labels {
+        key.push_str(&format!("{k}=\"{v}\","));
+    }
+    key.push_str(&format!("le=\"{le}\"}}"));
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0);
+    EXEMPLARS.lock().insert(key, Exemplar { trace_id, value, timestamp });
+}
+
+/// Whether a scrape asked for OpenMetrics, the only format that can carry exemplars.
+pub fn wants_openmetrics(accept: Option<&str>) -> bool {
+    accept.is_some_and(|a| a.contains("application/openmetrics-text"))
+}
+
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Rewrites Prometheus text exposition as OpenMetrics and attaches exemplars to buckets.
+pub fn to_openmetrics(text: &str) -> String {
+    let exemplars = EXEMPLARS.lock();
+    let mut out = String::with_capacity(text.len() + 64);
+    let mut counter_family: Option<String> = None;
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("# TYPE ") {
+            let (name, kind) = rest.split_once(' ').unwrap_or((rest, "unknown"));
+            counter_family = None;
+            match kind {
+                // OpenMetrics counter families are named without the `_total` suffix
+                "counter" => {
+                    let family = name.strip_suffix("_total").unwrap_or(name).to_string();
+                    out.push_str(&format!("# TYPE {family} counter\n"));
+                    counter_family = Some(family);
+                }
+                "untyped" => out.push_str(&format!("# TYPE {name} unknown\n")),
+                _ => { out.push_str(line); out.push('\n'); }
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("# HELP ") {
+            let (name, help) = rest.split_once(' ').unwrap_or((rest, ""));
+            let name = name.strip_suffix("_total").filter(|_| text.contains(&format!("# TYPE {name} counter"))).unwrap_or(name);
+            out.push_str(&format!("# HELP {name} {help}\n"));
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        match &counter_family {
+            Some(family) if !line.starts_with(&format!("{family}_total")) => {
+                out.push_str(&format!("{family}_total{}", &line[family.len()..]));
+            }
+            _ => out.push_str(line),
+        }
+        if let Some((series, _)) = line.rsplit_once(' ') {
+            if let Some(e) = exemplars.get(series) {
+                out.push_str(&format!(" # {{trace_id=\"{}\"}} {} {:.3}", e.trace_id, e.value, e.timestamp));
+            }
+        }
+        out.push('\n');
+    }
+    out.push_str("# EOF\n");
+    out
+}
@@ -0,0 +1,65 @@
+#![allow(dead_code)]
+
+use app_health_demo::{admin::AdminAuth, app, AppState};
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tower::ServiceExt;
+
+pub const TOKEN: &str = "test-token";
+
+/// Single-listener app with the admin routes behind `TOKEN`.
+pub fn router(state: &AppState) -> Router {
+    app(state, Arc::new(AdminAuth::new(Some(TOKEN.to_string()), false)))
+}
+
+/// Sends one in-process request (with the admin token) and returns status and body.
+pub async fn send(router: &Router, method: Method, uri: &str) -> (StatusCode, String) {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {TOKEN}"))
+        .body(Body::empty())
+        .unwrap();
+    respond(router, req).await
+}
+
+/// Like `send`, with `json` as the request body.
+pub async fn send_json(router: &Router, method: Method, uri: &str, json: serde_json::Value) -> (StatusCode, String) {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {TOKEN}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json.to_string()))
+        .unwrap();
+    respond(router, req).await
+}
+
+async fn respond(router: &Router, req: Request<Body>) -> (StatusCode, String) {
+    let res = router.clone().oneshot(req).await.unwrap();
+    let status = res.status();
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+pub async fn get(router: &Router, uri: &str) -> StatusCode {
+    send(router, Method::GET, uri).await.0
+}
+
+/// Minimal HTTP/1.1 GET (with the admin token) over a real socket, for tests that need the listener.
+pub async fn http_get(addr: SocketAddr, path: &str) -> std::io::Result<(u16, String)> {
+    let mut stream = tokio::net::TcpStream::connect(addr).await?;
+    let request = format!("GET {path} HTTP/1.1\r\nHost: test\r\nAuthorization: Bearer {TOKEN}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).await?;
+    let status = raw.split(' ').nth(1).and_then(|s| s.parse().ok())
+        .ok_or_else(|| std::io::Error::other(format!("bad response: {raw:?}")))?;
+    let body = raw.split_once("\r\n\r\n").map(|(_, b)| b.to_string()).unwrap_or_default();
+    Ok((status, body))
+}
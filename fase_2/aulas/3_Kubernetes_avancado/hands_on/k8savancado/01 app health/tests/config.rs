@@ -0,0 +1,131 @@
+mod common;
+
+use app_health_demo::{
+    config::{self, Config, ConfigStore},
+    AppState,
+};
+use axum::http::{Method, StatusCode};
+use common::{router, send};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+fn env<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+}
+
+fn write_config(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("app-health-{}-{name}.yaml", std::process::id()));
+    std::fs::write(&path, text).unwrap();
+    path
+}
+
+#[test]
+fn env_overrides_file_which_overrides_defaults() {
+    let file = "port: 9000\ndrain_timeout: 30s\nready_after_ms: 100\n";
+    let cfg = Config::from_sources(Some(file), env(&[("PORT", "9100"), ("PRE_STOP_DELAY", "500ms")])).unwrap();
+    assert_eq!(cfg.port, 9100);
+    assert_eq!(cfg.drain_timeout, Duration::from_secs(30));
+    assert_eq!(cfg.pre_stop_delay, Duration::from_millis(500));
+    assert_eq!(cfg.ready_after_ms, 100);
+    assert_eq!(cfg.startup_delay_ms, Config::default().startup_delay_ms);
+}
+
+#[test]
+fn invalid_settings_are_all_reported() {
+    let file = "health_check_interval_ms: 100\nhealth_check_timeout_ms: 500\n\
+                faults:\n  - path: /info\n    fault: error\n    probability: 2\n";
+    let errors = Config::from_sources(Some(file), env(&[("PORT", "http"), ("DRAIN_TIMEOUT", "soon")])).unwrap_err();
+    assert_eq!(errors.len(), 4, "{errors:#?}");
+    assert!(errors.iter().any(|e| e.starts_with("PORT=\"http\"")));
+    assert!(errors.iter().any(|e| e.starts_with("DRAIN_TIMEOUT=\"soon\"")));
+    assert!(errors.iter().any(|e| e.starts_with("health_check_timeout_ms (500)")));
+    assert!(errors.iter().any(|e| e.starts_with("faults[0]: probability")));
+
+    let errors = Config::from_sources(Some("prot: 8080\n"), env(&[])).unwrap_err();
+    assert!(errors[0].contains("unknown field `prot`"), "{errors:?}");
+
+    // durations too large for a Duration are validation errors, not panics
+    let errors = Config::from_sources(Some("drain_timeout: .inf\n"), env(&[])).unwrap_err();
+    assert!(errors[0].contains("invalid duration inf"), "{errors:?}");
+    let errors = Config::from_sources(None, env(&[("PRE_STOP_DELAY", "99999999999999999999h")])).unwrap_err();
+    assert!(errors[0].starts_with("PRE_STOP_DELAY="), "{errors:?}");
+}
+
+#[tokio::test]
+async fn reload_applies_runtime_settings_and_keeps_them_on_error() {
+    let path = write_config("reload", "drain_timeout: 5s\n");
+    let mut state = AppState::default();
+    let cfg = Config::from_sources(Some(&std::fs::read_to_string(&path).unwrap()), env(&[])).unwrap();
+    state.config = Arc::new(ConfigStore::new(cfg, Some(path.clone())));
+    let app = router(&state);
+
+    // reloadable settings are applied in place, others wait for a restart
+    std::fs::write(&path, "drain_timeout: 1s\nport: 9999\nfaults:\n  - path: /info\n    fault: error\n    status: 418\n").unwrap();
+    let (status, body) = send(&app, Method::POST, "/admin/config/reload").await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(state.config.get().drain_timeout, Duration::from_secs(1));
+    assert_eq!(state.config.get().port, 8080);
+    assert_eq!(send(&app, Method::GET, "/info").await.0, StatusCode::IM_A_TEAPOT);
+
+    // a broken file is rejected as a whole
+    std::fs::write(&path, "drain_timeout: 3s\nfaults: nope\n").unwrap();
+    assert!(config::reload(&state).is_err());
+    assert_eq!(state.config.get().drain_timeout, Duration::from_secs(1));
+    let (_, body) = send(&app, Method::GET, "/admin/config").await;
+    let view: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(view["config"]["drain_timeout"], "1000ms");
+    assert!(view["last_reload_error"].as_str().unwrap().contains("faults"));
+
+    // dropping the rules from the file removes the faults it added
+    std::fs::write(&path, "drain_timeout: 1s\n").unwrap();
+    config::reload(&state).unwrap();
+    assert_eq!(send(&app, Method::GET, "/info").await.0, StatusCode::OK);
+    let (_, text) = send(&app, Method::GET, "/metrics").await;
+    assert!(text.contains(r#"config_reloads_total{result="error"} 1"#));
+    assert!(text.contains(r#"config_reloads_total{result="success"} 2"#));
+
+    // a log level removed from the file is a change too (back to RUST_LOG)
+    std::fs::write(&path, "drain_timeout: 1s\nlog_level: debug\n").unwrap();
+    assert_eq!(config::reload(&state).unwrap(), ["log_level"]);
+    std::fs::write(&path, "drain_timeout: 1s\n").unwrap();
+    assert_eq!(config::reload(&state).unwrap(), ["log_level"]);
+    assert_eq!(state.config.get().log_level, None);
+    std::fs::remove_file(path).ok();
+}
+
+#[tokio::test]
+async fn limiter_metrics_admin_and_tracing_settings_come_from_the_file_too() {
+    let file = "admin_port: 8081\nadmin_token: s3cret\nadmin_tls_cert: /tls/tls.crt\nadmin_tls_key: /tls/tls.key\n\
+                concurrency_limit: aimd\nconcurrency_limit_max: 50\nconcurrency_limit_latency_target: 200ms\n\
+                http_duration_buckets: [0.1, 0.5, 1]\nmetrics_max_series: 100\nconfig_reload_interval: 30s\n\
+                otel_exporter_otlp_endpoint: http://collector:4317\notel_traces_sampler: parentbased_traceidratio\n\
+                otel_traces_sampler_arg: 0.25\n";
+    let cfg = Config::from_sources(Some(file), env(&[("CONCURRENCY_LIMIT_MAX", "80"), ("OTEL_SERVICE_NAME", "checkout")])).unwrap();
+    assert_eq!((cfg.concurrency_limit.as_str(), cfg.concurrency_limit_max), ("aimd", 80));
+    assert_eq!(cfg.concurrency_limit_latency_target, Duration::from_millis(200));
+    assert_eq!((cfg.http_duration_buckets.as_slice(), cfg.metrics_max_series), ([0.1, 0.5, 1.0].as_slice(), 100));
+    assert_eq!(cfg.config_reload_interval, Duration::from_secs(30));
+    assert_eq!(cfg.otel_service_name.as_deref(), Some("checkout"));
+    let limiter = app_health_demo::limiter::LimiterConfig::from_config(&cfg).unwrap();
+    assert_eq!((limiter.min, limiter.initial, limiter.max), (2, 20, 80));
+
+    let cfg = Config::from_sources(None, env(&[("HTTP_DURATION_BUCKETS", "0.05, 0.25,1"), ("SLO_LATENCY_THRESHOLD", "0.25")])).unwrap();
+    assert_eq!(cfg.http_duration_buckets, [0.05, 0.25, 1.0]);
+    assert!(app_health_demo::limiter::LimiterConfig::from_config(&cfg).is_none());
+
+    let file = "admin_tls_cert: /tls/tls.crt\nadmin_tls_client_ca: /tls/ca.crt\nconcurrency_limit: aimd\n\
+                concurrency_limit_min: 30\nconcurrency_limit_backoff: 1.5\nhttp_duration_buckets: [1, 0.5]\n\
+                otel_exporter_otlp_protocol: http/json\notel_traces_sampler: sometimes\n";
+    let errors = Config::from_sources(Some(file), env(&[("ADMIN_TOKEN", ""), ("METRICS_MAX_SERIES", "lots")])).unwrap_err();
+    for expected in ["METRICS_MAX_SERIES=\"lots\"", "admin_token is empty", "admin_tls_cert and admin_tls_key", "concurrency limits",
+        "concurrency_limit_backoff", "http_duration_buckets", "otel_exporter_otlp_protocol", "otel_traces_sampler"] {
+        assert!(errors.iter().any(|e| e.starts_with(expected)), "no {expected:?} in {errors:#?}");
+    }
+    let errors = Config::from_sources(Some("admin_tls_cert: a.crt\nadmin_tls_key: a.key\n"), env(&[])).unwrap_err();
+    assert!(errors[0].starts_with("admin_tls_* needs admin_port"), "{errors:?}");
+
+    // the token never shows up in /admin/config
+    let mut state = AppState::default();
+    state.config = Arc::new(ConfigStore::new(Config::from_sources(Some("admin_token: s3cret\n"), env(&[])).unwrap(), None));
+    let (_, body) = send(&router(&state), Method::GET, "/admin/config").await;
+    assert!(!body.contains("s3cret"), "{body}");
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use app_health_demo::AppState;
+use axum::http::{Method, StatusCode};
+use common::{router, send, send_json, TOKEN};
+use serde_json::json;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Installs `rule` through the admin API and returns its id.
+async fn install(app: &axum::Router, rule: serde_json::Value) -> u64 {
+    let (code, body) = send_json(app, Method::POST, "/admin/faults", rule).await;
+    assert_eq!(code, StatusCode::CREATED, "{body}");
+    serde_json::from_str::<serde_json::Value>(&body).unwrap()["id"].as_u64().unwrap()
+}
+
+/// Serves `app` on a local port, for faults that only show on the wire.
+async fn serve(app: axum::Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
+    });
+    addr
+}
+
+/// Raw bytes of a GET until the server closes the connection (or resets it).
+async fn raw_get(addr: SocketAddr, path: &str) -> String {
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {path} HTTP/1.1\r\nHost: test\r\nAuthorization: Bearer {TOKEN}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut raw = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while let Ok(n) = stream.read(&mut chunk).await {
+        if n == 0 { break; }
+        raw.extend_from_slice(&chunk[..n]);
+    }
+    String::from_utf8_lossy(&raw).into_owned()
+}
+
+#[tokio::test]
+async fn latency_delays_matching_paths_only() {
+    let app = router(&AppState::default());
+    install(&app, json!({ "path": "/info", "fault": "latency", "ms": 300 })).await;
+
+    let start = Instant::now();
+    let (code, body) = send(&app, Method::GET, "/info").await;
+    assert_eq!(code, StatusCode::OK);
+    assert!(body.contains("app-health-demo"), "the handler still answers: {body}");
+    assert!(start.elapsed() >= Duration::from_millis(300));
+
+    let start = Instant::now();
+    assert_eq!(send(&app, Method::GET, "/health/live").await.0, StatusCode::OK);
+    assert!(start.elapsed() < Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn errors_follow_glob_patterns_and_can_be_removed() {
+    let app = router(&AppState::default());
+    let id = install(&app, json!({ "path": "/simulate/*", "fault": "error", "status": 418 })).await;
+    install(&app, json!({ "path": "*/li*e", "fault": "error" })).await;
+
+    assert_eq!(send(&app, Method::GET, "/simulate/latency?ms=0").await, (StatusCode::IM_A_TEAPOT, "injected fault".into()));
+    assert_eq!(send(&app, Method::GET, "/simulate/error?rate=0").await.0, StatusCode::IM_A_TEAPOT);
+    // status defaults to 503; `*` also spans segments
+    assert_eq!(send(&app, Method::GET, "/health/live").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    // no match: the prefix alone, a different tail, and the admin API itself
+    assert_eq!(send(&app, Method::GET, "/simulate").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::GET, "/health/ready").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(send(&app, Method::GET, "/info").await.0, StatusCode::OK);
+    assert_eq!(send(&app, Method::GET, "/admin/faults").await.0, StatusCode::OK);
+
+    assert_eq!(send(&app, Method::DELETE, &format!("/admin/faults/{id}")).await.0, StatusCode::OK);
+    assert_eq!(send(&app, Method::GET, "/simulate/latency?ms=0").await.0, StatusCode::OK);
+    assert_eq!(send(&app, Method::DELETE, "/admin/faults").await.1, "removed 1 fault rules");
+    assert_eq!(send(&app, Method::GET, "/health/live").await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn invalid_rules_are_rejected() {
+    let app = router(&AppState::default());
+    for rule in [
+        json!({ "path": "/info", "fault": "error", "probability": 2.0 }),
+        json!({ "path": "info", "fault": "reset" }),
+        json!({ "path": "/info", "fault": "error", "status": 1000 }),
+    ] {
+        assert_eq!(send_json(&app, Method::POST, "/admin/faults", rule).await.0, StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(send(&app, Method::GET, "/admin/faults").await.1, "[]");
+}
+
+#[tokio::test]
+async fn reset_and_truncate_break_the_connection() {
+    let app = router(&AppState::default());
+    install(&app, json!({ "path": "/info", "fault": "reset" })).await;
+    install(&app, json!({ "path": "/health/*", "fault": "truncate", "bytes": 3 })).await;
+    let addr = serve(app).await;
+
+    // reset: the connection goes away before even the status line
+    assert_eq!(raw_get(addr, "/info").await, "");
+
+    // truncate: the real status and full Content-Length, then only `bytes` of the body
+    let raw = raw_get(addr, "/health/ready").await;
+    let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 503"), "{head}");
+    let length: usize = head.lines()
+        .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length: ").map(str::to_string))
+        .unwrap().parse().unwrap();
+    assert_eq!(body, "{\"c");
+    assert!(length > body.len());
+
+    // non-matching path on the same server is untouched
+    let raw = raw_get(addr, "/simulate/latency?ms=0").await;
+    assert!(raw.starts_with("HTTP/1.1 200"), "{raw}");
+}
@@ -0,0 +1,96 @@
+use app_health_demo::{
+    config::{Config, ConfigStore},
+    grpc,
+    shutdown::{self, Phase},
+    AppState,
+};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+use tonic::{transport::Channel, Code, Streaming};
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest, HealthCheckResponse,
+};
+
+async fn start(state: &AppState) -> (HealthClient<Channel>, tokio::task::JoinHandle<std::io::Result<()>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(grpc::serve(state.clone(), listener));
+    let channel = Channel::from_shared(format!("http://{addr}")).unwrap().connect().await.unwrap();
+    let client = HealthClient::new(channel);
+    (client, server)
+}
+
+async fn check(client: &mut HealthClient<Channel>, service: &str) -> Result<ServingStatus, Code> {
+    let req = HealthCheckRequest { service: service.to_string() };
+    match client.check(req).await {
+        Ok(res) => Ok(res.into_inner().status()),
+        Err(status) => Err(status.code()),
+    }
+}
+
+async fn next(stream: &mut Streaming<HealthCheckResponse>) -> Option<ServingStatus> {
+    stream.message().await.unwrap().map(|r| r.status())
+}
+
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(300)).await;
+}
+
+#[tokio::test]
+async fn check_follows_the_probe_flags_per_service() {
+    let state = AppState::default();
+    let (mut client, _server) = start(&state).await;
+
+    // before startup: alive, but neither started nor ready
+    assert_eq!(check(&mut client, "liveness").await, Ok(ServingStatus::Serving));
+    assert_eq!(check(&mut client, "startup").await, Ok(ServingStatus::NotServing));
+    assert_eq!(check(&mut client, "readiness").await, Ok(ServingStatus::NotServing));
+    assert_eq!(check(&mut client, "").await, Ok(ServingStatus::NotServing));
+    assert_eq!(check(&mut client, "app-health-demo").await, Err(Code::NotFound));
+
+    state.started.store(true, Ordering::SeqCst);
+    state.ready.store(true, Ordering::SeqCst);
+    settle().await;
+    assert_eq!(check(&mut client, "startup").await, Ok(ServingStatus::Serving));
+    assert_eq!(check(&mut client, "readiness").await, Ok(ServingStatus::Serving));
+    assert_eq!(check(&mut client, "").await, Ok(ServingStatus::Serving));
+
+    state.live.store(false, Ordering::SeqCst);
+    settle().await;
+    assert_eq!(check(&mut client, "liveness").await, Ok(ServingStatus::NotServing));
+    assert_eq!(check(&mut client, "readiness").await, Ok(ServingStatus::Serving));
+}
+
+#[tokio::test]
+async fn watch_streams_flips_and_ends_when_draining() {
+    let mut state = AppState::default();
+    let cfg = Config { pre_stop_delay: Duration::from_millis(100), ..Config::default() };
+    state.config = Arc::new(ConfigStore::new(cfg, None));
+    state.started.store(true, Ordering::SeqCst);
+    state.ready.store(true, Ordering::SeqCst);
+    let (mut client, server) = start(&state).await;
+
+    let req = HealthCheckRequest { service: "readiness".to_string() };
+    let mut stream = client.watch(req).await.unwrap().into_inner();
+    assert_eq!(next(&mut stream).await, Some(ServingStatus::Serving));
+
+    // what the pre-stop phase does to readiness
+    state.ready.store(false, Ordering::SeqCst);
+    assert_eq!(next(&mut stream).await, Some(ServingStatus::NotServing));
+
+    // draining ends the stream, so the graceful shutdown does not hang on it
+    state.shutdown.trigger();
+    let run = tokio::spawn(shutdown::run(state.clone(), server));
+    state.shutdown.reached(Phase::Draining).await;
+    let rest = tokio::time::timeout(Duration::from_secs(2), async {
+        let mut rest = Vec::new();
+        while let Some(s) = next(&mut stream).await {
+            rest.push(s);
+        }
+        rest
+    }).await.expect("watch stream should end on drain");
+    assert!(rest.iter().all(|s| *s == ServingStatus::NotServing), "{rest:?}");
+    tokio::time::timeout(Duration::from_secs(5), run).await.unwrap().unwrap().unwrap();
+}
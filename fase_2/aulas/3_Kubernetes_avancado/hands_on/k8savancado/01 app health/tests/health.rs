@@ -0,0 +1,164 @@
+mod common;
+
+use app_health_demo::{
+    health::{CheckStatus, FileExistsCheck, HealthCheck, HealthRegistry, HttpCheck, TcpCheck},
+    AppState,
+};
+use axum::http::{Method, StatusCode};
+use common::{router, send};
+use std::{net::SocketAddr, sync::atomic::Ordering, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpSocket, TcpStream},
+};
+
+const TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Registers `check` alone and returns its status after one evaluation.
+async fn evaluate(check: impl HealthCheck + 'static) -> CheckStatus {
+    let registry = HealthRegistry::default();
+    registry.register(check, TIMEOUT);
+    registry.evaluate().await;
+    registry.statuses().remove(0)
+}
+
+/// HTTP server on `listener`: `/ok` answers 200, `/hang` never answers, anything else 500.
+fn serve(listener: TcpListener) -> SocketAddr {
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else { return };
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let status = match request.split_whitespace().nth(1) {
+                    Some("/ok") => "200 OK",
+                    Some("/hang") => return tokio::time::sleep(Duration::from_secs(60)).await,
+                    _ => "500 Internal Server Error",
+                };
+                let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.ok();
+            });
+        }
+    });
+    addr
+}
+
+/// A port nothing listens on.
+async fn closed_port() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap()
+}
+
+#[tokio::test]
+async fn tcp_checks_pass_fail_and_time_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let st = evaluate(TcpCheck::new("up", listener.local_addr().unwrap().to_string())).await;
+    assert_eq!(st.status, "up", "{:?}", st.last_error);
+    assert_eq!(st.kind, "tcp");
+
+    let st = evaluate(TcpCheck::new("refused", closed_port().await.to_string())).await;
+    assert_eq!(st.status, "down");
+    assert!(st.last_error.unwrap().starts_with("connect "));
+
+    // a listener that never accepts: once its backlog is full, further SYNs go unanswered
+    let socket = TcpSocket::new_v4().unwrap();
+    socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = socket.local_addr().unwrap();
+    let _backlog = socket.listen(1).unwrap();
+    let mut held = Vec::new();
+    while let Ok(Ok(stream)) = tokio::time::timeout(Duration::from_millis(100), TcpStream::connect(addr)).await {
+        held.push(stream);
+    }
+    let st = evaluate(TcpCheck::new("stuck", addr.to_string())).await;
+    assert_eq!(st.status, "down");
+    assert_eq!(st.last_error.as_deref(), Some("timed out after 300 ms"));
+}
+
+#[tokio::test]
+async fn http_checks_pass_fail_and_time_out() {
+    let addr = serve(TcpListener::bind("127.0.0.1:0").await.unwrap());
+
+    let st = evaluate(HttpCheck::new("ok", format!("http://{addr}/ok"))).await;
+    assert_eq!(st.status, "up", "{:?}", st.last_error);
+    assert_eq!(st.kind, "http");
+
+    let st = evaluate(HttpCheck::new("error", format!("http://{addr}/fail"))).await;
+    assert_eq!(st.last_error.as_deref(), Some(format!("GET http://{addr}/fail returned 500").as_str()));
+
+    let st = evaluate(HttpCheck::new("refused", format!("http://{}/ok", closed_port().await))).await;
+    assert_eq!(st.status, "down");
+
+    let st = evaluate(HttpCheck::new("hang", format!("http://{addr}/hang"))).await;
+    assert_eq!(st.last_error.as_deref(), Some("timed out after 300 ms"));
+
+    let st = evaluate(HttpCheck::new("https", "https://example.com/")).await;
+    assert!(st.last_error.unwrap().contains("only http://"));
+}
+
+#[tokio::test]
+async fn http_checks_handle_ipv6_literals() {
+    let Ok(listener) = TcpListener::bind("[::1]:0").await else { return }; // no IPv6 loopback here
+    let addr = serve(listener);
+    assert!(addr.is_ipv6());
+
+    let st = evaluate(HttpCheck::new("v6", format!("http://[::1]:{}/ok", addr.port()))).await;
+    assert_eq!(st.status, "up", "{:?}", st.last_error);
+
+    // without a port the default 80 goes after the brackets, not into the address
+    let st = evaluate(HttpCheck::new("v6-default", "http://[::1]/ok")).await;
+    if st.status == "down" {
+        assert!(st.last_error.as_deref().unwrap().starts_with("connect [::1]:80: "), "{:?}", st.last_error);
+    }
+}
+
+#[tokio::test]
+async fn file_checks_follow_the_file() {
+    let path = std::env::temp_dir().join(format!("app-health-check-{}", std::process::id()));
+    std::fs::remove_file(&path).ok();
+
+    let st = evaluate(FileExistsCheck::new("cfg", &path)).await;
+    assert_eq!(st.status, "down");
+    assert_eq!(st.kind, "file");
+    assert_eq!(st.last_error, Some(format!("{} does not exist", path.display())));
+
+    std::fs::write(&path, "x").unwrap();
+    let st = evaluate(FileExistsCheck::new("cfg", &path)).await;
+    assert_eq!(st.status, "up");
+    assert_eq!(st.last_error, None);
+    std::fs::remove_file(&path).ok();
+}
+
+#[tokio::test]
+async fn readiness_is_503_while_a_check_is_down() {
+    let path = std::env::temp_dir().join(format!("app-health-ready-{}", std::process::id()));
+    std::fs::remove_file(&path).ok();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let state = AppState::default();
+    state.started.store(true, Ordering::SeqCst);
+    state.ready.store(true, Ordering::SeqCst);
+    state.health.register(TcpCheck::new("db", listener.local_addr().unwrap().to_string()), TIMEOUT);
+    state.health.register(FileExistsCheck::new("cfg", &path), TIMEOUT);
+    let app = router(&state);
+
+    // not evaluated yet: "unknown" does not count as up
+    assert_eq!(send(&app, Method::GET, "/health/ready").await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+    state.health.evaluate().await;
+    let (code, body) = send(&app, Method::GET, "/health/ready").await;
+    assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"][0]["status"], "up");
+    assert_eq!(body["checks"][1]["status"], "down");
+
+    std::fs::write(&path, "x").unwrap();
+    state.health.evaluate().await;
+    assert_eq!(send(&app, Method::GET, "/health/ready").await.0, StatusCode::OK);
+
+    drop(listener);
+    state.health.evaluate().await;
+    assert_eq!(send(&app, Method::GET, "/health/ready").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    std::fs::remove_file(&path).ok();
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use app_health_demo::AppState;
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+};
+use common::{router, send};
+use std::time::Duration;
+use tower::ServiceExt;
+
+async fn in_flight(app: &axum::Router) -> f64 {
+    let (_, text) = send(app, Method::GET, "/metrics").await;
+    text.lines().find_map(|l| l.strip_prefix("http_requests_in_flight ")?.parse().ok()).unwrap()
+}
+
+// `http_requests_in_flight` is process-wide, so this binary holds a single test.
+#[tokio::test]
+async fn a_request_dropped_mid_flight_is_no_longer_counted() {
+    let app = router(&AppState::default());
+    // the scrape counts itself
+    assert_eq!(in_flight(&app).await, 1.0);
+
+    let req = Request::builder()
+        .uri("/simulate/latency?ms=5000")
+        .header(header::AUTHORIZATION, format!("Bearer {}", common::TOKEN))
+        .body(Body::empty())
+        .unwrap();
+    // the client gives up: hyper drops the request future
+    let gone = tokio::time::timeout(Duration::from_millis(100), app.clone().oneshot(req)).await;
+    assert!(gone.is_err());
+    assert_eq!(in_flight(&app).await, 1.0);
+}
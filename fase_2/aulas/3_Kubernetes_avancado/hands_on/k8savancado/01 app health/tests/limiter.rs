@@ -0,0 +1,143 @@
+mod common;
+
+use app_health_demo::{
+    limiter::{AdaptiveLimiter, LimiterConfig},
+    AppState,
+};
+use axum::{body::Body, http::{Method, Request, StatusCode}};
+use common::{router, send};
+use std::{sync::Arc, time::Duration};
+use tower::ServiceExt;
+
+fn limited_state(cfg: LimiterConfig) -> AppState {
+    let mut state = AppState::default();
+    state.limiter = Some(Arc::new(AdaptiveLimiter::new(cfg)));
+    state
+}
+
+#[tokio::test]
+async fn sheds_with_retry_after_but_keeps_probes_served() {
+    let state = limited_state(LimiterConfig {
+        initial: 1,
+        min: 1,
+        max: 1,
+        queue_timeout: Duration::from_millis(50),
+        ..LimiterConfig::default()
+    });
+    let app = router(&state);
+
+    // one slow request takes the only slot
+    let busy = tokio::spawn({
+        let app = app.clone();
+        async move { send(&app, Method::GET, "/simulate/latency?ms=400").await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let res = app.clone().oneshot(Request::get("/info").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.headers()["retry-after"], "1");
+
+    // the priority lane bypasses the limiter
+    assert_eq!(send(&app, Method::GET, "/health/live").await.0, StatusCode::OK);
+    assert_eq!(send(&app, Method::GET, "/admin/faults").await.0, StatusCode::OK);
+    let (status, text) = send(&app, Method::GET, "/metrics").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(text.contains(r#"concurrency_limit_rejections_total{reason="queue_timeout"}"#));
+    assert!(text.contains(r#"http_requests_total{method="GET",path="/info",status="503"}"#));
+
+    assert_eq!(busy.await.unwrap().0, StatusCode::OK);
+    // the slot is free again
+    assert_eq!(send(&app, Method::GET, "/info").await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn queued_requests_get_the_released_slot() {
+    let state = limited_state(LimiterConfig {
+        initial: 1,
+        min: 1,
+        max: 1,
+        queue_timeout: Duration::from_secs(2),
+        ..LimiterConfig::default()
+    });
+    let app = router(&state);
+    let first = tokio::spawn({
+        let app = app.clone();
+        async move { send(&app, Method::GET, "/simulate/latency?ms=100").await.0 }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(send(&app, Method::GET, "/simulate/latency?ms=10").await.0, StatusCode::OK);
+    assert_eq!(first.await.unwrap(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn slow_responses_shrink_the_limit() {
+    let state = limited_state(LimiterConfig {
+        initial: 10,
+        min: 2,
+        latency_target: Duration::from_millis(20),
+        ..LimiterConfig::default()
+    });
+    let app = router(&state);
+    let limiter = state.limiter.clone().unwrap();
+    for _ in 0..5 {
+        send(&app, Method::GET, "/simulate/latency?ms=40").await;
+    }
+    assert!(limiter.limit() < 10, "limit = {}", limiter.limit());
+    assert!(limiter.limit() >= 2);
+}
+
+#[tokio::test]
+async fn every_slot_freed_by_a_limit_increase_reaches_a_waiter() {
+    let state = limited_state(LimiterConfig {
+        initial: 1,
+        min: 1,
+        max: 10,
+        latency_target: Duration::from_secs(1),
+        queue_timeout: Duration::from_secs(2),
+        ..LimiterConfig::default()
+    });
+    let app = router(&state);
+    let start = std::time::Instant::now();
+    let request = |ms: u64| tokio::spawn({
+        let app = app.clone();
+        async move { (send(&app, Method::GET, &format!("/simulate/latency?ms={ms}")).await.0, start.elapsed()) }
+    });
+
+    let first = request(100);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let waiters = [request(300), request(300)];
+    // the fast, saturated first request grows the limit to 2 and its release wakes one waiter;
+    // that one must pass the wakeup on, or the other waits for the next release (~700 ms)
+    assert_eq!(first.await.unwrap().0, StatusCode::OK);
+    for waiter in waiters {
+        let (status, elapsed) = waiter.await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(elapsed < Duration::from_millis(600), "waited until the next release: {elapsed:?}");
+    }
+}
+
+#[tokio::test]
+async fn a_request_dropped_while_queued_gives_its_place_back() {
+    let state = limited_state(LimiterConfig {
+        initial: 1,
+        min: 1,
+        max: 1,
+        max_queue: 1,
+        queue_timeout: Duration::from_secs(2),
+        ..LimiterConfig::default()
+    });
+    let app = router(&state);
+    let busy = tokio::spawn({
+        let app = app.clone();
+        async move { send(&app, Method::GET, "/simulate/latency?ms=300").await.0 }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // the client gives up while queued: its request future is dropped
+    let gone = tokio::time::timeout(Duration::from_millis(50), send(&app, Method::GET, "/info")).await;
+    assert!(gone.is_err());
+
+    // the only queue place is free again: this one waits for the slot instead of a queue_full 503
+    assert_eq!(send(&app, Method::GET, "/info").await.0, StatusCode::OK);
+    assert_eq!(busy.await.unwrap(), StatusCode::OK);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use app_health_demo::AppState;
+use axum::http::{Method, StatusCode};
+use common::{router, send};
+use std::time::Duration;
+
+// CGROUP_ROOT is process-wide, so this binary holds a single test.
+#[tokio::test(flavor = "multi_thread")]
+async fn allocations_are_bounded_and_ramps_stop_at_their_target() {
+    let root = std::env::temp_dir().join(format!("app-health-memory-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let write = |file: &str, content: &str| std::fs::write(root.join(file), content).unwrap();
+    write("memory.current", "0\n");
+    write("memory.max", "1073741824\n");
+    std::env::set_var("CGROUP_ROOT", &root);
+
+    let app = router(&AppState::default());
+    let status = || async {
+        let body = send(&app, Method::GET, "/simulate/mem/status").await.1;
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()
+    };
+
+    // mb * MiB overflows: rejected instead of panicking in the handler
+    let (code, body) = send(&app, Method::POST, &format!("/simulate/mem?mb={}", usize::MAX)).await;
+    assert_eq!(code, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(status().await["blocks"], 0);
+
+    // one tick of 40 MiB is held as CHUNK_MIB blocks
+    let (code, _) = send(&app, Method::POST, "/simulate/mem/ramp?mb_per_sec=40&mb=40&touch=false").await;
+    assert_eq!(code, StatusCode::ACCEPTED);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let st = status().await;
+    assert_eq!(st["blocks"], 3, "{st}");
+    assert_eq!(st["held_mib"], 40, "{st}");
+    send(&app, Method::POST, "/simulate/mem/free").await;
+
+    // a percent ramp stops once memory.max goes away instead of growing without bound
+    let (code, _) = send(&app, Method::POST, "/simulate/mem/ramp?mb_per_sec=16&percent=50&touch=false").await;
+    assert_eq!(code, StatusCode::ACCEPTED);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(status().await["ramp"]["added_mib"], 16);
+    write("memory.max", "max\n");
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+    let st = status().await;
+    assert!(st["ramp"].is_null(), "{st}");
+    assert_eq!(st["held_mib"], 16, "{st}");
+
+    std::fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use app_health_demo::AppState;
+use axum::http::{Method, StatusCode};
+use common::{router, send};
+
+async fn scrape(app: &axum::Router) -> String {
+    let (status, body) = send(app, Method::GET, "/metrics").await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+fn series<'a>(text: &'a str, name: &str) -> Vec<&'a str> {
+    text.lines().filter(|l| l.starts_with(&format!("{name}{{"))).collect()
+}
+
+#[tokio::test]
+async fn requests_are_labelled_by_route_template() {
+    let state = AppState::default();
+    let app = router(&state);
+
+    assert_eq!(send(&app, Method::GET, "/health/live").await.0, StatusCode::OK);
+    assert_eq!(send(&app, Method::DELETE, "/admin/faults/42").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::DELETE, "/admin/faults/7").await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::GET, "/simulate/error?rate=1").await.0, StatusCode::INTERNAL_SERVER_ERROR);
+
+    let text = scrape(&app).await;
+    let totals = series(&text, "http_requests_total");
+    assert!(totals.contains(&r#"http_requests_total{method="GET",path="/health/live",status="200"} 1"#), "{totals:#?}");
+    // both ids collapse into one series
+    assert!(totals.contains(&r#"http_requests_total{method="DELETE",path="/admin/faults/:id",status="404"} 2"#), "{totals:#?}");
+    assert!(!text.contains("/admin/faults/42"));
+    assert!(series(&text, "http_request_duration_seconds_count")
+        .contains(&r#"http_request_duration_seconds_count{method="GET",path="/health/live",status="200"} 1"#));
+    assert!(series(&text, "http_errors_total")
+        .contains(&r#"http_errors_total{path="/simulate/error",status="500"} 1"#));
+}
+
+#[tokio::test]
+async fn unknown_paths_and_methods_are_bucketed() {
+    let state = AppState::default();
+    let app = router(&state);
+
+    for path in ["/wp-login.php", "/.env", "/a/b/c"] {
+        assert_eq!(send(&app, Method::GET, path).await.0, StatusCode::NOT_FOUND);
+    }
+    let brew = Method::from_bytes(b"BREW").unwrap();
+    assert_eq!(send(&app, brew, "/info").await.0, StatusCode::METHOD_NOT_ALLOWED);
+
+    let text = scrape(&app).await;
+    let totals = series(&text, "http_requests_total");
+    assert!(totals.contains(&r#"http_requests_total{method="GET",path="unmatched",status="404"} 3"#), "{totals:#?}");
+    assert!(totals.contains(&r#"http_requests_total{method="OTHER",path="/info",status="405"} 1"#), "{totals:#?}");
+    assert!(!text.contains("wp-login") && !text.contains("BREW"));
+}
+
+#[tokio::test]
+async fn openmetrics_scrape_is_terminated() {
+    let state = AppState::default();
+    let app = router(&state);
+    let req = axum::http::Request::get("/metrics")
+        .header("accept", "application/openmetrics-text; version=1.0.0")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let res = tower::ServiceExt::oneshot(app, req).await.unwrap();
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("application/openmetrics-text"));
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert!(String::from_utf8_lossy(&body).ends_with("# EOF\n"));
+}
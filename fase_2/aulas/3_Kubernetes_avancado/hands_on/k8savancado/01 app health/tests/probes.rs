@@ -0,0 +1,116 @@
+mod common;
+
+use app_health_demo::{config::Config, spawn_startup, AppState, StartupTiming};
+use axum::http::{Method, StatusCode};
+use common::{get, router, send};
+use std::{sync::atomic::Ordering, time::Duration};
+
+async fn sleep_ms(ms: u64) {
+    tokio::time::sleep(Duration::from_millis(ms)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn startup_and_readiness_follow_env_delays() {
+    let env = |name: &str| match name {
+        "STARTUP_DELAY_MS" => Some("3000".to_string()),
+        "READY_AFTER_MS" => Some("5000".to_string()),
+        _ => None,
+    };
+    let timing = StartupTiming::from(&Config::from_sources(None, env).unwrap());
+    assert_eq!(timing.startup_delay, Duration::from_millis(3000));
+    assert_eq!(timing.ready_after, Duration::from_millis(5000));
+
+    let state = AppState::default();
+    let app = router(&state);
+    spawn_startup(state.clone(), timing);
+
+    // t = 0: nothing passes except liveness
+    assert_eq!(get(&app, "/health/live").await, StatusCode::OK);
+    assert_eq!(get(&app, "/health/startup").await, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+
+    // just before and after STARTUP_DELAY_MS
+    sleep_ms(2_900).await;
+    assert_eq!(get(&app, "/health/startup").await, StatusCode::SERVICE_UNAVAILABLE);
+    sleep_ms(200).await;
+    assert_eq!(get(&app, "/health/startup").await, StatusCode::OK);
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+
+    // readiness waits READY_AFTER_MS more, counted from startup
+    sleep_ms(4_800).await;
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+    sleep_ms(200).await;
+    let (status, body) = send(&app, Method::GET, "/health/ready").await;
+    assert_eq!(status, StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["started"], true);
+}
+
+#[tokio::test]
+async fn flip_ready_requires_started() {
+    let state = AppState::default();
+    let app = router(&state);
+    // an explicit flip cannot make an unstarted pod ready
+    assert_eq!(send(&app, Method::POST, "/admin/flip_ready?value=true").await.0, StatusCode::OK);
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+    state.started.store(true, Ordering::SeqCst);
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn flip_ready_and_live_toggle_probes() {
+    let state = AppState::default();
+    state.started.store(true, Ordering::SeqCst);
+    state.ready.store(true, Ordering::SeqCst);
+    let app = router(&state);
+
+    let (status, body) = send(&app, Method::POST, "/admin/flip_ready?value=false").await;
+    assert_eq!((status, body.as_str()), (StatusCode::OK, "readiness = false"));
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+    // liveness is independent of readiness
+    assert_eq!(get(&app, "/health/live").await, StatusCode::OK);
+
+    // `value` defaults to true
+    send(&app, Method::POST, "/admin/flip_ready").await;
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::OK);
+
+    send(&app, Method::POST, "/admin/flip_live?value=false").await;
+    assert_eq!(get(&app, "/health/live").await, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(get(&app, "/health/ready").await, StatusCode::OK);
+    send(&app, Method::POST, "/admin/flip_live?value=true").await;
+    assert_eq!(get(&app, "/health/live").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn flips_are_post_only_and_authenticated() {
+    let state = AppState::default();
+    state.started.store(true, Ordering::SeqCst);
+    state.ready.store(true, Ordering::SeqCst);
+    let app = router(&state);
+
+    assert_eq!(get(&app, "/admin/flip_ready?value=false").await, StatusCode::METHOD_NOT_ALLOWED);
+    assert!(state.ready.load(Ordering::SeqCst));
+
+    let req = axum::http::Request::post("/admin/flip_live?value=false").body(axum::body::Body::empty()).unwrap();
+    let res = tower::ServiceExt::oneshot(app.clone(), req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert!(state.live.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn failure_simulators_need_the_admin_guard() {
+    let state = AppState::default();
+    let app = router(&state);
+    for uri in ["/simulate/latency?ms=1", "/simulate/error?rate=0"] {
+        let req = axum::http::Request::get(uri).body(axum::body::Body::empty()).unwrap();
+        let res = tower::ServiceExt::oneshot(app.clone(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{uri}");
+        assert_eq!(get(&app, uri).await, StatusCode::OK, "{uri} with the token");
+    }
+
+    // with ADMIN_PORT they live on the admin listener only
+    let public = app_health_demo::with_layers(app_health_demo::public_routes(), &state);
+    assert_eq!(get(&public, "/simulate/latency?ms=1").await, StatusCode::NOT_FOUND);
+    assert_eq!(get(&public, "/simulate/error?rate=0").await, StatusCode::NOT_FOUND);
+}
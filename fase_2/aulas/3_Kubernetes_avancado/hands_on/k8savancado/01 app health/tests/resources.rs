@@ -0,0 +1,54 @@
+mod common;
+
+use app_health_demo::{resources, AppState};
+use axum::http::Method;
+use common::{router, send};
+use std::time::Duration;
+
+/// Value of the label-less series `name`, if it is exported.
+fn value(text: &str, name: &str) -> Option<f64> {
+    text.lines().find_map(|l| l.strip_prefix(&format!("{name} "))?.parse().ok())
+}
+
+// CGROUP_ROOT is process-wide, so this binary holds a single test.
+#[tokio::test(flavor = "multi_thread")]
+async fn cgroup_limits_are_exported_only_while_set() {
+    let root = std::env::temp_dir().join(format!("app-health-cgroup-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let write = |file: &str, content: &str| std::fs::write(root.join(file), content).unwrap();
+    write("memory.current", "67108864\n");
+    write("memory.max", "max\n");
+    write("cpu.max", "max 100000\n");
+    std::env::set_var("CGROUP_ROOT", &root);
+
+    let app = router(&AppState::default());
+    resources::spawn_collector(Duration::from_millis(10));
+    let scrape = || async { send(&app, Method::GET, "/metrics").await.1 };
+    let settle = || tokio::time::sleep(Duration::from_millis(100));
+
+    settle().await;
+    let text = scrape().await;
+    assert_eq!(value(&text, "cgroup_memory_current_bytes"), Some(67108864.0));
+    // unlimited: no series rather than a 0 that reads as "no memory/CPU allowed"
+    for name in ["cgroup_memory_max_bytes", "cgroup_memory_usage_ratio", "cgroup_cpu_limit_cores"] {
+        assert_eq!(value(&text, name), None, "{name} exported without a limit");
+    }
+
+    write("memory.max", "268435456\n");
+    write("cpu.max", "50000 100000\n");
+    settle().await;
+    let text = scrape().await;
+    assert_eq!(value(&text, "cgroup_memory_max_bytes"), Some(268435456.0));
+    assert_eq!(value(&text, "cgroup_memory_usage_ratio"), Some(0.25));
+    assert_eq!(value(&text, "cgroup_cpu_limit_cores"), Some(0.5));
+
+    // a limit lifted in place drops the series again
+    write("memory.max", "max\n");
+    write("cpu.max", "max 100000\n");
+    settle().await;
+    let text = scrape().await;
+    assert_eq!(value(&text, "cgroup_memory_max_bytes"), None);
+    assert_eq!(value(&text, "cgroup_cpu_limit_cores"), None);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use app_health_demo::{
+    scenario::{parse_duration, Scenario},
+    AppState,
+};
+use axum::http::{Method, StatusCode};
+use common::{router, send, send_json};
+use serde_json::json;
+use std::{sync::atomic::Ordering, time::Duration};
+
+#[test]
+fn durations_parse_units_and_reject_overflow() {
+    assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+    assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_duration("99999999999999999999h"), None);
+
+    for at in ["99999999999999999999h", "1e300", ".inf", "-1"] {
+        let err = Scenario::parse(format!("steps:\n  - {{ at: {at}, action: ready, value: false }}\n").as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("duration"), "{at}: {err}");
+    }
+}
+
+#[test]
+fn scenarios_ending_beyond_a_duration_are_rejected() {
+    // each offset fits, but the step ends past Duration::MAX
+    let yaml = "steps:\n  - { at: 10000000000000000000, action: unstable, error_rate: 0.5, for: 10000000000000000000 }\n";
+    let err = Scenario::parse(yaml.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("too long"), "{err}");
+}
+
+#[test]
+fn memory_steps_that_cannot_be_allocated_are_rejected() {
+    let yaml = format!("steps:\n  - {{ at: 0, action: mem, mb: {} }}\n", usize::MAX);
+    let err = Scenario::parse(yaml.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("MiB"), "{err}");
+}
+
+#[tokio::test(start_paused = true)]
+async fn stopping_a_scenario_restores_the_probe_flags() {
+    let state = AppState::default();
+    state.ready.store(true, Ordering::SeqCst);
+    let app = router(&state);
+    let flags = || (state.ready.load(Ordering::SeqCst), state.live.load(Ordering::SeqCst));
+
+    let scenario = json!({ "name": "outage", "steps": [
+        { "at": "0s", "action": "ready", "value": false },
+        { "at": "1s", "action": "live", "value": false },
+        { "at": "10s", "action": "ready", "value": true },
+    ]});
+    assert_eq!(send_json(&app, Method::POST, "/admin/scenario/start", scenario).await.0, StatusCode::ACCEPTED);
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(flags(), (false, true));
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(flags(), (false, false));
+
+    let (code, body) = send(&app, Method::POST, "/admin/scenario/stop").await;
+    assert_eq!((code, body.as_str()), (StatusCode::OK, "scenario 'outage' stopped"));
+    assert_eq!(flags(), (true, true));
+
+    // the aborted timeline fires nothing more
+    tokio::time::sleep(Duration::from_secs(10)).await;
+    assert_eq!(flags(), (true, true));
+    let progress: serde_json::Value = serde_json::from_str(&send(&app, Method::GET, "/admin/scenario").await.1).unwrap();
+    assert_eq!(progress["running"], false);
+    assert_eq!(progress["steps"][2]["state"], "skipped");
+}
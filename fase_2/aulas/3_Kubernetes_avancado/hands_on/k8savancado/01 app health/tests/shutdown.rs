@@ -0,0 +1,96 @@
+mod common;
+
+use app_health_demo::{
+    config::{Config, ConfigStore},
+    shutdown::{self, Phase},
+    AppState,
+};
+use common::{http_get, router};
+use std::{
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
+
+fn with_delays(pre_stop_delay: Duration, drain_timeout: Duration) -> AppState {
+    let mut state = AppState::default();
+    let cfg = Config { pre_stop_delay, drain_timeout, ..Config::default() };
+    state.config = Arc::new(ConfigStore::new(cfg, None));
+    state
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn shutdown_runs_pre_stop_then_drains_in_flight_requests() {
+    let state = with_delays(Duration::from_millis(300), Duration::from_secs(5));
+    state.started.store(true, Ordering::SeqCst);
+    state.ready.store(true, Ordering::SeqCst);
+    let app = router(&state);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let drain = state.shutdown.clone();
+    let server = tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async move { drain.reached(Phase::Draining).await })
+            .await
+    });
+
+    // Record when each phase is reached, and the readiness flag at pre-stop
+    let watch = |phase| {
+        let s = state.clone();
+        tokio::spawn(async move {
+            s.shutdown.reached(phase).await;
+            (Instant::now(), s.ready.load(Ordering::SeqCst))
+        })
+    };
+    let (pre_stop, draining, stopped) = (watch(Phase::PreStop), watch(Phase::Draining), watch(Phase::Stopped));
+
+    let pre_stop_delay = Duration::from_millis(300);
+    let run = tokio::spawn(shutdown::run(state.clone(), server));
+
+    // A slow request is in flight when the shutdown starts
+    let slow = tokio::spawn(http_get(addr, "/simulate/latency?ms=800"));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let triggered = Instant::now();
+    state.shutdown.trigger();
+
+    // Pre-stop: readiness is already false, but new connections are still served
+    let (at_pre_stop, ready_at_pre_stop) = pre_stop.await.unwrap();
+    assert!(!ready_at_pre_stop, "readiness must flip before the pre-stop delay");
+    assert_eq!(state.shutdown.phase(), Phase::PreStop);
+    // counted per state, so requests from other tests in this binary don't hold up the drain
+    assert_eq!(state.shutdown.in_flight(), 1);
+    assert_eq!(http_get(addr, "/health/ready").await.unwrap().0, 503);
+    assert_eq!(http_get(addr, "/health/live").await.unwrap().0, 200);
+
+    // Draining: only after the pre-stop delay, and the listener no longer accepts
+    let (at_draining, _) = draining.await.unwrap();
+    assert!(at_draining.duration_since(at_pre_stop) >= pre_stop_delay);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(http_get(addr, "/health/live").await.is_err(), "listener should be closed while draining");
+
+    // The in-flight request still completes, and only then the sequence stops
+    let (status, body) = slow.await.unwrap().unwrap();
+    assert_eq!((status, body.as_str()), (200, "Slept 800 ms"));
+    run.await.unwrap().unwrap();
+    let (at_stopped, _) = stopped.await.unwrap();
+    assert!(at_pre_stop <= at_draining && at_draining <= at_stopped);
+    assert!(at_stopped.duration_since(triggered) >= Duration::from_millis(650));
+    assert_eq!(state.shutdown.phase(), Phase::Stopped);
+    assert_eq!(state.shutdown.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn admin_trigger_rejects_a_second_shutdown() {
+    let state = with_delays(Duration::from_millis(200), Duration::from_millis(200));
+    let app = router(&state);
+    let (status, _) = common::send(&app, axum::http::Method::POST, "/admin/shutdown").await;
+    assert_eq!(status, axum::http::StatusCode::ACCEPTED);
+    // the phase only changes once `run` picks the trigger up
+    let server = tokio::spawn(async { Ok(()) });
+    let run = tokio::spawn(shutdown::run(state.clone(), server));
+    state.shutdown.reached(Phase::PreStop).await;
+    let (status, _) = common::send(&app, axum::http::Method::POST, "/admin/shutdown").await;
+    assert_eq!(status, axum::http::StatusCode::CONFLICT);
+    run.await.unwrap().unwrap();
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use app_health_demo::{
+    config::Config,
+    slo::{rules_yaml, Counts, SloConfig, SloReport, SloTracker},
+    AppState,
+};
+use axum::http::{Method, StatusCode};
+use common::{router, send};
+use std::time::{Duration, Instant};
+
+const MIN: Duration = Duration::from_secs(60);
+
+fn counts(total: f64, errors: f64, fast: f64) -> Counts {
+    Counts { total, errors, fast }
+}
+
+fn burn(report: &SloReport, window: &str) -> f64 {
+    report.windows.iter().find(|w| w.window == window).unwrap().burn_rate
+}
+
+fn firing(report: &SloReport, severity: &str) -> bool {
+    report.alerts.iter().find(|a| a.severity == severity).unwrap().firing
+}
+
+/// Records a sample at `t0` and one per minute after it, adding each `(total, errors, fast)` step.
+fn feed(tracker: &SloTracker, t0: Instant, start: Counts, minutes: impl IntoIterator<Item = (f64, f64, f64)>) -> (Instant, Counts) {
+    let (mut now, mut c) = (t0, start);
+    tracker.record(now, c);
+    for (total, errors, fast) in minutes {
+        now += MIN;
+        c = counts(c.total + total, c.errors + errors, c.fast + fast);
+        tracker.record(now, c);
+    }
+    (now, c)
+}
+
+#[test]
+fn burn_rates_use_the_sample_at_each_window_start() {
+    let tracker = SloTracker::new(SloConfig::default());
+    // 1% errors (exactly the 99% budget) for 2h, then 20% errors and 10% slow for 5 minutes
+    let steady = std::iter::repeat_n((100.0, 1.0, 100.0), 120);
+    let spike = std::iter::repeat_n((100.0, 20.0, 90.0), 5);
+    let (now, c) = feed(&tracker, Instant::now(), Counts::default(), steady.chain(spike));
+
+    let r = tracker.report(now, c);
+    assert!((burn(&r.availability, "5m") - 20.0).abs() < 1e-9);
+    // 55 minutes at 1x plus 5 at 20x
+    assert!((burn(&r.availability, "1h") - (55.0 + 5.0 * 20.0) / 60.0).abs() < 1e-9);
+    // only 125 minutes of data: the 6h window covers all of it
+    let six = r.availability.windows.iter().find(|w| w.window == "6h").unwrap();
+    assert_eq!(six.coverage_seconds, 125 * 60);
+    assert_eq!(six.requests, 12_500.0);
+    assert!(!firing(&r.availability, "page"), "1h burn is below 14.4x");
+
+    // latency: 10% slow against a 5% budget
+    assert!((burn(&r.latency, "5m") - 2.0).abs() < 1e-9);
+    assert!((r.latency.windows[0].sli - 0.9).abs() < 1e-9);
+}
+
+#[test]
+fn page_fires_only_while_both_windows_burn() {
+    let tracker = SloTracker::new(SloConfig::default());
+    // 50% errors for a whole hour: 50x on every window
+    let (now, c) = feed(&tracker, Instant::now(), Counts::default(), std::iter::repeat_n((100.0, 50.0, 100.0), 60));
+    let r = tracker.report(now, c);
+    assert!(firing(&r.availability, "page") && firing(&r.availability, "ticket"));
+    assert!(!firing(&r.latency, "page"));
+
+    // it stopped 5 minutes ago: the short window clears the page, the ticket stays
+    let (now, c) = feed(&tracker, now, c, std::iter::repeat_n((100.0, 0.0, 100.0), 5));
+    let r = tracker.report(now, c);
+    assert_eq!(burn(&r.availability, "5m"), 0.0);
+    assert!(!firing(&r.availability, "page"));
+    assert!(firing(&r.availability, "ticket"));
+}
+
+#[test]
+fn no_traffic_means_no_burn() {
+    let tracker = SloTracker::new(SloConfig::default());
+    let r = tracker.report(Instant::now(), Counts::default());
+    for w in &r.availability.windows {
+        assert_eq!((w.sli, w.burn_rate, w.requests), (1.0, 0.0, 0.0));
+    }
+}
+
+#[test]
+fn bundled_prometheus_rules_match_the_generator() {
+    let manifest: serde_yaml::Value =
+        serde_yaml::from_str(include_str!("../k8s/base/slo-rules.yaml")).unwrap();
+    let bundled: serde_yaml::Value = serde_yaml::from_str(manifest["data"]["slo-rules.yml"].as_str().unwrap()).unwrap();
+    let generated: serde_yaml::Value = serde_yaml::from_str(&rules_yaml(&SloConfig::default())).unwrap();
+    assert_eq!(bundled, generated, "regenerate k8s/base/slo-rules.yaml from /slo/rules");
+    let rules = generated["groups"][0]["rules"].as_sequence().unwrap();
+    assert_eq!(rules.len(), 6 + 4);
+}
+
+#[tokio::test]
+async fn slo_endpoint_counts_user_traffic_only() {
+    let state = AppState::default();
+    let app = router(&state);
+    for _ in 0..3 {
+        send(&app, Method::GET, "/simulate/error?rate=1").await;
+        send(&app, Method::GET, "/info").await;
+        // every EXCLUDED_PATHS alternative: probes, scrapes, this API, admin and unmatched routes
+        for path in ["/health/live", "/metrics", "/slo/rules", "/admin/faults", "/nope"] {
+            send(&app, Method::GET, path).await;
+        }
+    }
+    let (status, body) = send(&app, Method::GET, "/slo").await;
+    assert_eq!(status, StatusCode::OK);
+    let slo: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let five = &slo["availability"]["windows"][0];
+    assert_eq!(five["window"], "5m");
+    assert_eq!((five["requests"].as_f64(), five["bad"].as_f64()), (Some(6.0), Some(3.0)));
+    assert_eq!(five["burn_rate"].as_f64().unwrap().round(), 50.0);
+    assert_eq!(slo["availability"]["alerts"][0]["firing"], true);
+
+    let (_, text) = send(&app, Method::GET, "/metrics").await;
+    let gauge = text.lines().find_map(|l| l.strip_prefix(r#"slo_burn_rate{slo="availability",window="5m"} "#)).unwrap();
+    assert_eq!(gauge.parse::<f64>().unwrap().round(), 50.0);
+    assert!(text.contains(r#"slo_alert_firing{severity="page",slo="availability"} 1"#));
+}
+
+#[test]
+fn objectives_come_from_the_slo_section_with_env_overrides() {
+    let file = "http_duration_buckets: [0.1, 0.25, 1]\nslo:\n  availability: 0.999\n  latency_threshold: 0.25\n  sample_interval: 30s\n";
+    let env = |name: &str| (name == "SLO_LATENCY_TARGET").then(|| "0.9".to_string());
+    let cfg = Config::from_sources(Some(file), env).unwrap();
+    assert_eq!(cfg.slo, SloConfig { availability: 0.999, latency_threshold: 0.25, latency_target: 0.9, sample_interval: Duration::from_secs(30) });
+
+    // the default 0.5s threshold is not one of these buckets
+    let errors = Config::from_sources(Some("http_duration_buckets: [0.1, 1]\nslo:\n  availability: 1.5\n  window: 1h\n"), |_| None).unwrap_err();
+    assert!(errors[0].contains("unknown field `window`"), "{errors:?}");
+    let errors = Config::from_sources(Some("http_duration_buckets: [0.1, 1]\nslo:\n  availability: 1.5\n"), |_| None).unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:#?}");
+    assert!(errors[0].starts_with("slo.availability (1.5)"));
+    assert!(errors[1].starts_with("slo.latency_threshold (0.5)"));
+}
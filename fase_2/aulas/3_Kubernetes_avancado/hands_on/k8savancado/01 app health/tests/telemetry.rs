@@ -0,0 +1,111 @@
+mod common;
+
+use app_health_demo::{config::Config, telemetry, AppState};
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+};
+use opentelemetry::trace::{SpanId, TraceId};
+use opentelemetry_proto::tonic::{
+    collector::trace::v1::{
+        trace_service_server::{TraceService, TraceServiceServer},
+        ExportTraceServiceRequest, ExportTraceServiceResponse,
+    },
+    common::v1::any_value::Value,
+    trace::v1::{span::SpanKind, Span},
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tonic::{transport::server::TcpIncoming, Response, Status};
+use tower::ServiceExt;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+/// Stand-in OTLP/gRPC collector: hands every exported span, with its `service.name`, to the test.
+struct Collector(mpsc::UnboundedSender<(String, Span)>);
+
+#[tonic::async_trait]
+impl TraceService for Collector {
+    async fn export(
+        &self,
+        req: tonic::Request<ExportTraceServiceRequest>,
+    ) -> Result<Response<ExportTraceServiceResponse>, Status> {
+        for resource_spans in req.into_inner().resource_spans {
+            let service = resource_spans.resource.iter()
+                .flat_map(|r| &r.attributes)
+                .find(|a| a.key == "service.name")
+                .and_then(|a| match a.value.as_ref()?.value.as_ref()? {
+                    Value::StringValue(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            for span in resource_spans.scope_spans.into_iter().flat_map(|s| s.spans) {
+                let _ = self.0.send((service.clone(), span));
+            }
+        }
+        Ok(Response::new(ExportTraceServiceResponse { partial_success: None }))
+    }
+}
+
+async fn request(router: &axum::Router, method: Method, uri: &str, traceparent: Option<&str>) {
+    let mut req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", common::TOKEN));
+    if let Some(tp) = traceparent {
+        req = req.header("traceparent", tp);
+    }
+    router.clone().oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+}
+
+// One test per binary: `telemetry::init` installs the global subscriber and tracer provider.
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_are_exported_over_otlp_grpc_named_by_route_and_continue_the_callers_trace() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(TraceServiceServer::new(Collector(tx)))
+            .serve_with_incoming(TcpIncoming::from(listener)),
+    );
+
+    let file = format!("log_level: info\notel_exporter_otlp_endpoint: http://{addr}\notel_exporter_otlp_protocol: grpc\n");
+    let cfg = Config::from_sources(Some(&file), |_| None).unwrap();
+    let provider = telemetry::init(&cfg).unwrap().expect("tracing is enabled");
+
+    let router = common::router(&AppState::default());
+    request(&router, Method::GET, "/info", Some(&format!("00-{TRACE_ID}-{PARENT_ID}-01"))).await;
+    request(&router, Method::DELETE, "/admin/faults/42", None).await;
+    request(&router, Method::GET, "/no/such/route", None).await;
+
+    let flush = provider.clone();
+    tokio::task::spawn_blocking(move || flush.force_flush()).await.unwrap().unwrap();
+
+    let mut spans = Vec::new();
+    while spans.len() < 3 {
+        let next = tokio::time::timeout(Duration::from_secs(10), rx.recv()).await;
+        spans.push(next.expect("collector got fewer than 3 spans").unwrap());
+    }
+    let span = |name: &str| {
+        spans.iter().find(|(_, s)| s.name == name)
+            .unwrap_or_else(|| panic!("no span {name:?} in {:?}", spans.iter().map(|(_, s)| &s.name).collect::<Vec<_>>()))
+    };
+
+    // named after the route template, never the raw path
+    let (service, info) = span("GET /info");
+    assert_eq!(service, "app-health-demo");
+    assert_eq!(info.kind, SpanKind::Server as i32);
+    span("DELETE /admin/faults/:id");
+    span("GET unmatched");
+
+    // the traceparent header makes the span a child of the caller's
+    assert_eq!(info.trace_id, TraceId::from_hex(TRACE_ID).unwrap().to_bytes());
+    assert_eq!(info.parent_span_id, SpanId::from_hex(PARENT_ID).unwrap().to_bytes());
+    let (_, other) = span("GET unmatched");
+    assert_ne!(other.trace_id, info.trace_id);
+    assert!(other.parent_span_id.is_empty());
+
+    tokio::task::spawn_blocking(move || provider.shutdown()).await.unwrap().unwrap();
+}
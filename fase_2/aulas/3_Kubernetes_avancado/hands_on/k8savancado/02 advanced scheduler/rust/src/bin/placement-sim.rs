@@ -0,0 +1,100 @@
+use aula02_scheduler_demo::{
+    manifests::Manifests,
+    simulate::{Cluster, Decision},
+};
+use clap::Parser;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Simulates kube-scheduler: reads Nodes and Pods (or Deployments, StatefulSets, DaemonSets...) from
+/// YAML files and shows, for each pod, the filter and score of every node and the chosen node.
+/// Pods with spec.nodeName count as already placed.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// YAML files (multi-document; `kubectl get nodes -o yaml` works too)
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Overrides the replica count of the workloads
+    #[arg(short, long)]
+    replicas: Option<i32>,
+    /// Only print the pod -> node summary
+    #[arg(short, long)]
+    summary: bool,
+}
+
+fn print_decision(pod: &str, namespace: &str, decision: &Decision) {
+    println!("== {pod} ({namespace}) ==");
+    let width = decision.results.iter().map(|r| r.node.len()).max().unwrap_or(0).max(2);
+    println!("{:<width$}  {:>8} {:>6} {:>7} {:>7} {:>6}", "NODE", "NODEAFF", "TAINT", "PODAFF", "SPREAD", "TOTAL");
+    for r in &decision.results {
+        match &r.scores {
+            Some(s) => {
+                let mark = if decision.chosen.as_ref() == Some(&r.node) { "  <- chosen" } else { "" };
+                println!(
+                    "{:<width$}  {:>8} {:>6} {:>7} {:>7} {:>6}{mark}",
+                    r.node, s.node_affinity, s.taint_toleration, s.inter_pod_affinity, s.topology_spread, s.total
+                );
+            }
+            None => println!("{:<width$}  {:>8} {:>6} {:>7} {:>7} {:>6}  x {}", r.node, "-", "-", "-", "-", "-", r.failures.join("; ")),
+        }
+    }
+    match &decision.chosen {
+        Some(_) if !decision.tied_with.is_empty() => {
+            println!("Tied with {}: the scheduler would pick at random; here the alphabetical order wins.", decision.tied_with.join(", "))
+        }
+        Some(_) => {}
+        None => println!("No feasible node: the pod would stay Pending (FailedScheduling)."),
+    }
+    println!();
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let mut manifests = Manifests::default();
+    for file in &args.files {
+        let text = match std::fs::read_to_string(file) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("cannot read {}: {}", file.display(), e);
+                return ExitCode::FAILURE;
+            }
+        };
+        if let Err(e) = manifests.add(&text, args.replicas) {
+            eprintln!("invalid YAML in {}: {}", file.display(), e);
+            return ExitCode::FAILURE;
+        }
+    }
+    if !manifests.skipped.is_empty() {
+        eprintln!("Skipped: {}", manifests.skipped.join(", "));
+    }
+    if manifests.nodes.is_empty() {
+        eprintln!("No Node in the files (hint: kubectl get nodes -o yaml > nodes.yaml).");
+        return ExitCode::FAILURE;
+    }
+
+    for skip in manifests.daemon_skipped() {
+        eprintln!("DaemonSet {} does not run on {}: {}", skip.daemonset, skip.node, skip.reasons.join("; "));
+    }
+
+    let pending = manifests.pending();
+    let mut cluster = Cluster::new(manifests.nodes, manifests.placed);
+    println!("{} nodes, {} pods already placed, {} to schedule\n", cluster.nodes.len(), cluster.placed.len(), pending.len());
+
+    let mut placements = Vec::new();
+    for pod in pending {
+        let (name, namespace) = (pod.name.clone(), pod.namespace.clone());
+        let decision = cluster.schedule(pod);
+        if !args.summary {
+            print_decision(&name, &namespace, &decision);
+        }
+        placements.push((name, decision.chosen));
+    }
+
+    println!("Summary:");
+    let width = placements.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+    for (pod, node) in &placements {
+        println!("  {:<width$}  -> {}", pod, node.as_deref().unwrap_or("Pending"));
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,228 @@
+//! Ephemeral-storage pressure: files written to an emptyDir or to the container's writable layer.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::pressure::MIB;
+
+/// Files are at most this big, so a cleanup can also be partial (`rm` a few) when done by hand.
+const FILE_MAX: u64 = 64 * MIB;
+const FILE_PREFIX: &str = "fill-";
+
+/// Where the bytes land, which decides what the kubelet counts them against.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    /// An `emptyDir` volume: counted against its `sizeLimit` and the pod's ephemeral-storage.
+    EmptyDir,
+    /// The container's writable layer: counted against the container's ephemeral-storage limit.
+    Container,
+}
+
+impl Target {
+    pub const ALL: [Target; 2] = [Target::EmptyDir, Target::Container];
+
+    /// The name used in JSON and metric labels.
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::EmptyDir => "empty_dir",
+            Target::Container => "container",
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "emptydir" | "empty_dir" => Ok(Target::EmptyDir),
+            "container" | "rootfs" => Ok(Target::Container),
+            other => Err(format!("unknown target {other:?}: use emptydir or container")),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Written {
+    pub target: Target,
+    pub path: PathBuf,
+    pub written_mb: u64,
+    pub files: usize,
+    pub fsync: bool,
+    pub elapsed_ms: u64,
+    pub mb_per_s: f64,
+}
+
+pub struct Disk {
+    emptydir: PathBuf,
+    container: PathBuf,
+    /// Upper bound for everything written under one target.
+    pub max_mb: u64,
+    next_file: AtomicU64,
+    // one writer at a time, so the budget check and the write do not race
+    writing: Mutex<()>,
+}
+
+impl Disk {
+    /// `DISK_EMPTYDIR_PATH` (default `/scratch`), `DISK_CONTAINER_PATH` (default `/tmp/disk-pressure`)
+    /// and `DISK_PRESSURE_MAX_MB` (default 1024; values whose byte count overflows are ignored).
+    pub fn from_env() -> Self {
+        let path = |key: &str, default: &str| PathBuf::from(std::env::var(key).unwrap_or_else(|_| default.into()));
+        Self::new(
+            path("DISK_EMPTYDIR_PATH", "/scratch"),
+            path("DISK_CONTAINER_PATH", "/tmp/disk-pressure"),
+            std::env::var("DISK_PRESSURE_MAX_MB")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|mb| mb.checked_mul(MIB).is_some())
+                .unwrap_or(1024),
+        )
+    }
+
+    pub fn new(emptydir: PathBuf, container: PathBuf, max_mb: u64) -> Self {
+        Self { emptydir, container, max_mb, next_file: AtomicU64::new(0), writing: Mutex::new(()) }
+    }
+
+    pub fn path(&self, target: Target) -> &Path {
+        match target {
+            Target::EmptyDir => &self.emptydir,
+            Target::Container => &self.container,
+        }
+    }
+
+    /// Writes `mb` MiB of non-zero data in files of up to 64 MiB, `sync_all`-ing each one when `fsync`.
+    pub fn write(&self, target: Target, mb: u64, fsync: bool) -> Result<Written, String> {
+        if mb == 0 {
+            return Err("nothing to write".into());
+        }
+        // `mb` comes straight from the query string
+        let bytes = mb.checked_mul(MIB).ok_or_else(|| format!("{mb} MiB does not fit in a byte count"))?;
+        let max_bytes = self.max_mb.checked_mul(MIB).ok_or_else(|| format!("DISK_PRESSURE_MAX_MB {} is too large", self.max_mb))?;
+        let _one_writer = self.writing.lock().unwrap();
+        let dir = self.path(target);
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        let (used, _) = usage(dir);
+        if used.checked_add(bytes).is_none_or(|total| total > max_bytes) {
+            return Err(format!(
+                "{mb} MiB more would exceed DISK_PRESSURE_MAX_MB: {} MiB already under {}, limit {} MiB",
+                used / MIB, dir.display(), self.max_mb
+            ));
+        }
+
+        // a pattern rather than zeros, so compressing or deduplicating filesystems still store every byte
+        let block: Vec<u8> = (0..MIB).map(|i| (i % 251) as u8 + 1).collect();
+        let start = Instant::now();
+        let (mut left, mut files) = (bytes, 0);
+        while left > 0 {
+            let size = left.min(FILE_MAX);
+            let n = self.next_file.fetch_add(1, Ordering::Relaxed);
+            let file = dir.join(format!("{FILE_PREFIX}{}-{n:05}.bin", std::process::id()));
+            write_file(&file, size, &block, fsync).map_err(|e| {
+                // ENOSPC here is the point of the demo, so say how far it got
+                format!("{} after {} MiB: {e}", file.display(), (bytes - left) / MIB)
+            })?;
+            left -= size;
+            files += 1;
+        }
+        let elapsed = start.elapsed();
+        Ok(Written {
+            target,
+            path: dir.to_path_buf(),
+            written_mb: mb,
+            files,
+            fsync,
+            elapsed_ms: elapsed.as_millis() as u64,
+            mb_per_s: mb as f64 / elapsed.as_secs_f64().max(1e-6),
+        })
+    }
+
+    /// Removes the files this app wrote under `target`; returns the MiB freed.
+    pub fn clean(&self, target: Target) -> Result<u64, String> {
+        let _one_writer = self.writing.lock().unwrap();
+        let dir = self.path(target);
+        let mut freed = 0;
+        for (path, len) in fill_files(dir) {
+            fs::remove_file(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            freed += len;
+        }
+        Ok(freed / MIB)
+    }
+
+    /// Bytes of fill files under `target`.
+    pub fn used_bytes(&self, target: Target) -> u64 {
+        usage(self.path(target)).0
+    }
+
+    /// Per target: the directory, what is written there and whether it exists at all.
+    pub fn status(&self) -> Value {
+        let targets: serde_json::Map<String, Value> = Target::ALL
+            .iter()
+            .map(|t| {
+                let dir = self.path(*t);
+                let (bytes, files) = usage(dir);
+                (t.name().to_string(), json!({ "path": dir, "exists": dir.is_dir(), "used_mb": bytes / MIB, "files": files }))
+            })
+            .collect();
+        json!({ "max_mb_per_target": self.max_mb, "targets": targets })
+    }
+}
+
+fn write_file(path: &Path, size: u64, block: &[u8], fsync: bool) -> std::io::Result<()> {
+    let mut f = File::create(path)?;
+    let mut left = size;
+    while left > 0 {
+        let n = left.min(block.len() as u64) as usize;
+        f.write_all(&block[..n])?;
+        left -= n as u64;
+    }
+    if fsync {
+        f.sync_all()?;
+    }
+    Ok(())
+}
+
+fn fill_files(dir: &Path) -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(FILE_PREFIX))
+        .filter_map(|e| Some((e.path(), e.metadata().ok()?.len())))
+        .collect()
+}
+
+/// Bytes and count of the fill files in `dir`, including those of earlier containers of this pod
+/// (an emptyDir outlives container restarts).
+fn usage(dir: &Path) -> (u64, usize) {
+    let files = fill_files(dir);
+    (files.iter().map(|(_, len)| len).sum(), files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_within_the_cap_and_cleans_up() {
+        let root = std::env::temp_dir().join(format!("disk-pressure-test-{}", std::process::id()));
+        let disk = Disk::new(root.join("scratch"), root.join("rootfs"), 100);
+
+        let w = disk.write(Target::EmptyDir, 70, true).unwrap();
+        assert_eq!((w.written_mb, w.files), (70, 2));
+        assert_eq!(usage(&root.join("scratch")), (70 * MIB, 2));
+        assert!(disk.write(Target::EmptyDir, 40, false).is_err(), "over max_mb");
+        assert!(disk.write(Target::EmptyDir, u64::MAX, false).is_err(), "overflowing mb");
+        assert!(disk.write(Target::EmptyDir, u64::MAX / MIB, false).is_err(), "overflowing used + mb");
+        disk.write(Target::Container, 40, false).unwrap();
+
+        let status = disk.status();
+        assert_eq!(status["targets"]["empty_dir"]["used_mb"], 70);
+        assert_eq!(status["targets"]["container"]["files"], 1);
+
+        assert_eq!(disk.clean(Target::EmptyDir).unwrap(), 70);
+        assert_eq!(usage(&root.join("scratch")), (0, 0));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
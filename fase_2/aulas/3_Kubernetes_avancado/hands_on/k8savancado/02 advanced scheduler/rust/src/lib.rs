@@ -0,0 +1,5 @@
+//! Scheduling rules shared by the demo server (`/topology`) and the offline `placement-sim`.
+
+pub mod manifests;
+pub mod placement;
+pub mod simulate;
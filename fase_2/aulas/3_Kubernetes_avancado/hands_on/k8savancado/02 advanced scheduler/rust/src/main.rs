@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
@@ -12,22 +12,68 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::{net::TcpListener, task};
 
-#[derive(Clone, Default)]
+mod disk;
+mod metrics;
+mod pressure;
+mod topology;
+
+use pressure::{Pressure, StartError, MIB};
+
+#[derive(Clone)]
 struct AppState {
     allocations: Arc<Mutex<Vec<Vec<u8>>>>,
+    // cgroup limits, memory budget and background jobs shared by /busy, /alloc and /pressure
+    pressure: Arc<Pressure>,
+    // fill files for ephemeral-storage demos (/disk)
+    disk: Arc<disk::Disk>,
+    // in-cluster Pod/Node lookups for /topology (KUBE_INTROSPECTION=true)
+    introspector: Option<Arc<topology::Introspector>>,
 }
 
 #[tokio::main]
 async fn main() {
-    let state = AppState::default();
+    let limits = pressure::Limits::detect();
+    println!(
+        "Pressure limits: {:.2} CPU ({}), memory {} MiB ({}), budget {} MiB",
+        limits.cpu_cores,
+        limits.cpu_source,
+        limits.memory_bytes.map_or(0, |b| b / MIB),
+        limits.memory_source,
+        limits.budget_bytes() / MIB
+    );
+    let mut state = AppState {
+        allocations: Arc::default(),
+        pressure: Arc::new(Pressure::new(limits)),
+        disk: Arc::new(disk::Disk::from_env()),
+        introspector: None,
+    };
+    match topology::Introspector::from_env().await {
+        Ok(Some(i)) => {
+            println!("Topology introspection enabled (/topology)");
+            state.introspector = Some(Arc::new(i));
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("Topology introspection disabled: {}", e),
+    }
 
     let app = Router::new()
         .route("/", get(root))
         .route("/healthz", get(healthz))
+        .route("/metrics", get(metrics::handler))
         .route("/info", get(info))
+        .route("/topology", get(topology))
         .route("/busy", post(busy))
         .route("/alloc", post(alloc))
         .route("/free", post(free))
+        .route("/disk", get(disk_status))
+        .route("/disk/write", post(disk_write))
+        .route("/disk/clean", post(disk_clean))
+        .route("/pressure", get(pressure_status))
+        .route("/pressure/cpu", post(pressure_cpu))
+        .route("/pressure/memory", post(pressure_memory))
+        .route("/pressure/jobs/:id", get(pressure_job).delete(pressure_cancel))
+        // `layer`, not `route_layer`: 404s go through the fallback and are counted as `unmatched`
+        .layer(axum::middleware::from_fn(metrics::track))
         .with_state(state);
 
     let port = env::var("PORT").unwrap_or_else(|_| "8080".into());
@@ -46,7 +92,7 @@ async fn healthz() -> impl IntoResponse {
     StatusCode::OK
 }
 
-async fn info() -> impl IntoResponse {
+async fn info(State(state): State<AppState>) -> impl IntoResponse {
     let pod_name = env::var("POD_NAME").unwrap_or_else(|_| "unknown".into());
     let node_name = env::var("NODE_NAME").unwrap_or_else(|_| "unknown".into());
     let hostname = env::var("HOSTNAME").unwrap_or_else(|_| "unknown".into());
@@ -54,32 +100,41 @@ async fn info() -> impl IntoResponse {
     Json(json!({
         "pod_name": pod_name,
         "node_name": node_name,
-        "hostname": hostname
+        "hostname": hostname,
+        "disk": state.disk.status()
     }))
 }
 
+async fn topology(State(state): State<AppState>) -> impl IntoResponse {
+    let Some(introspector) = state.introspector else {
+        let hint = "topology introspection is off: set KUBE_INTROSPECTION=true, POD_NAME/POD_NAMESPACE and a service account that can read pods and nodes";
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": hint })));
+    };
+    match introspector.report().await {
+        Ok(report) => (StatusCode::OK, Json(report)),
+        Err(e) => (StatusCode::BAD_GATEWAY, Json(json!({ "error": e.to_string() }))),
+    }
+}
+
 #[derive(Deserialize)]
 struct BusyParams {
     ms: Option<u64>,
     threads: Option<usize>,
 }
 
-async fn busy(Query(params): Query<BusyParams>) -> impl IntoResponse {
+async fn busy(State(state): State<AppState>, Query(params): Query<BusyParams>) -> impl IntoResponse {
     let ms = params.ms.unwrap_or(2000);
-    let threads = params.threads.unwrap_or(1).max(1).min(64);
+    let threads = params.threads.unwrap_or(1).clamp(1, 64);
+    if let Err(e) = state.pressure.check_millis(ms) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e })));
+    }
 
     let mut handles = Vec::with_capacity(threads);
     for _ in 0..threads {
+        let pressure = state.pressure.clone();
         let handle = task::spawn_blocking(move || {
-            let start = Instant::now();
-            let mut x: u64 = 1;
-            while start.elapsed() < Duration::from_millis(ms) {
-                x = x.wrapping_mul(1664525).wrapping_add(1013904223);
-                if x % 97 == 0 {
-                    std::hint::black_box(x);
-                }
-            }
-            x
+            let _busy = pressure.busy();
+            pressure::spin(Instant::now() + Duration::from_millis(ms));
         });
         handles.push(handle);
     }
@@ -87,7 +142,7 @@ async fn busy(Query(params): Query<BusyParams>) -> impl IntoResponse {
     for h in handles {
         let _ = h.await;
     }
-    Json(json!({"status": "ok", "elapsed_ms": ms, "threads": threads}))
+    (StatusCode::OK, Json(json!({"status": "ok", "elapsed_ms": ms, "threads": threads})))
 }
 
 #[derive(Deserialize)]
@@ -105,30 +160,156 @@ async fn alloc(
     let chunks = params.chunks.unwrap_or(1).max(1);
     let do_touch = params.touch.unwrap_or(true);
 
-    let mut guard = state.allocations.lock().unwrap();
-    for _ in 0..chunks {
-        let mut v = vec![0u8; mb * 1024 * 1024];
-        if do_touch {
-            for i in (0..v.len()).step_by(4096) {
-                v[i] = 1;
-            }
-        }
-        guard.push(v);
+    let bytes = (mb as u64).saturating_mul(chunks as u64).saturating_mul(MIB);
+    if let Err(e) = state.pressure.reserve(bytes) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e })));
     }
+    // allocate without the lock, so /free and other allocations are not stuck behind us
+    let fresh = task::spawn_blocking(move || {
+        (0..chunks)
+            .map(|_| if do_touch { pressure::touched(mb * 1024 * 1024) } else { vec![0u8; mb * 1024 * 1024] })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap();
+
+    let mut guard = state.allocations.lock().unwrap();
+    guard.extend(fresh);
     let total_mb: usize = guard.iter().map(|v| v.len() / (1024 * 1024)).sum();
-    Json(json!({
+    (StatusCode::OK, Json(json!({
         "status": "ok",
         "allocated_now_mb": mb * chunks,
         "total_allocated_mb": total_mb
-    }))
+    })))
 }
 
 async fn free(State(state): State<AppState>) -> impl IntoResponse {
     let mut guard = state.allocations.lock().unwrap();
     let freed_mb: usize = guard.iter().map(|v| v.len() / (1024 * 1024)).sum();
     guard.clear();
+    state.pressure.release(freed_mb as u64 * MIB);
     Json(json!({
         "status": "ok",
         "freed_mb": freed_mb
     }))
 }
+
+async fn pressure_status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.pressure.status())
+}
+
+#[derive(Deserialize)]
+struct CpuPressureParams {
+    percent: Option<f64>,
+    seconds: Option<u64>,
+}
+
+/// Background CPU load as a share of the container's CPU limit, e.g. `?percent=80&seconds=60`.
+async fn pressure_cpu(
+    State(state): State<AppState>,
+    Query(params): Query<CpuPressureParams>,
+) -> impl IntoResponse {
+    let started = state.pressure.start_cpu(params.percent.unwrap_or(80.0), params.seconds.unwrap_or(60));
+    job_response(started)
+}
+
+#[derive(Deserialize)]
+struct MemoryPressureParams {
+    percent: Option<f64>,
+    mb: Option<u64>,
+    seconds: Option<u64>,
+}
+
+/// Background memory held for a while, as a share of the memory limit (`percent`) or in `mb`.
+async fn pressure_memory(
+    State(state): State<AppState>,
+    Query(params): Query<MemoryPressureParams>,
+) -> impl IntoResponse {
+    let bytes = match (params.mb, params.percent, state.pressure.limits.memory_bytes) {
+        (Some(mb), _, _) => mb.saturating_mul(MIB),
+        (None, Some(p), Some(limit)) if p > 0.0 => (limit as f64 * p / 100.0) as u64,
+        (None, Some(_), None) => return job_response(Err(StartError::Invalid("no memory limit detected: use mb instead of percent".into()))),
+        _ => return job_response(Err(StartError::Invalid("pass percent (of the memory limit) or mb".into()))),
+    };
+    job_response(state.pressure.start_memory(bytes, params.seconds.unwrap_or(60)).map_err(Into::into))
+}
+
+async fn pressure_job(State(state): State<AppState>, Path(id): Path<u64>) -> impl IntoResponse {
+    match state.pressure.job(id) {
+        Some(job) => (StatusCode::OK, Json(json!(job))),
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": format!("no job {id}") }))),
+    }
+}
+
+async fn pressure_cancel(State(state): State<AppState>, Path(id): Path<u64>) -> impl IntoResponse {
+    match state.pressure.cancel(id) {
+        Some(job) => (StatusCode::ACCEPTED, Json(json!(job))),
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": format!("no job {id}") }))),
+    }
+}
+
+fn job_response(started: Result<pressure::JobStatus, StartError>) -> (StatusCode, Json<serde_json::Value>) {
+    match started {
+        Ok(job) => (StatusCode::ACCEPTED, Json(json!(job))),
+        Err(StartError::Invalid(e)) => (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e }))),
+        Err(StartError::Busy(e)) => (StatusCode::TOO_MANY_REQUESTS, Json(json!({ "error": e }))),
+    }
+}
+
+async fn disk_status(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.disk.status())
+}
+
+#[derive(Deserialize)]
+struct DiskWriteParams {
+    mb: Option<u64>,
+    target: Option<String>,
+    fsync: Option<bool>,
+}
+
+/// Writes `mb` MiB to the emptyDir (`target=emptydir`, default) or the container layer (`target=container`).
+async fn disk_write(
+    State(state): State<AppState>,
+    Query(params): Query<DiskWriteParams>,
+) -> impl IntoResponse {
+    let target = match disk::Target::parse(params.target.as_deref().unwrap_or("emptydir")) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    };
+    let (mb, fsync) = (params.mb.unwrap_or(64), params.fsync.unwrap_or(false));
+    let disk = state.disk.clone();
+    match task::spawn_blocking(move || disk.write(target, mb, fsync)).await.unwrap() {
+        Ok(written) => (StatusCode::OK, Json(json!(written))),
+        Err(e) => (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e }))),
+    }
+}
+
+#[derive(Deserialize)]
+struct DiskCleanParams {
+    target: Option<String>,
+}
+
+/// Deletes the fill files of one target, or of both when `target` is omitted.
+async fn disk_clean(
+    State(state): State<AppState>,
+    Query(params): Query<DiskCleanParams>,
+) -> impl IntoResponse {
+    let targets = match params.target.as_deref().map(disk::Target::parse).transpose() {
+        Ok(Some(t)) => vec![t],
+        Ok(None) => disk::Target::ALL.to_vec(),
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    };
+    let disk = state.disk.clone();
+    let cleaned = task::spawn_blocking(move || {
+        targets.into_iter().map(|t| disk.clean(t).map(|mb| (t, mb))).collect::<Result<Vec<_>, _>>()
+    })
+    .await
+    .unwrap();
+    match cleaned {
+        Ok(freed) => {
+            let freed: Vec<_> = freed.into_iter().map(|(t, mb)| json!({ "target": t, "freed_mb": mb })).collect();
+            (StatusCode::OK, Json(json!({ "status": "ok", "freed": freed })))
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e }))),
+    }
+}
@@ -0,0 +1,162 @@
+//! Nodes and pods out of multi-document YAML: Nodes, Pods, workload templates and `kind: List` dumps.
+
+use k8s_openapi::api::core::v1::{
+    Affinity, Node, NodeAffinity, NodeSelector, NodeSelectorRequirement, NodeSelectorTerm, PodTemplateSpec, Toleration,
+};
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::simulate::{node_fit_failures, SimPod};
+
+/// Taints the DaemonSet controller adds tolerations for on every pod it creates.
+const DAEMONSET_TOLERATIONS: [(&str, &str); 7] = [
+    ("node.kubernetes.io/not-ready", "NoExecute"),
+    ("node.kubernetes.io/unreachable", "NoExecute"),
+    ("node.kubernetes.io/disk-pressure", "NoSchedule"),
+    ("node.kubernetes.io/memory-pressure", "NoSchedule"),
+    ("node.kubernetes.io/pid-pressure", "NoSchedule"),
+    ("node.kubernetes.io/unschedulable", "NoSchedule"),
+    ("node.kubernetes.io/network-unavailable", "NoSchedule"),
+];
+
+#[derive(Default)]
+pub struct Manifests {
+    pub nodes: Vec<Node>,
+    /// Pods that already have `spec.nodeName`.
+    pub placed: Vec<SimPod>,
+    pending: Vec<Pending>,
+    /// `kind/name` of the documents that were not used.
+    pub skipped: Vec<String>,
+}
+
+/// A node a DaemonSet has no pod for: its taints, nodeSelector or required nodeAffinity rule it out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DaemonSkip {
+    pub daemonset: String,
+    pub node: String,
+    pub reasons: Vec<String>,
+}
+
+enum Pending {
+    Pod(SimPod),
+    /// One pod per node, created once all the nodes are known.
+    DaemonSet(SimPod),
+}
+
+impl Manifests {
+    /// Adds every document of `yaml`; `replicas` overrides the count of the workloads in it.
+    pub fn add(&mut self, yaml: &str, replicas: Option<i32>) -> Result<(), String> {
+        for doc in serde_yaml::Deserializer::from_str(yaml) {
+            let value = Value::deserialize(doc).map_err(|e| e.to_string())?;
+            if !value.is_null() {
+                self.add_object(value, replicas)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_object(&mut self, value: Value, replicas: Option<i32>) -> Result<(), String> {
+        let kind = value["kind"].as_str().unwrap_or_default().to_string();
+        let name = value["metadata"]["name"].as_str().unwrap_or_default().to_string();
+        let namespace = value["metadata"]["namespace"].as_str().unwrap_or("default").to_string();
+        let err = |e: serde_yaml::Error| format!("{kind}/{name}: {e}");
+        match kind.as_str() {
+            "List" | "NodeList" | "PodList" => {
+                for item in value["items"].as_sequence().cloned().unwrap_or_default() {
+                    self.add_object(item, replicas)?;
+                }
+            }
+            "Node" => self.nodes.push(serde_yaml::from_value(value).map_err(err)?),
+            "Pod" => {
+                let pod = SimPod::from_pod(serde_yaml::from_value(value).map_err(err)?);
+                if pod.node.is_some() {
+                    self.placed.push(pod);
+                } else {
+                    self.pending.push(Pending::Pod(pod));
+                }
+            }
+            "Deployment" | "ReplicaSet" | "StatefulSet" | "ReplicationController" | "Job" | "DaemonSet" => {
+                let template: PodTemplateSpec = serde_yaml::from_value(value["spec"]["template"].clone()).map_err(err)?;
+                let pod = |name: String| SimPod {
+                    name,
+                    namespace: namespace.clone(),
+                    labels: template.metadata.as_ref().and_then(|m| m.labels.clone()).unwrap_or_default(),
+                    spec: template.spec.clone().unwrap_or_default(),
+                    node: None,
+                };
+                if kind == "DaemonSet" {
+                    self.pending.push(Pending::DaemonSet(pod(name)));
+                    return Ok(());
+                }
+                let count_key = if kind == "Job" { "parallelism" } else { "replicas" };
+                let count = match (replicas, &value["spec"][count_key]) {
+                    (Some(n), _) => n,
+                    (None, serde_yaml::Value::Null) => 1,
+                    (None, v) => v.as_i64().and_then(|n| i32::try_from(n).ok())
+                        .ok_or_else(|| format!("{kind}/{name}: spec.{count_key} {v:?} is not a 32-bit integer"))?,
+                };
+                for i in 0..count.max(0) {
+                    // StatefulSet pods are numbered from 0, the others get a stand-in for their random suffix
+                    let ordinal = if kind == "StatefulSet" { i } else { i + 1 };
+                    self.pending.push(Pending::Pod(pod(format!("{name}-{ordinal}"))));
+                }
+            }
+            _ => self.skipped.push(format!("{kind}/{name}")),
+        }
+        Ok(())
+    }
+
+    /// The pods to schedule, in manifest order; DaemonSets expand to one pod pinned to each node that should run it.
+    pub fn pending(&self) -> Vec<SimPod> {
+        self.expand().0
+    }
+
+    /// The nodes each DaemonSet creates no pod for, like the controller's `NodeShouldRunDaemonPod`.
+    pub fn daemon_skipped(&self) -> Vec<DaemonSkip> {
+        self.expand().1
+    }
+
+    fn expand(&self) -> (Vec<SimPod>, Vec<DaemonSkip>) {
+        let (mut pods, mut skipped) = (Vec::new(), Vec::new());
+        for p in &self.pending {
+            match p {
+                Pending::Pod(pod) => pods.push(pod.clone()),
+                Pending::DaemonSet(template) => {
+                    for node in &self.nodes {
+                        let Some(name) = node.metadata.name.as_deref() else { continue };
+                        let pod = daemon_pod(template, name);
+                        let reasons = node_fit_failures(&pod.spec, node);
+                        if reasons.is_empty() {
+                            pods.push(pod);
+                        } else {
+                            skipped.push(DaemonSkip { daemonset: template.name.clone(), node: name.into(), reasons });
+                        }
+                    }
+                }
+            }
+        }
+        (pods, skipped)
+    }
+}
+
+/// What the DaemonSet controller creates for `node`: the template, pinned with a `metadata.name` field term.
+fn daemon_pod(template: &SimPod, node: &str) -> SimPod {
+    let mut pod = template.clone();
+    pod.name = format!("{}-{node}", template.name);
+    let pin = NodeSelectorRequirement { key: "metadata.name".into(), operator: "In".into(), values: Some(vec![node.into()]) };
+    let affinity = pod.spec.affinity.get_or_insert_with(Affinity::default);
+    let node_affinity = affinity.node_affinity.get_or_insert_with(NodeAffinity::default);
+    let required = node_affinity.required_during_scheduling_ignored_during_execution.get_or_insert_with(NodeSelector::default);
+    // the controller replaces any matchFields of the template's terms, and adds a term if there is none
+    if required.node_selector_terms.is_empty() {
+        required.node_selector_terms.push(NodeSelectorTerm::default());
+    }
+    for term in &mut required.node_selector_terms {
+        term.match_fields = Some(vec![pin.clone()]);
+    }
+    let tolerations = pod.spec.tolerations.get_or_insert_with(Vec::new);
+    for (key, effect) in DAEMONSET_TOLERATIONS {
+        tolerations.push(Toleration { key: Some(key.into()), operator: Some("Exists".into()), effect: Some(effect.into()), ..Toleration::default() });
+    }
+    pod
+}
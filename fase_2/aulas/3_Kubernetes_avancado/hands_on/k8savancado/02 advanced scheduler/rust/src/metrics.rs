@@ -0,0 +1,133 @@
+//! Prometheus metrics: HTTP traffic plus what the pressure endpoints currently hold.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_gauge, register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec, Encoder,
+    Gauge, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use std::time::Instant;
+
+use crate::{disk, pressure, AppState};
+
+lazy_static! {
+    static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests by route template",
+        &["method", "path", "status"]
+    ).unwrap();
+    static ref HTTP_DURATION: HistogramVec = register_histogram_vec!(
+        "http_request_duration_seconds",
+        "HTTP request latencies in seconds",
+        &["method", "path"]
+    ).unwrap();
+    static ref HTTP_IN_FLIGHT: IntGauge = register_int_gauge!(
+        "http_requests_in_flight",
+        "HTTP requests being served"
+    ).unwrap();
+
+    // the rest is read from the state on every scrape
+    static ref ALLOC_HELD: IntGauge = register_int_gauge!(
+        "alloc_held_bytes",
+        "Bytes held by /alloc until /free"
+    ).unwrap();
+    static ref PRESSURE_HELD: IntGauge = register_int_gauge!(
+        "pressure_memory_held_bytes",
+        "Bytes reserved by /alloc and memory pressure jobs"
+    ).unwrap();
+    static ref PRESSURE_BUDGET: IntGauge = register_int_gauge!(
+        "pressure_memory_budget_bytes",
+        "Bytes /alloc and memory jobs may hold together (share of the memory limit)"
+    ).unwrap();
+    static ref CPU_LIMIT: Gauge = register_gauge!(
+        "pressure_cpu_limit_cores",
+        "CPU limit CPU pressure percentages refer to"
+    ).unwrap();
+    static ref BUSY_THREADS: IntGauge = register_int_gauge!(
+        "busy_threads",
+        "Threads spinning for /busy or a CPU pressure job"
+    ).unwrap();
+    static ref JOBS: IntGaugeVec = register_int_gauge_vec!(
+        "pressure_jobs",
+        "Pressure jobs still listed by /pressure, by kind and state",
+        &["kind", "state"]
+    ).unwrap();
+    static ref DISK_FILL: IntGaugeVec = register_int_gauge_vec!(
+        "disk_fill_bytes",
+        "Bytes of /disk fill files per target",
+        &["target"]
+    ).unwrap();
+}
+
+/// Decrements `http_requests_in_flight` even when the client goes away and the request future is dropped.
+struct InFlight;
+
+impl InFlight {
+    fn start() -> Self {
+        HTTP_IN_FLIGHT.inc();
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        HTTP_IN_FLIGHT.dec();
+    }
+}
+
+/// Counts every request under its route template (`/pressure/jobs/:id`), never the raw path; paths
+/// that match no route (404) all share `unmatched`.
+pub async fn track(req: Request, next: Next) -> Response {
+    let method = req.method().as_str().to_string();
+    let path = req.extensions().get::<MatchedPath>().map_or("unmatched", |p| p.as_str()).to_string();
+    let _in_flight = InFlight::start();
+    let start = Instant::now();
+    let res = next.run(req).await;
+    HTTP_DURATION.with_label_values(&[&method, &path]).observe(start.elapsed().as_secs_f64());
+    HTTP_REQUESTS.with_label_values(&[&method, &path, res.status().as_str()]).inc();
+    res
+}
+
+pub async fn handler(State(state): State<AppState>) -> impl IntoResponse {
+    let held: usize = state.allocations.lock().unwrap().iter().map(Vec::len).sum();
+    ALLOC_HELD.set(held as i64);
+
+    let p = &state.pressure;
+    PRESSURE_HELD.set(p.held_bytes() as i64);
+    PRESSURE_BUDGET.set(p.limits.budget_bytes() as i64);
+    CPU_LIMIT.set(p.limits.cpu_cores);
+    BUSY_THREADS.set(p.busy_threads() as i64);
+    // reset first, so kinds/states without jobs report 0 instead of their last value
+    JOBS.reset();
+    for kind in ["cpu", "memory"] {
+        for s in ["running", "done", "cancelled"] {
+            JOBS.with_label_values(&[kind, s]).set(0);
+        }
+    }
+    for job in p.jobs() {
+        let kind = match job.target {
+            pressure::Target::Cpu { .. } => "cpu",
+            pressure::Target::Memory { .. } => "memory",
+        };
+        let s = match job.state {
+            pressure::JobState::Running => "running",
+            pressure::JobState::Done => "done",
+            pressure::JobState::Cancelled => "cancelled",
+        };
+        JOBS.with_label_values(&[kind, s]).inc();
+    }
+
+    let disk = state.disk.clone();
+    let fill = tokio::task::spawn_blocking(move || disk::Target::ALL.map(|t| (t, disk.used_bytes(t)))).await.unwrap();
+    for (t, bytes) in fill {
+        DISK_FILL.with_label_values(&[t.name()]).set(bytes as i64);
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
@@ -0,0 +1,534 @@
+//! Bounded CPU/memory pressure: limits come from the container's cgroup (v2), load runs as cancellable jobs.
+
+use lazy_static::lazy_static;
+use prometheus::{register_counter, Counter};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const CGROUP: &str = "/sys/fs/cgroup";
+pub const MIB: u64 = 1024 * 1024;
+/// Memory jobs allocate (and look at the cancel flag) in chunks of this size.
+const CHUNK: u64 = 16 * MIB;
+/// CPU jobs alternate busy/idle inside this period to hit a fractional target.
+const SLICE: Duration = Duration::from_millis(100);
+/// Finished jobs kept around for `GET /pressure`.
+const KEEP_FINISHED: usize = 32;
+/// `spin` reports the CPU it burned at least this often, so `rate()` stays smooth during a long /busy.
+const REPORT_EVERY: Duration = Duration::from_millis(100);
+
+lazy_static! {
+    static ref BUSY_CPU_SECONDS: Counter = register_counter!(
+        "busy_cpu_seconds_total",
+        "On-CPU time burned by busy threads; under cgroup throttling it grows slower than wall time"
+    ).unwrap();
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Limits {
+    pub cpu_cores: f64,
+    pub cpu_source: &'static str,
+    pub memory_bytes: Option<u64>,
+    pub memory_source: &'static str,
+    /// Share of the memory limit everything allocated here may add up to.
+    pub memory_ceiling_percent: u64,
+    /// Cgroup usage at startup (runtime, binary, page cache), taken off the budget.
+    pub baseline_bytes: u64,
+    /// Longest duration a `busy` call or job may run or hold for.
+    pub max_seconds: u64,
+}
+
+impl Limits {
+    /// `PRESSURE_CPU_LIMIT`/`PRESSURE_MEMORY_LIMIT_MB` override the cgroup v2 `cpu.max`/`memory.max`;
+    /// without a limit it falls back to the host CPUs and `MemAvailable`.
+    pub fn detect() -> Self {
+        let env_num = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok()).filter(|v| *v > 0.0);
+        let read = |file: &str| std::fs::read_to_string(format!("{CGROUP}/{file}")).ok();
+
+        let (cpu_cores, cpu_source) = if let Some(cores) = env_num("PRESSURE_CPU_LIMIT") {
+            (cores, "env")
+        } else if let Some(cores) = read("cpu.max").as_deref().and_then(parse_cpu_max) {
+            (cores, "cgroup")
+        } else {
+            (std::thread::available_parallelism().map_or(1, |n| n.get()) as f64, "host")
+        };
+
+        let (memory_bytes, memory_source) = if let Some(mb) = env_num("PRESSURE_MEMORY_LIMIT_MB") {
+            (Some(mb as u64 * MIB), "env")
+        } else if let Some(bytes) = read("memory.max").as_deref().and_then(parse_memory_max) {
+            (Some(bytes), "cgroup")
+        } else if let Some(bytes) = std::fs::read_to_string("/proc/meminfo").ok().as_deref().and_then(parse_mem_available) {
+            (Some(bytes), "meminfo")
+        } else {
+            (None, "unknown")
+        };
+        // only a real cgroup limit also counts what the process already uses
+        let baseline_bytes = match memory_source {
+            "cgroup" => read("memory.current").and_then(|s| s.trim().parse().ok()).unwrap_or(0),
+            _ => 0,
+        };
+
+        let env_u64 = |key: &str, default: u64| std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            cpu_cores,
+            cpu_source,
+            memory_bytes,
+            memory_source,
+            memory_ceiling_percent: env_u64("PRESSURE_MEMORY_CEILING_PERCENT", 90).clamp(1, 100),
+            baseline_bytes,
+            max_seconds: env_u64("PRESSURE_MAX_SECONDS", 600).max(1),
+        }
+    }
+
+    /// Threads all running CPU jobs may use together: what one job at the 200% maximum gets.
+    pub fn cpu_threads(&self) -> usize {
+        ((self.cpu_cores * 2.0).ceil() as usize).clamp(1, 64)
+    }
+
+    /// Bytes the generator may hold at once.
+    pub fn budget_bytes(&self) -> u64 {
+        let ceiling = self.memory_bytes.unwrap_or(0) / 100 * self.memory_ceiling_percent;
+        ceiling.saturating_sub(self.baseline_bytes)
+    }
+}
+
+/// `cpu.max` is `"<quota> <period>"` in microseconds, or `"max <period>"` without a limit.
+fn parse_cpu_max(s: &str) -> Option<f64> {
+    let mut parts = s.split_whitespace();
+    let quota: f64 = parts.next()?.parse().ok()?;
+    let period: f64 = parts.next().unwrap_or("100000").parse().ok()?;
+    (period > 0.0 && quota > 0.0).then(|| quota / period)
+}
+
+/// `memory.max` is a byte count, or `"max"` without a limit.
+fn parse_memory_max(s: &str) -> Option<u64> {
+    s.trim().parse().ok()
+}
+
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    let kb: u64 = meminfo.lines().find_map(|l| l.strip_prefix("MemAvailable:"))?.split_whitespace().next()?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// `cpu.stat` as key/value pairs (`usage_usec`, `nr_throttled`, `throttled_usec`, ...).
+fn cpu_stat() -> BTreeMap<String, u64> {
+    std::fs::read_to_string(format!("{CGROUP}/cpu.stat"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| {
+            let (k, v) = l.split_once(' ')?;
+            Some((k.to_string(), v.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// On-CPU time of the calling thread, as accounted by the kernel scheduler.
+fn thread_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/thread-self/schedstat").ok()?;
+    Some(Duration::from_nanos(stat.split_whitespace().next()?.parse().ok()?))
+}
+
+/// Spins the current thread until `deadline`, adding what it burned to `busy_cpu_seconds_total`.
+pub fn spin(deadline: Instant) {
+    let mut x: u64 = 1;
+    while Instant::now() < deadline {
+        let (wall, cpu) = (Instant::now(), thread_cpu_time());
+        let until = deadline.min(wall + REPORT_EVERY);
+        while Instant::now() < until {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            if x.is_multiple_of(97) {
+                std::hint::black_box(x);
+            }
+        }
+        // wall time when schedstat is not available
+        let burned = cpu.zip(thread_cpu_time()).map_or_else(|| wall.elapsed(), |(a, b)| b.saturating_sub(a));
+        BUSY_CPU_SECONDS.inc_by(burned.as_secs_f64());
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Done,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Target {
+    Cpu { percent: f64, cores: f64, threads: usize },
+    Memory { bytes: u64 },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobStatus {
+    pub id: u64,
+    #[serde(flatten)]
+    pub target: Target,
+    pub state: JobState,
+    pub seconds: u64,
+    pub elapsed_ms: u64,
+    /// Memory jobs: how much of the target is allocated so far.
+    pub held_bytes: u64,
+}
+
+struct Job {
+    target: Target,
+    seconds: u64,
+    started: Instant,
+    finished: Option<Instant>,
+    state: JobState,
+    held: Arc<AtomicU64>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    fn status(&self, id: u64) -> JobStatus {
+        let end = self.finished.unwrap_or_else(Instant::now);
+        JobStatus {
+            id,
+            target: self.target.clone(),
+            state: self.state,
+            seconds: self.seconds,
+            elapsed_ms: end.duration_since(self.started).as_millis() as u64,
+            held_bytes: self.held.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Why a job was not started.
+#[derive(Debug, PartialEq)]
+pub enum StartError {
+    /// Bad parameters or a request that could never fit.
+    Invalid(String),
+    /// Running jobs already use the capacity; retry once they finish.
+    Busy(String),
+}
+
+impl From<String> for StartError {
+    fn from(e: String) -> Self {
+        StartError::Invalid(e)
+    }
+}
+
+/// Counts a busy thread for as long as it lives.
+pub struct BusyGuard<'a>(&'a AtomicUsize);
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub struct Pressure {
+    pub limits: Limits,
+    held: AtomicU64,
+    busy_threads: AtomicUsize,
+    /// Threads promised to running CPU jobs, at most `limits.cpu_threads()`.
+    cpu_threads: AtomicUsize,
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Job>>,
+}
+
+impl Pressure {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            held: AtomicU64::new(0),
+            busy_threads: AtomicUsize::new(0),
+            cpu_threads: AtomicUsize::new(0),
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Takes `bytes` out of the budget, or says why not.
+    pub fn reserve(&self, bytes: u64) -> Result<(), String> {
+        let budget = self.limits.budget_bytes();
+        self.held
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |held| held.checked_add(bytes).filter(|t| *t <= budget))
+            .map(|_| ())
+            .map_err(|held| {
+                format!(
+                    "{} MiB would exceed the budget: {} MiB held of {} MiB ({}% of the {} memory limit, minus the baseline)",
+                    bytes / MIB, held / MIB, budget / MIB, self.limits.memory_ceiling_percent, self.limits.memory_source
+                )
+            })
+    }
+
+    pub fn release(&self, bytes: u64) {
+        self.held.fetch_sub(bytes, Ordering::SeqCst);
+    }
+
+    pub fn held_bytes(&self) -> u64 {
+        self.held.load(Ordering::SeqCst)
+    }
+
+    pub fn busy_threads(&self) -> usize {
+        self.busy_threads.load(Ordering::Relaxed)
+    }
+
+    pub fn busy(&self) -> BusyGuard<'_> {
+        self.busy_threads.fetch_add(1, Ordering::Relaxed);
+        BusyGuard(&self.busy_threads)
+    }
+
+    pub fn check_seconds(&self, seconds: u64) -> Result<(), String> {
+        match seconds {
+            0 => Err("duration must be at least 1s".into()),
+            s if s > self.limits.max_seconds => Err(format!("{s}s is over the {}s maximum (PRESSURE_MAX_SECONDS)", self.limits.max_seconds)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Like `check_seconds` for `/busy`, where `ms=0` is a valid no-op.
+    pub fn check_millis(&self, ms: u64) -> Result<(), String> {
+        let max_ms = self.limits.max_seconds.saturating_mul(1000);
+        match ms {
+            ms if ms > max_ms => Err(format!("{ms}ms is over the {}s maximum (PRESSURE_MAX_SECONDS)", self.limits.max_seconds)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Keeps `percent` of the CPU limit busy for `seconds`; above 100 the cgroup throttles the excess.
+    /// Jobs running together may not use more than `limits.cpu_threads()` threads.
+    pub fn start_cpu(self: &Arc<Self>, percent: f64, seconds: u64) -> Result<JobStatus, StartError> {
+        if !(percent > 0.0 && percent <= 200.0) {
+            return Err(StartError::Invalid(format!("percent must be in (0, 200], got {percent}")));
+        }
+        self.check_seconds(seconds)?;
+        let cores = self.limits.cpu_cores * percent / 100.0;
+        let threads = (cores.ceil() as usize).clamp(1, 64);
+        let max = self.limits.cpu_threads();
+        self.cpu_threads
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| Some(used + threads).filter(|t| *t <= max))
+            .map_err(|used| {
+                StartError::Busy(format!(
+                    "{threads} more threads would exceed the {max} allowed for CPU jobs ({used} in use, 200% of {} cores from {})",
+                    self.limits.cpu_cores, self.limits.cpu_source
+                ))
+            })?;
+        // each thread is busy for this share of every slice
+        let duty = (cores / threads as f64).min(1.0);
+        let (id, cancel, _) = self.insert(Target::Cpu { percent, cores, threads }, seconds);
+
+        let deadline = Instant::now() + Duration::from_secs(seconds);
+        let this = self.clone();
+        std::thread::spawn(move || {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    let (this, cancel) = (this.clone(), cancel.clone());
+                    std::thread::spawn(move || {
+                        let _busy = this.busy();
+                        while Instant::now() < deadline && !cancel.load(Ordering::Relaxed) {
+                            let slice = Instant::now();
+                            spin(slice + SLICE.mul_f64(duty));
+                            std::thread::sleep(SLICE.saturating_sub(slice.elapsed()));
+                        }
+                    })
+                })
+                .collect();
+            for w in workers {
+                let _ = w.join();
+            }
+            this.cpu_threads.fetch_sub(threads, Ordering::SeqCst);
+            this.finish(id);
+        });
+        Ok(self.job(id).expect("just inserted"))
+    }
+
+    /// Allocates and touches `bytes` (reserved up front) and holds them for `seconds`.
+    pub fn start_memory(self: &Arc<Self>, bytes: u64, seconds: u64) -> Result<JobStatus, String> {
+        if bytes == 0 {
+            return Err("nothing to allocate".into());
+        }
+        self.check_seconds(seconds)?;
+        self.reserve(bytes)?;
+        let (id, cancel, held) = self.insert(Target::Memory { bytes }, seconds);
+
+        let deadline = Instant::now() + Duration::from_secs(seconds);
+        let this = self.clone();
+        std::thread::spawn(move || {
+            let mut chunks = Vec::new();
+            let mut left = bytes;
+            while left > 0 && !cancel.load(Ordering::Relaxed) {
+                let size = left.min(CHUNK);
+                chunks.push(touched(size as usize));
+                left -= size;
+                held.fetch_add(size, Ordering::Relaxed);
+            }
+            while Instant::now() < deadline && !cancel.load(Ordering::Relaxed) {
+                std::thread::sleep(SLICE);
+            }
+            drop(chunks);
+            held.store(0, Ordering::Relaxed);
+            this.release(bytes);
+            this.finish(id);
+        });
+        Ok(self.job(id).expect("just inserted"))
+    }
+
+    pub fn job(&self, id: u64) -> Option<JobStatus> {
+        self.jobs.lock().unwrap().get(&id).map(|j| j.status(id))
+    }
+
+    /// Asks a running job to stop; it winds down within one slice/chunk.
+    pub fn cancel(&self, id: u64) -> Option<JobStatus> {
+        let mut jobs = self.jobs.lock().unwrap();
+        let job = jobs.get_mut(&id)?;
+        if job.state == JobState::Running {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+        Some(job.status(id))
+    }
+
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs.lock().unwrap().iter().map(|(id, j)| j.status(*id)).collect()
+    }
+
+    pub fn status(&self) -> Value {
+        let jobs = self.jobs();
+        json!({
+            "limits": self.limits,
+            "memory": {
+                "budget_bytes": self.limits.budget_bytes(),
+                "held_bytes": self.held_bytes(),
+                "cgroup_current_bytes": std::fs::read_to_string(format!("{CGROUP}/memory.current")).ok().and_then(|s| s.trim().parse::<u64>().ok()),
+            },
+            "cpu": {
+                "busy_threads": self.busy_threads(),
+                "cgroup_stat": cpu_stat(),
+            },
+            "jobs": jobs,
+        })
+    }
+
+    fn insert(&self, target: Target, seconds: u64) -> (u64, Arc<AtomicBool>, Arc<AtomicU64>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (cancel, held) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicU64::new(0)));
+        let job = Job {
+            target,
+            seconds,
+            started: Instant::now(),
+            finished: None,
+            state: JobState::Running,
+            held: held.clone(),
+            cancel: cancel.clone(),
+        };
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(id, job);
+        // ids grow with time, so the first finished ones are the oldest
+        let finished: Vec<u64> = jobs.iter().filter(|(_, j)| j.state != JobState::Running).map(|(id, _)| *id).collect();
+        for old in finished.iter().take(finished.len().saturating_sub(KEEP_FINISHED)) {
+            jobs.remove(old);
+        }
+        (id, cancel, held)
+    }
+
+    fn finish(&self, id: u64) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&id) {
+            job.finished = Some(Instant::now());
+            job.state = if job.cancel.load(Ordering::Relaxed) { JobState::Cancelled } else { JobState::Done };
+        }
+    }
+}
+
+/// A zeroed buffer with one byte written per page, so it counts as resident.
+pub fn touched(len: usize) -> Vec<u8> {
+    let mut v = vec![0u8; len];
+    for i in (0..v.len()).step_by(4096) {
+        v[i] = 1;
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(memory_mb: u64) -> Limits {
+        Limits {
+            cpu_cores: 0.5,
+            cpu_source: "env",
+            memory_bytes: Some(memory_mb * MIB),
+            memory_source: "env",
+            memory_ceiling_percent: 50,
+            baseline_bytes: 0,
+            max_seconds: 5,
+        }
+    }
+
+    #[test]
+    fn parses_cgroup_files() {
+        assert_eq!(parse_cpu_max("150000 100000\n"), Some(1.5));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_memory_max("268435456\n"), Some(256 * MIB));
+        assert_eq!(parse_memory_max("max\n"), None);
+        assert_eq!(parse_mem_available("MemTotal: 8000 kB\nMemAvailable:    2048 kB\n"), Some(2 * MIB));
+    }
+
+    #[test]
+    fn reservations_stop_at_the_budget() {
+        let p = Pressure::new(limits(100));
+        assert_eq!(p.limits.budget_bytes(), 50 * MIB);
+        p.reserve(30 * MIB).unwrap();
+        assert!(p.reserve(30 * MIB).is_err());
+        p.reserve(20 * MIB).unwrap();
+        p.release(50 * MIB);
+        assert_eq!(p.held_bytes(), 0);
+    }
+
+    #[test]
+    fn busy_millis_are_checked_against_max_seconds() {
+        let p = Pressure::new(limits(100));
+        assert!(p.check_millis(0).is_ok());
+        assert!(p.check_millis(500).is_ok());
+        assert!(p.check_millis(5000).is_ok());
+        assert!(p.check_millis(5001).is_err());
+    }
+
+    #[test]
+    fn jobs_run_and_cancel() {
+        let p = Arc::new(Pressure::new(limits(256)));
+        assert!(p.start_cpu(50.0, 6).is_err(), "over max_seconds");
+        assert!(p.start_memory(200 * MIB, 1).is_err(), "over budget");
+
+        let cpu = p.start_cpu(100.0, 5).unwrap();
+        assert!(matches!(cpu.target, Target::Cpu { threads: 1, .. }));
+        let mem = p.start_memory(32 * MIB, 5).unwrap();
+        assert_eq!(p.held_bytes(), 32 * MIB);
+
+        p.cancel(cpu.id).unwrap();
+        p.cancel(mem.id).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while [cpu.id, mem.id].iter().any(|id| p.job(*id).unwrap().state == JobState::Running) {
+            assert!(Instant::now() < deadline, "jobs did not stop");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(p.job(cpu.id).unwrap().state, JobState::Cancelled);
+        assert_eq!(p.held_bytes(), 0);
+        assert_eq!(p.busy_threads(), 0);
+    }
+
+    #[test]
+    fn concurrent_cpu_jobs_share_the_thread_limit() {
+        // 0.5 cores: one job at 200% already takes the single thread allowed
+        let p = Arc::new(Pressure::new(limits(256)));
+        assert_eq!(p.limits.cpu_threads(), 1);
+        let first = p.start_cpu(50.0, 5).unwrap();
+        assert!(matches!(p.start_cpu(50.0, 5), Err(StartError::Busy(_))));
+
+        p.cancel(first.id).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while p.job(first.id).unwrap().state == JobState::Running {
+            assert!(Instant::now() < deadline, "job did not stop");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let second = p.start_cpu(50.0, 5).unwrap();
+        p.cancel(second.id).unwrap();
+    }
+}
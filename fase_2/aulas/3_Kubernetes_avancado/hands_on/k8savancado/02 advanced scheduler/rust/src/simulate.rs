@@ -0,0 +1,375 @@
+//! Offline kube-scheduler: filters and scores every node for one pod at a time, against the pods already placed.
+//!
+//! Models NodeUnschedulable, TaintToleration, NodeAffinity (nodeSelector included), InterPodAffinity and
+//! PodTopologySpread with the default plugin weights. Resources, ports, volumes and preemption are not modeled.
+
+use k8s_openapi::api::core::v1::{Node, Pod, PodAffinityTerm, PodSpec, Taint, TopologySpreadConstraint};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::placement::{label_selector_matches, node_labels, node_taints, term_matches, tolerates};
+
+/// Default weights of the score plugins (kube-scheduler `default` profile).
+pub const NODE_AFFINITY_WEIGHT: i64 = 2;
+pub const TAINT_TOLERATION_WEIGHT: i64 = 3;
+pub const INTER_POD_AFFINITY_WEIGHT: i64 = 2;
+pub const TOPOLOGY_SPREAD_WEIGHT: i64 = 2;
+const MAX_NODE_SCORE: i64 = 100;
+
+/// A pod as the simulator sees it: identity, labels, spec and, once placed, its node.
+#[derive(Clone, Debug)]
+pub struct SimPod {
+    pub name: String,
+    pub namespace: String,
+    pub labels: BTreeMap<String, String>,
+    pub spec: PodSpec,
+    pub node: Option<String>,
+}
+
+impl SimPod {
+    pub fn from_pod(pod: Pod) -> Self {
+        let spec = pod.spec.unwrap_or_default();
+        Self {
+            name: pod.metadata.name.unwrap_or_default(),
+            namespace: pod.metadata.namespace.unwrap_or_else(|| "default".into()),
+            labels: pod.metadata.labels.unwrap_or_default(),
+            node: spec.node_name.clone(),
+            spec,
+        }
+    }
+}
+
+/// Normalized (0-100) score of each plugin, and their weighted sum.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scores {
+    pub node_affinity: i64,
+    pub taint_toleration: i64,
+    pub inter_pod_affinity: i64,
+    pub topology_spread: i64,
+    pub total: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct NodeResult {
+    pub node: String,
+    /// Why the node was filtered out; empty when it is feasible.
+    pub failures: Vec<String>,
+    /// Only for feasible nodes.
+    pub scores: Option<Scores>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Decision {
+    pub results: Vec<NodeResult>,
+    pub chosen: Option<String>,
+    /// Other nodes with the same top score (kube-scheduler picks one of them at random).
+    pub tied_with: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Cluster {
+    pub nodes: Vec<Node>,
+    /// Pods with a node, whether read from the manifests or placed by `schedule`.
+    pub placed: Vec<SimPod>,
+}
+
+/// Raw, not yet normalized, plugin scores of one node.
+struct Raw {
+    node_affinity: i64,
+    untolerated_prefer_no_schedule: i64,
+    inter_pod_affinity: i64,
+    // None when the node lacks a key of a ScheduleAnyway constraint
+    spread: Option<i64>,
+}
+
+impl Cluster {
+    pub fn new(mut nodes: Vec<Node>, placed: Vec<SimPod>) -> Self {
+        // ties go to the first node, so keep that deterministic
+        nodes.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+        Self { nodes, placed }
+    }
+
+    /// Runs the filters and scores for `pod` and, when a node fits, places the pod there.
+    pub fn schedule(&mut self, mut pod: SimPod) -> Decision {
+        let decision = self.evaluate(&pod);
+        if let Some(node) = &decision.chosen {
+            pod.node = Some(node.clone());
+            self.placed.push(pod);
+        }
+        decision
+    }
+
+    pub fn evaluate(&self, pod: &SimPod) -> Decision {
+        let by_name: HashMap<&str, &Node> = self.nodes.iter().filter_map(|n| Some((n.metadata.name.as_deref()?, n))).collect();
+        let placed: Vec<(&SimPod, &Node)> =
+            self.placed.iter().filter_map(|p| Some((p, *by_name.get(p.node.as_deref()?)?))).collect();
+
+        let mut results = Vec::new();
+        let mut raws = Vec::new();
+        for node in &self.nodes {
+            let failures = self.filter(pod, node, &placed);
+            if failures.is_empty() {
+                raws.push((results.len(), self.raw_scores(pod, node, &placed)));
+            }
+            results.push(NodeResult { node: node.metadata.name.clone().unwrap_or_default(), failures, scores: None });
+        }
+
+        let soft_spread = pod.spec.topology_spread_constraints.iter().flatten().any(|c| c.when_unsatisfiable == "ScheduleAnyway");
+        for (i, scores) in normalize(&raws, soft_spread) {
+            results[i].scores = Some(scores);
+        }
+
+        let best = results.iter().filter_map(|r| r.scores.as_ref().map(|s| s.total)).max();
+        let mut top = results.iter().filter(|r| best.is_some() && r.scores.as_ref().map(|s| s.total) == best);
+        let chosen = top.next().map(|r| r.node.clone());
+        let tied_with = top.map(|r| r.node.clone()).collect();
+        Decision { results, chosen, tied_with }
+    }
+
+    fn filter(&self, pod: &SimPod, node: &Node, placed: &[(&SimPod, &Node)]) -> Vec<String> {
+        let mut why = Vec::new();
+        let spec = &pod.spec;
+        let labels = node_labels(node);
+        let tolerations = spec.tolerations.as_deref().unwrap_or(&[]);
+        let tolerated = |taint: &Taint| tolerations.iter().any(|t| tolerates(t, taint));
+
+        // NodeUnschedulable
+        if node.spec.as_ref().and_then(|s| s.unschedulable) == Some(true) {
+            let cordon = Taint { key: "node.kubernetes.io/unschedulable".into(), effect: "NoSchedule".into(), ..Taint::default() };
+            if !tolerated(&cordon) {
+                why.push("node cordoned (spec.unschedulable)".to_string());
+            }
+        }
+
+        // TaintToleration, NodeAffinity
+        why.extend(node_fit_failures(spec, node));
+
+        // InterPodAffinity: the pod's own required terms
+        let affinity = spec.affinity.as_ref();
+        let required_affinity = affinity.and_then(|a| a.pod_affinity.as_ref()).and_then(|a| a.required_during_scheduling_ignored_during_execution.as_deref());
+        for term in required_affinity.unwrap_or(&[]) {
+            if matching_in_domain(term, &pod.namespace, node, placed).is_empty() {
+                // the first pod of a group that wants to be with itself may go anywhere
+                let anywhere = placed.iter().any(|(p, _)| selects(term, &pod.namespace, p));
+                if anywhere || !selects(term, &pod.namespace, pod) {
+                    why.push(format!("podAffinity {}: no pod matching the selector in domain {}", term.topology_key, domain_str(node, &term.topology_key)));
+                }
+            }
+        }
+        let required_anti = affinity.and_then(|a| a.pod_anti_affinity.as_ref()).and_then(|a| a.required_during_scheduling_ignored_during_execution.as_deref());
+        for term in required_anti.unwrap_or(&[]) {
+            let there = matching_in_domain(term, &pod.namespace, node, placed);
+            if !there.is_empty() {
+                why.push(format!("podAntiAffinity {}: {} already in {}", term.topology_key, there.join(","), domain_str(node, &term.topology_key)));
+            }
+        }
+        // ...and the required anti-affinity of the pods already placed, which works both ways
+        for (other, other_node) in placed {
+            let terms = other.spec.affinity.as_ref().and_then(|a| a.pod_anti_affinity.as_ref())
+                .and_then(|a| a.required_during_scheduling_ignored_during_execution.as_deref());
+            for term in terms.unwrap_or(&[]) {
+                if selects(term, &other.namespace, pod) && same_domain(node, other_node, &term.topology_key) {
+                    why.push(format!("podAntiAffinity of {} ({})", other.name, term.topology_key));
+                }
+            }
+        }
+
+        // PodTopologySpread (DoNotSchedule)
+        for c in spec.topology_spread_constraints.iter().flatten().filter(|c| c.when_unsatisfiable == "DoNotSchedule") {
+            let Some(domain) = labels.get(&c.topology_key) else {
+                why.push(format!("topologySpread: node has no label {}", c.topology_key));
+                continue;
+            };
+            let counts = self.spread_counts(pod, c, placed);
+            let self_match = c.label_selector.as_ref().is_some_and(|s| label_selector_matches(s, &pod.labels)) as i64;
+            let min_domains = c.min_domains.unwrap_or(1).max(1) as usize;
+            // fewer domains than minDomains: the global minimum counts as 0
+            let min = if counts.len() < min_domains { 0 } else { counts.values().copied().min().unwrap_or(0) };
+            let skew = counts.get(domain.as_str()).copied().unwrap_or(0) + self_match - min;
+            if skew > c.max_skew as i64 {
+                why.push(format!("topologySpread {}: skew {skew} > maxSkew {} in {domain}", c.topology_key, c.max_skew));
+            }
+        }
+        why
+    }
+
+    /// Matching pods per domain, over the domains of the nodes the constraint considers.
+    fn spread_counts<'a>(&'a self, pod: &SimPod, c: &TopologySpreadConstraint, placed: &[(&SimPod, &'a Node)]) -> BTreeMap<&'a str, i64> {
+        let tolerations = pod.spec.tolerations.as_deref().unwrap_or(&[]);
+        let honors_affinity = c.node_affinity_policy.as_deref() != Some("Ignore");
+        let honors_taints = c.node_taints_policy.as_deref() == Some("Honor");
+        let mut counts: BTreeMap<&str, i64> = self.nodes.iter()
+            .filter(|n| !honors_affinity || (node_selector_matches(&pod.spec, n) && required_node_affinity(&pod.spec, n)))
+            .filter(|n| {
+                !honors_taints || node_taints(n).iter()
+                    .filter(|t| t.effect == "NoSchedule" || t.effect == "NoExecute")
+                    .all(|t| tolerations.iter().any(|tol| tolerates(tol, t)))
+            })
+            .filter_map(|n| node_labels(n).get(&c.topology_key).map(|d| (d.as_str(), 0)))
+            .collect();
+        for (p, n) in placed {
+            let selected = p.namespace == pod.namespace && c.label_selector.as_ref().is_some_and(|s| label_selector_matches(s, &p.labels));
+            if let Some(count) = node_labels(n).get(&c.topology_key).and_then(|d| counts.get_mut(d.as_str())) {
+                *count += selected as i64;
+            }
+        }
+        counts
+    }
+
+    fn raw_scores(&self, pod: &SimPod, node: &Node, placed: &[(&SimPod, &Node)]) -> Raw {
+        let spec = &pod.spec;
+        let affinity = spec.affinity.as_ref();
+
+        let node_affinity = affinity.and_then(|a| a.node_affinity.as_ref())
+            .and_then(|na| na.preferred_during_scheduling_ignored_during_execution.as_ref())
+            .into_iter().flatten()
+            .filter(|t| term_matches(&t.preference, node))
+            .map(|t| t.weight as i64)
+            .sum();
+
+        let tolerations = spec.tolerations.as_deref().unwrap_or(&[]);
+        let untolerated_prefer_no_schedule = node_taints(node).iter()
+            .filter(|t| t.effect == "PreferNoSchedule" && !tolerations.iter().any(|tol| tolerates(tol, t)))
+            .count() as i64;
+
+        // the pod's preferred terms, weighted per matching pod in the node's domain
+        let mut inter_pod_affinity = 0;
+        let preferred = |anti: bool| {
+            let terms = if anti {
+                affinity.and_then(|a| a.pod_anti_affinity.as_ref()).and_then(|a| a.preferred_during_scheduling_ignored_during_execution.as_ref())
+            } else {
+                affinity.and_then(|a| a.pod_affinity.as_ref()).and_then(|a| a.preferred_during_scheduling_ignored_during_execution.as_ref())
+            };
+            terms.into_iter().flatten()
+        };
+        for w in preferred(false) {
+            inter_pod_affinity += w.weight as i64 * matching_in_domain(&w.pod_affinity_term, &pod.namespace, node, placed).len() as i64;
+        }
+        for w in preferred(true) {
+            inter_pod_affinity -= w.weight as i64 * matching_in_domain(&w.pod_affinity_term, &pod.namespace, node, placed).len() as i64;
+        }
+        // the placed pods' terms that select this pod count too (required affinity with weight 1)
+        for (other, other_node) in placed {
+            let Some(a) = other.spec.affinity.as_ref() else { continue };
+            let pa = a.pod_affinity.as_ref();
+            let paa = a.pod_anti_affinity.as_ref();
+            let weighted = pa.and_then(|p| p.required_during_scheduling_ignored_during_execution.as_ref()).into_iter().flatten().map(|t| (1, t))
+                .chain(pa.and_then(|p| p.preferred_during_scheduling_ignored_during_execution.as_ref()).into_iter().flatten().map(|w| (w.weight as i64, &w.pod_affinity_term)))
+                .chain(paa.and_then(|p| p.preferred_during_scheduling_ignored_during_execution.as_ref()).into_iter().flatten().map(|w| (-(w.weight as i64), &w.pod_affinity_term)));
+            for (weight, term) in weighted {
+                if selects(term, &other.namespace, pod) && same_domain(node, other_node, &term.topology_key) {
+                    inter_pod_affinity += weight;
+                }
+            }
+        }
+
+        // ScheduleAnyway constraints: fewer matching pods in the node's domain is better
+        let labels = node_labels(node);
+        let spread = spec.topology_spread_constraints.iter().flatten()
+            .filter(|c| c.when_unsatisfiable == "ScheduleAnyway")
+            .try_fold(0, |sum, c| {
+                let domain = labels.get(&c.topology_key)?;
+                Some(sum + self.spread_counts(pod, c, placed).get(domain.as_str()).copied().unwrap_or(0))
+            });
+
+        Raw { node_affinity, untolerated_prefer_no_schedule, inter_pod_affinity, spread }
+    }
+}
+
+/// Scales every plugin to 0-100 across the feasible nodes, the way each plugin's NormalizeScore does.
+fn normalize(raws: &[(usize, Raw)], soft_spread: bool) -> Vec<(usize, Scores)> {
+    let max_of = |f: &dyn Fn(&Raw) -> i64| raws.iter().map(|(_, r)| f(r)).max().unwrap_or(0);
+    let min_of = |f: &dyn Fn(&Raw) -> i64| raws.iter().map(|(_, r)| f(r)).min().unwrap_or(0);
+    let na_max = max_of(&|r| r.node_affinity);
+    let tt_max = max_of(&|r| r.untolerated_prefer_no_schedule);
+    let (ipa_min, ipa_max) = (min_of(&|r| r.inter_pod_affinity), max_of(&|r| r.inter_pod_affinity));
+    let spreads: Vec<i64> = raws.iter().filter_map(|(_, r)| r.spread).collect();
+    let (pts_min, pts_max) = (spreads.iter().copied().min().unwrap_or(0), spreads.iter().copied().max().unwrap_or(0));
+
+    raws.iter()
+        .map(|(i, r)| {
+            let node_affinity = if na_max == 0 { 0 } else { r.node_affinity * MAX_NODE_SCORE / na_max };
+            let taint_toleration = if tt_max == 0 { MAX_NODE_SCORE } else { MAX_NODE_SCORE - r.untolerated_prefer_no_schedule * MAX_NODE_SCORE / tt_max };
+            let inter_pod_affinity = if ipa_max == ipa_min { 0 } else { (r.inter_pod_affinity - ipa_min) * MAX_NODE_SCORE / (ipa_max - ipa_min) };
+            let topology_spread = match r.spread {
+                _ if !soft_spread => 0,
+                None => 0,
+                Some(_) if pts_max == 0 => MAX_NODE_SCORE,
+                Some(s) => MAX_NODE_SCORE * (pts_max + pts_min - s) / pts_max,
+            };
+            let total = NODE_AFFINITY_WEIGHT * node_affinity
+                + TAINT_TOLERATION_WEIGHT * taint_toleration
+                + INTER_POD_AFFINITY_WEIGHT * inter_pod_affinity
+                + TOPOLOGY_SPREAD_WEIGHT * topology_spread;
+            (*i, Scores { node_affinity, taint_toleration, inter_pod_affinity, topology_spread, total })
+        })
+        .collect()
+}
+
+/// The TaintToleration (NoSchedule/NoExecute) and NodeAffinity filters alone, which only look at the node:
+/// what the DaemonSet controller checks before creating a pod for it.
+pub fn node_fit_failures(spec: &PodSpec, node: &Node) -> Vec<String> {
+    let mut why = Vec::new();
+    let labels = node_labels(node);
+    let tolerations = spec.tolerations.as_deref().unwrap_or(&[]);
+    for taint in node_taints(node).iter().filter(|t| t.effect == "NoSchedule" || t.effect == "NoExecute") {
+        if !tolerations.iter().any(|t| tolerates(t, taint)) {
+            why.push(format!("taint {} not tolerated", taint_str(taint)));
+        }
+    }
+    for (key, want) in spec.node_selector.iter().flatten() {
+        if labels.get(key) != Some(want) {
+            why.push(format!("nodeSelector {key}={want} (node: {})", labels.get(key).map_or("-", String::as_str)));
+        }
+    }
+    if !required_node_affinity(spec, node) {
+        why.push("nodeAffinity required: no nodeSelectorTerm matches".to_string());
+    }
+    why
+}
+
+fn node_selector_matches(spec: &PodSpec, node: &Node) -> bool {
+    let labels = node_labels(node);
+    spec.node_selector.iter().flatten().all(|(k, v)| labels.get(k) == Some(v))
+}
+
+fn required_node_affinity(spec: &PodSpec, node: &Node) -> bool {
+    spec.affinity.as_ref()
+        .and_then(|a| a.node_affinity.as_ref())
+        .and_then(|na| na.required_during_scheduling_ignored_during_execution.as_ref())
+        .is_none_or(|r| r.node_selector_terms.iter().any(|t| term_matches(t, node)))
+}
+
+/// Whether `term`, declared by a pod in `owner_ns`, selects `pod`.
+/// A `namespaceSelector` is taken as "all namespaces": namespace labels are not in the manifests.
+fn selects(term: &PodAffinityTerm, owner_ns: &str, pod: &SimPod) -> bool {
+    let in_namespace = term.namespace_selector.is_some()
+        || match term.namespaces.as_deref() {
+            Some(list) if !list.is_empty() => list.contains(&pod.namespace),
+            _ => pod.namespace == owner_ns,
+        };
+    in_namespace && term.label_selector.as_ref().is_some_and(|s| label_selector_matches(s, &pod.labels))
+}
+
+fn same_domain(a: &Node, b: &Node, key: &str) -> bool {
+    node_labels(a).get(key).is_some_and(|d| node_labels(b).get(key) == Some(d))
+}
+
+/// Names of the placed pods `term` selects in `node`'s domain; none when the node lacks the key.
+fn matching_in_domain(term: &PodAffinityTerm, owner_ns: &str, node: &Node, placed: &[(&SimPod, &Node)]) -> Vec<String> {
+    let names: BTreeSet<&str> = placed.iter()
+        .filter(|(p, n)| same_domain(node, n, &term.topology_key) && selects(term, owner_ns, p))
+        .map(|(p, _)| p.name.as_str())
+        .collect();
+    names.into_iter().map(String::from).collect()
+}
+
+fn taint_str(t: &Taint) -> String {
+    match t.value.as_deref() {
+        Some(v) if !v.is_empty() => format!("{}={v}:{}", t.key, t.effect),
+        _ => format!("{}:{}", t.key, t.effect),
+    }
+}
+
+fn domain_str(node: &Node, key: &str) -> String {
+    node_labels(node).get(key).map_or_else(|| format!("(no {key})"), |d| format!("{key}={d}"))
+}
@@ -0,0 +1,221 @@
+//! In-cluster introspection: reads this pod, its node and its peers, and explains the placement.
+
+use k8s_openapi::api::core::v1::{Node, Pod, PodAffinityTerm};
+use kube::{api::ListParams, Api, Client};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use aula02_scheduler_demo::placement::{
+    label_selector_matches, node_labels, node_taints, requirement_matches, term_matches, tolerates, INSTANCE_TYPE_LABEL,
+    REGION_LABEL, ZONE_LABEL,
+};
+
+pub struct Introspector {
+    client: Client,
+    namespace: String,
+    pod_name: String,
+}
+
+#[derive(Serialize)]
+struct Check {
+    rule: String,
+    matched: bool,
+    detail: Value,
+}
+
+impl Introspector {
+    /// `None` unless `KUBE_INTROSPECTION=true`; needs `POD_NAME`/`POD_NAMESPACE` and the in-cluster service account.
+    pub async fn from_env() -> Result<Option<Self>, String> {
+        if !matches!(std::env::var("KUBE_INTROSPECTION").as_deref(), Ok("true" | "1")) {
+            return Ok(None);
+        }
+        let pod_name = std::env::var("POD_NAME").map_err(|_| "KUBE_INTROSPECTION needs POD_NAME (downward API)")?;
+        let namespace = match std::env::var("POD_NAMESPACE") {
+            Ok(ns) => ns,
+            Err(_) => std::fs::read_to_string("/var/run/secrets/kubernetes.io/serviceaccount/namespace")
+                .map_err(|_| "KUBE_INTROSPECTION needs POD_NAMESPACE (downward API)")?,
+        };
+        let client = Client::try_default().await.map_err(|e| format!("kube client: {e}"))?;
+        Ok(Some(Self { client, namespace, pod_name }))
+    }
+
+    pub async fn report(&self) -> Result<Value, kube::Error> {
+        let pods: Api<Pod> = Api::namespaced(self.client.clone(), &self.namespace);
+        let nodes: Api<Node> = Api::all(self.client.clone());
+        let pod = pods.get(&self.pod_name).await?;
+        let spec = pod.spec.clone().unwrap_or_default();
+        let Some(node_name) = spec.node_name.clone() else {
+            return Ok(json!({ "pod": self.pod_name, "scheduled": false }));
+        };
+        let node = nodes.get(&node_name).await?;
+        let labels = node_labels(&node);
+
+        let mut checks = Vec::new();
+
+        // nodeSelector: every key must be equal
+        for (key, want) in spec.node_selector.iter().flatten() {
+            let have = labels.get(key);
+            checks.push(Check {
+                rule: format!("nodeSelector {key}={want}"),
+                matched: have == Some(want),
+                detail: json!({ "node_value": have }),
+            });
+        }
+
+        // node affinity: one required term must match; preferred terms add their weight to the score
+        let node_affinity = spec.affinity.as_ref().and_then(|a| a.node_affinity.as_ref());
+        if let Some(required) = node_affinity.and_then(|na| na.required_during_scheduling_ignored_during_execution.as_ref()) {
+            for (i, term) in required.node_selector_terms.iter().enumerate() {
+                checks.push(Check {
+                    rule: format!("nodeAffinity required term {i}"),
+                    matched: term_matches(term, &node),
+                    detail: expressions(term.match_expressions.as_deref().unwrap_or(&[]), labels),
+                });
+            }
+        }
+        for term in node_affinity.and_then(|na| na.preferred_during_scheduling_ignored_during_execution.as_ref()).into_iter().flatten() {
+            checks.push(Check {
+                rule: format!("nodeAffinity preferred (weight {})", term.weight),
+                matched: term_matches(&term.preference, &node),
+                detail: expressions(term.preference.match_expressions.as_deref().unwrap_or(&[]), labels),
+            });
+        }
+
+        // taints: NoSchedule/NoExecute ones must be tolerated, PreferNoSchedule only lowers the score
+        let tolerations = spec.tolerations.clone().unwrap_or_default();
+        for taint in node_taints(&node) {
+            let by = tolerations.iter().position(|t| tolerates(t, taint));
+            checks.push(Check {
+                rule: format!("taint {}={}:{}", taint.key, taint.value.as_deref().unwrap_or(""), taint.effect),
+                matched: by.is_some(),
+                detail: json!({ "tolerated_by": by.map(|i| &tolerations[i]) }),
+            });
+        }
+
+        // inter-pod (anti-)affinity and topology spread need the peers and where they run
+        let affinity = spec.affinity.as_ref();
+        let pod_affinity = affinity.and_then(|a| a.pod_affinity.as_ref());
+        let anti_affinity = affinity.and_then(|a| a.pod_anti_affinity.as_ref());
+        let spread = spec.topology_spread_constraints.clone().unwrap_or_default();
+        let has_pod_terms = pod_affinity.is_some() || anti_affinity.is_some() || !spread.is_empty();
+        if has_pod_terms {
+            let (all_nodes, peers) = self.placed_pods(&pods, &nodes).await?;
+            let terms = |req: Option<&Vec<PodAffinityTerm>>, pref: Option<&Vec<k8s_openapi::api::core::v1::WeightedPodAffinityTerm>>| {
+                req.into_iter().flatten().map(|t| (None, t.clone()))
+                    .chain(pref.into_iter().flatten().map(|w| (Some(w.weight), w.pod_affinity_term.clone())))
+                    .collect::<Vec<_>>()
+            };
+            let aff = terms(
+                pod_affinity.and_then(|a| a.required_during_scheduling_ignored_during_execution.as_ref()),
+                pod_affinity.and_then(|a| a.preferred_during_scheduling_ignored_during_execution.as_ref()),
+            );
+            let anti = terms(
+                anti_affinity.and_then(|a| a.required_during_scheduling_ignored_during_execution.as_ref()),
+                anti_affinity.and_then(|a| a.preferred_during_scheduling_ignored_during_execution.as_ref()),
+            );
+            for (kind, list, want_peers) in [("podAffinity", aff, true), ("podAntiAffinity", anti, false)] {
+                for (weight, term) in list {
+                    let domain = labels.get(&term.topology_key);
+                    let same: Vec<&str> = peers.iter()
+                        .filter(|p| p.name != self.pod_name && domain.is_some() && p.node_labels.get(&term.topology_key) == domain)
+                        .filter(|p| term.label_selector.as_ref().is_some_and(|s| label_selector_matches(s, &p.labels)))
+                        .map(|p| p.name.as_str())
+                        .collect();
+                    let how = weight.map_or("required".to_string(), |w| format!("preferred (weight {w})"));
+                    checks.push(Check {
+                        rule: format!("{kind} {how} on {}", term.topology_key),
+                        // a node without the topology key has no peers: affinity fails, anti-affinity holds
+                        matched: if want_peers { !same.is_empty() } else { same.is_empty() },
+                        detail: json!({ "domain": domain, "matching_pods_in_domain": same }),
+                    });
+                }
+            }
+            // domains come from the nodes the pod could use (nodeAffinityPolicy: Honor)
+            let eligible = |n: &Node| {
+                let l = node_labels(n);
+                spec.node_selector.iter().flatten().all(|(k, v)| l.get(k) == Some(v))
+                    && node_affinity.and_then(|na| na.required_during_scheduling_ignored_during_execution.as_ref())
+                        .is_none_or(|r| r.node_selector_terms.iter().any(|t| term_matches(t, n)))
+            };
+            for c in &spread {
+                let mut counts: BTreeMap<&str, usize> = all_nodes.iter()
+                    .filter(|n| eligible(n))
+                    .filter_map(|n| node_labels(n).get(&c.topology_key))
+                    .map(|d| (d.as_str(), 0))
+                    .collect();
+                for p in &peers {
+                    let selected = c.label_selector.as_ref().is_some_and(|s| label_selector_matches(s, &p.labels));
+                    if let Some(n) = p.node_labels.get(&c.topology_key).and_then(|d| counts.get_mut(d.as_str())) {
+                        *n += selected as usize;
+                    }
+                }
+                let skew = counts.values().max().unwrap_or(&0) - counts.values().min().unwrap_or(&0);
+                checks.push(Check {
+                    rule: format!("topologySpread {} maxSkew {} ({})", c.topology_key, c.max_skew, c.when_unsatisfiable),
+                    matched: skew <= c.max_skew as usize,
+                    detail: json!({ "domain": labels.get(&c.topology_key), "pods_per_domain": counts, "skew": skew }),
+                });
+            }
+        }
+
+        Ok(json!({
+            "pod": { "name": self.pod_name, "namespace": self.namespace },
+            "node": {
+                "name": node_name,
+                "zone": labels.get(ZONE_LABEL),
+                "region": labels.get(REGION_LABEL),
+                "instance_type": labels.get(INSTANCE_TYPE_LABEL),
+                "labels": labels,
+                "taints": node_taints(&node),
+            },
+            "spec": {
+                "node_selector": spec.node_selector,
+                "affinity": spec.affinity,
+                "tolerations": spec.tolerations,
+                "topology_spread_constraints": spec.topology_spread_constraints,
+                "priority_class_name": spec.priority_class_name,
+            },
+            "checks": checks,
+        }))
+    }
+
+    /// All nodes, plus the scheduled, unfinished pods in this namespace with the labels of their node.
+    /// Pod (anti-)affinity terms are evaluated against this namespace only.
+    async fn placed_pods(&self, pods: &Api<Pod>, nodes: &Api<Node>) -> Result<(Vec<Node>, Vec<Peer>), kube::Error> {
+        let nodes = nodes.list(&ListParams::default()).await?.items;
+        let by_name: BTreeMap<&str, &Node> = nodes.iter().filter_map(|n| Some((n.metadata.name.as_deref()?, n))).collect();
+        let peers = pods.list(&ListParams::default()).await?
+            .into_iter()
+            .filter(|p| !matches!(p.status.as_ref().and_then(|s| s.phase.as_deref()), Some("Succeeded" | "Failed")))
+            .filter_map(|p| {
+                let node = by_name.get(p.spec.as_ref()?.node_name.as_deref()?)?;
+                Some(Peer {
+                    name: p.metadata.name.clone()?,
+                    labels: p.metadata.labels.clone().unwrap_or_default(),
+                    node_labels: node_labels(node).clone(),
+                })
+            })
+            .collect();
+        Ok((nodes, peers))
+    }
+}
+
+struct Peer {
+    name: String,
+    labels: BTreeMap<String, String>,
+    node_labels: BTreeMap<String, String>,
+}
+
+fn expressions(exprs: &[k8s_openapi::api::core::v1::NodeSelectorRequirement], labels: &BTreeMap<String, String>) -> Value {
+    exprs.iter().map(|r| {
+        let have = labels.get(&r.key);
+        json!({
+            "key": r.key,
+            "operator": r.operator,
+            "values": r.values,
+            "node_value": have,
+            "matched": requirement_matches(r, have.map(String::as_str)),
+        })
+    }).collect()
+}
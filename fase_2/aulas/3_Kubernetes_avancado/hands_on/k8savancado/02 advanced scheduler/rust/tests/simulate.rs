@@ -0,0 +1,185 @@
+use aula02_scheduler_demo::{
+    manifests::Manifests,
+    simulate::{Cluster, Decision},
+};
+
+const KIND_NODES: &str = include_str!("../../sim/nodes-kind.yaml");
+
+/// Schedules every pending pod of `yaml` on the kind nodes; returns each decision by pod name.
+fn run(yaml: &str) -> Vec<(String, Decision)> {
+    let mut m = Manifests::default();
+    m.add(KIND_NODES, None).unwrap();
+    m.add(yaml, None).unwrap();
+    let pending = m.pending();
+    let mut cluster = Cluster::new(m.nodes, m.placed);
+    pending.into_iter().map(|p| (p.name.clone(), cluster.schedule(p))).collect()
+}
+
+fn chosen(decisions: &[(String, Decision)]) -> Vec<Option<&str>> {
+    decisions.iter().map(|(_, d)| d.chosen.as_deref()).collect()
+}
+
+fn failures<'a>(d: &'a Decision, node: &str) -> &'a [String] {
+    &d.results.iter().find(|r| r.node == node).unwrap().failures
+}
+
+fn deployment(name: &str, replicas: u32, pod_spec: &str) -> String {
+    let spec = pod_spec.lines().map(|l| format!("      {l}")).collect::<Vec<_>>().join("\n");
+    format!(
+        "apiVersion: apps/v1\nkind: Deployment\nmetadata: {{ name: {name}, namespace: aula02 }}\nspec:\n  replicas: {replicas}\n  selector: {{ matchLabels: {{ app: {name} }} }}\n  template:\n    metadata: {{ labels: {{ app: {name} }} }}\n    spec:\n{spec}\n"
+    )
+}
+
+#[test]
+fn noisy_canary_only_fits_the_tainted_node_it_tolerates() {
+    let decisions = run(include_str!("../../k8s/11-noisy-canary.yaml"));
+    assert_eq!(chosen(&decisions), [Some("aula02-worker3"); 2]);
+    let first = &decisions[0].1;
+    assert_eq!(failures(first, "aula02-worker"), ["nodeSelector workload=noisy (node: -)"]);
+    assert!(failures(first, "aula02-control-plane")[0].contains("control-plane:NoSchedule not tolerated"));
+}
+
+#[test]
+fn required_anti_affinity_leaves_the_extra_replica_pending() {
+    let yaml = deployment("cache", 3, "\
+affinity:
+  podAntiAffinity:
+    requiredDuringSchedulingIgnoredDuringExecution:
+      - labelSelector: { matchLabels: { app: cache } }
+        topologyKey: kubernetes.io/hostname
+containers: [{ name: app, image: x }]");
+    let decisions = run(&yaml);
+    assert_eq!(chosen(&decisions), [Some("aula02-worker"), Some("aula02-worker2"), None]);
+    let last = &decisions[2].1;
+    // the incoming pod's term, and the symmetric one of the pod already there
+    assert_eq!(failures(last, "aula02-worker2"), [
+        "podAntiAffinity kubernetes.io/hostname: cache-2 already in kubernetes.io/hostname=aula02-worker2",
+        "podAntiAffinity of cache-2 (kubernetes.io/hostname)",
+    ]);
+}
+
+#[test]
+fn spread_counts_tainted_nodes_unless_taints_are_honored() {
+    let spread = |policy: &str| deployment("web", 4, &format!("\
+topologySpreadConstraints:
+  - maxSkew: 1
+    topologyKey: kubernetes.io/hostname
+    whenUnsatisfiable: DoNotSchedule
+    labelSelector: {{ matchLabels: {{ app: web }} }}{policy}
+containers: [{{ name: app, image: x }}]"));
+
+    // default nodeTaintsPolicy=Ignore: the tainted nodes are empty domains, so the minimum stays 0
+    let ignore = run(&spread(""));
+    assert_eq!(chosen(&ignore), [Some("aula02-worker"), Some("aula02-worker2"), None, None]);
+    assert!(failures(&ignore[2].1, "aula02-worker")[0].contains("skew 2 > maxSkew 1"));
+
+    let honor = run(&spread("\n    nodeTaintsPolicy: Honor"));
+    assert_eq!(chosen(&honor), [Some("aula02-worker"), Some("aula02-worker2"), Some("aula02-worker"), Some("aula02-worker2")]);
+}
+
+#[test]
+fn preferred_terms_and_prefer_no_schedule_decide_the_score() {
+    let nodes = "\
+apiVersion: v1
+kind: Node
+metadata: { name: a, labels: { kubernetes.io/hostname: a, disktype: ssd } }
+spec:
+  taints: [{ key: maintenance, effect: PreferNoSchedule }]
+---
+apiVersion: v1
+kind: Node
+metadata: { name: b, labels: { kubernetes.io/hostname: b, disktype: hdd } }
+---
+apiVersion: v1
+kind: Node
+metadata: { name: c, labels: { kubernetes.io/hostname: c, disktype: ssd } }
+";
+    let pod = deployment("db", 1, "\
+affinity:
+  nodeAffinity:
+    preferredDuringSchedulingIgnoredDuringExecution:
+      - weight: 80
+        preference: { matchExpressions: [{ key: disktype, operator: In, values: [ssd] }] }
+      - weight: 20
+        preference: { matchExpressions: [{ key: disktype, operator: In, values: [hdd] }] }
+containers: [{ name: app, image: x }]");
+    let mut m = Manifests::default();
+    m.add(nodes, None).unwrap();
+    m.add(&pod, None).unwrap();
+    let pending = m.pending();
+    let mut cluster = Cluster::new(m.nodes, m.placed);
+    let d = cluster.schedule(pending.into_iter().next().unwrap());
+
+    let score = |node: &str| d.results.iter().find(|r| r.node == node).unwrap().scores.clone().unwrap();
+    assert_eq!((score("a").node_affinity, score("a").taint_toleration), (100, 0));
+    assert_eq!((score("b").node_affinity, score("b").taint_toleration), (25, 100));
+    assert_eq!(score("c").total, 2 * 100 + 3 * 100);
+    assert_eq!(d.chosen.as_deref(), Some("c"));
+}
+
+#[test]
+fn daemonset_gets_one_pod_per_eligible_node_and_placed_pods_count() {
+    let yaml = "\
+apiVersion: apps/v1
+kind: DaemonSet
+metadata: { name: agent, namespace: aula02 }
+spec:
+  selector: { matchLabels: { app: agent } }
+  template:
+    metadata: { labels: { app: agent } }
+    spec:
+      containers: [{ name: agent, image: x }]
+---
+apiVersion: v1
+kind: Pod
+metadata: { name: agent-manual, namespace: aula02, labels: { app: solo } }
+spec:
+  nodeName: aula02-worker2
+  containers: [{ name: app, image: x }]
+";
+    let decisions = run(yaml);
+    let placed: Vec<_> = decisions.iter().map(|(name, d)| (name.as_str(), d.chosen.as_deref())).collect();
+    assert_eq!(placed, [("agent-aula02-worker", Some("aula02-worker")), ("agent-aula02-worker2", Some("aula02-worker2"))]);
+    // only its own node passes the metadata.name pin
+    assert_eq!(failures(&decisions[0].1, "aula02-worker2"), ["nodeAffinity required: no nodeSelectorTerm matches"]);
+
+    // the tainted nodes get no pod at all instead of a Pending one
+    let mut m = Manifests::default();
+    m.add(KIND_NODES, None).unwrap();
+    m.add(yaml, None).unwrap();
+    let skipped: Vec<_> = m.daemon_skipped().into_iter().map(|s| (s.node, s.reasons)).collect();
+    assert_eq!(skipped, [
+        ("aula02-control-plane".to_string(), vec!["taint node-role.kubernetes.io/control-plane:NoSchedule not tolerated".to_string()]),
+        ("aula02-worker3".to_string(), vec!["taint workload=noisy:NoSchedule not tolerated".to_string()]),
+    ]);
+
+    // a nodeSelector in the template narrows the nodes too, and a toleration widens them
+    let narrowed = yaml.replace(
+        "      containers: [{ name: agent, image: x }]",
+        "      nodeSelector: { workload: noisy }\n      tolerations: [{ key: workload, operator: Exists }]\n      containers: [{ name: agent, image: x }]",
+    );
+    assert_eq!(chosen(&run(&narrowed)), [Some("aula02-worker3")]);
+
+    let solo = deployment("solo", 1, "\
+affinity:
+  podAffinity:
+    requiredDuringSchedulingIgnoredDuringExecution:
+      - labelSelector: { matchLabels: { app: solo } }
+        topologyKey: topology.kubernetes.io/zone
+containers: [{ name: app, image: x }]");
+    let with_manual = run(&format!("{yaml}---\n{solo}"));
+    assert_eq!(with_manual.last().unwrap().1.chosen.as_deref(), Some("aula02-worker2"));
+}
+
+#[test]
+fn replica_counts_out_of_range_are_errors() {
+    let mut m = Manifests::default();
+    let huge = deployment("huge", 1, "containers: [{ name: app, image: x }]").replace("replicas: 1", "replicas: 4294967297");
+    let err = m.add(&huge, None).unwrap_err();
+    assert!(err.contains("Deployment/huge: spec.replicas"), "{err}");
+    assert!(m.pending().is_empty());
+
+    // --replicas still wins over the manifest
+    m.add(&huge, Some(2)).unwrap();
+    assert_eq!(m.pending().len(), 2);
+}
@@ -1,8 +1,10 @@
-
-use axum::{routing::get, Json, Router};
+use axum::{extract::State, http::{header, StatusCode}, routing::get, Json, Router};
 use std::env;
 use serde::Serialize;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Serialize)]
 struct Version {
@@ -10,26 +12,217 @@ struct Version {
     hostname: String,
 }
 
-async fn root() -> &'static str { "ok" }
+/// Comportamento de uma release ruim, lido do ambiente; tudo desligado por padrão.
+#[derive(Debug, Default, PartialEq)]
+struct Faults {
+    /// STARTUP_DELAY_SECONDS: /readyz responde 503 até passar esse tempo.
+    startup_delay: Duration,
+    /// NEVER_READY=true: /readyz nunca responde 200 e o rollout para no progressDeadlineSeconds.
+    never_ready: bool,
+    /// CRASH_AFTER_SECONDS: o processo sai com erro depois desse tempo (CrashLoopBackOff).
+    crash_after: Option<Duration>,
+    /// ERROR_RATE: fração (0 a 1) das requisições em / que respondem 500.
+    error_rate: f64,
+    /// LATENCY_MS: atraso de cada requisição em /.
+    latency: Duration,
+}
+
+impl Faults {
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Faults, String> {
+        let seconds = |name: &str| match var(name) {
+            None => Ok(None),
+            Some(v) => v
+                .parse::<f64>()
+                .ok()
+                .and_then(|s| Duration::try_from_secs_f64(s).ok())
+                .map(Some)
+                .ok_or(format!("{}: \"{}\" não é um número de segundos", name, v)),
+        };
+        let never_ready = match var("NEVER_READY").as_deref() {
+            None | Some("false" | "0") => false,
+            Some("true" | "1") => true,
+            Some(v) => return Err(format!("NEVER_READY: \"{}\" deve ser true ou false", v)),
+        };
+        let error_rate = match var("ERROR_RATE") {
+            None => 0.0,
+            Some(v) => v
+                .parse::<f64>()
+                .ok()
+                .filter(|r| (0.0..=1.0).contains(r))
+                .ok_or(format!("ERROR_RATE: \"{}\" deve estar entre 0 e 1", v))?,
+        };
+        let latency = match var("LATENCY_MS") {
+            None => Duration::ZERO,
+            Some(v) => Duration::from_millis(v.parse().map_err(|_| format!("LATENCY_MS: \"{}\" não é um número de milissegundos", v))?),
+        };
+        Ok(Faults {
+            startup_delay: seconds("STARTUP_DELAY_SECONDS")?.unwrap_or_default(),
+            never_ready,
+            crash_after: seconds("CRASH_AFTER_SECONDS")?,
+            error_rate,
+            latency,
+        })
+    }
+}
+
+/// A n-ésima requisição (a partir de 1) falha quando `n * rate` passa de mais um inteiro: com 0.25,
+/// uma em cada quatro, sem depender de sorte para a demo.
+fn fails(n: u64, rate: f64) -> bool {
+    (n as f64 * rate).floor() > ((n - 1) as f64 * rate).floor()
+}
+
+struct AppState {
+    version: String,
+    hostname: String,
+    faults: Faults,
+    started: Instant,
+    requests: AtomicU64,
+    ok: AtomicU64,
+    errors: AtomicU64,
+    latency_micros: AtomicU64,
+}
+
+impl AppState {
+    fn ready(&self) -> bool {
+        !self.faults.never_ready && self.started.elapsed() >= self.faults.startup_delay
+    }
+}
+
+type Shared = State<Arc<AppState>>;
+
+async fn root(State(s): Shared) -> (StatusCode, &'static str) {
+    let start = Instant::now();
+    if !s.faults.latency.is_zero() {
+        tokio::time::sleep(s.faults.latency).await;
+    }
+    let n = s.requests.fetch_add(1, Ordering::Relaxed) + 1;
+    let response = if fails(n, s.faults.error_rate) {
+        s.errors.fetch_add(1, Ordering::Relaxed);
+        (StatusCode::INTERNAL_SERVER_ERROR, "error")
+    } else {
+        s.ok.fetch_add(1, Ordering::Relaxed);
+        (StatusCode::OK, "ok")
+    };
+    s.latency_micros.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    response
+}
+
 async fn healthz() -> &'static str { "ok" }
-async fn readyz() -> &'static str { "ready" }
 
-async fn version() -> Json<Version> {
-    let version = env::var("APP_VERSION").unwrap_or_else(|_| "1.0.0".to_string());
-    let hostname = hostname::get().unwrap_or_default().to_string_lossy().into_owned();
-    Json(Version { version, hostname })
+async fn readyz(State(s): Shared) -> (StatusCode, &'static str) {
+    match s.ready() {
+        true => (StatusCode::OK, "ready"),
+        false => (StatusCode::SERVICE_UNAVAILABLE, "not ready"),
+    }
+}
+
+async fn version(State(s): Shared) -> Json<Version> {
+    Json(Version { version: s.version.clone(), hostname: s.hostname.clone() })
+}
+
+/// Formato texto do Prometheus, tudo com o rótulo `version` para comparar as duas versões no meio do rollout.
+async fn metrics(State(s): Shared) -> ([(header::HeaderName, &'static str); 1], String) {
+    let label = |v: &str| v.replace('\\', "\\\\").replace('"', "\\\"");
+    let (version, hostname) = (label(&s.version), label(&s.hostname));
+    // contadores separados: requisições concorrentes mudam os dois entre uma leitura e outra
+    let (ok, errors) = (s.ok.load(Ordering::Relaxed), s.errors.load(Ordering::Relaxed));
+    let body = format!(
+        "# HELP myapp_info Versão (APP_VERSION) e pod.\n\
+         # TYPE myapp_info gauge\n\
+         myapp_info{{version=\"{version}\",hostname=\"{hostname}\"}} 1\n\
+         # HELP myapp_http_requests_total Requisições em / por código de resposta.\n\
+         # TYPE myapp_http_requests_total counter\n\
+         myapp_http_requests_total{{version=\"{version}\",code=\"200\"}} {ok}\n\
+         myapp_http_requests_total{{version=\"{version}\",code=\"500\"}} {errors}\n\
+         # HELP myapp_http_request_duration_seconds Latência das requisições em /.\n\
+         # TYPE myapp_http_request_duration_seconds summary\n\
+         myapp_http_request_duration_seconds_sum{{version=\"{version}\"}} {sum}\n\
+         myapp_http_request_duration_seconds_count{{version=\"{version}\"}} {count}\n\
+         # HELP myapp_ready 1 quando /readyz responde 200.\n\
+         # TYPE myapp_ready gauge\n\
+         myapp_ready{{version=\"{version}\"}} {ready}\n\
+         # HELP myapp_uptime_seconds Tempo desde o início do processo.\n\
+         # TYPE myapp_uptime_seconds gauge\n\
+         myapp_uptime_seconds{{version=\"{version}\"}} {uptime:.3}\n",
+        count = ok + errors,
+        sum = s.latency_micros.load(Ordering::Relaxed) as f64 / 1e6,
+        ready = s.ready() as u8,
+        uptime = s.started.elapsed().as_secs_f64(),
+    );
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
 }
 
 #[tokio::main]
 async fn main() {
+    let faults = match Faults::from_env(|name| env::var(name).ok().filter(|v| !v.is_empty())) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if faults != Faults::default() {
+        println!("Falhas simuladas: {:?}", faults);
+    }
+    if let Some(after) = faults.crash_after {
+        tokio::spawn(async move {
+            tokio::time::sleep(after).await;
+            eprintln!("CRASH_AFTER_SECONDS: saindo com erro depois de {:?}", after);
+            std::process::exit(1);
+        });
+    }
+    let state = Arc::new(AppState {
+        version: env::var("APP_VERSION").unwrap_or_else(|_| "1.0.0".to_string()),
+        hostname: hostname::get().unwrap_or_default().to_string_lossy().into_owned(),
+        faults,
+        started: Instant::now(),
+        requests: AtomicU64::new(0),
+        ok: AtomicU64::new(0),
+        errors: AtomicU64::new(0),
+        latency_micros: AtomicU64::new(0),
+    });
+
     let app = Router::new()
         .route("/", get(root))
         .route("/healthz", get(healthz))
         .route("/readyz", get(readyz))
-        .route("/version", get(version));
+        .route("/version", get(version))
+        .route("/metrics", get(metrics))
+        .with_state(state);
 
     let port: u16 = env::var("PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(8080);
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     println!("Listening on {}", addr);
     axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app).await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_rate_is_an_exact_fraction() {
+        let failed = |rate| (1..=100).filter(|&n| fails(n, rate)).count();
+        assert_eq!((failed(0.0), failed(0.25), failed(0.5), failed(1.0)), (0, 25, 50, 100));
+        assert_eq!((1..=4).map(|n| fails(n, 0.25)).collect::<Vec<_>>(), [false, false, false, true]);
+    }
+
+    #[test]
+    fn reads_faults_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            Faults::from_env(move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string()))
+        };
+        assert_eq!(env(&[]), Ok(Faults::default()));
+        let f = env(&[("STARTUP_DELAY_SECONDS", "2.5"), ("NEVER_READY", "true"), ("CRASH_AFTER_SECONDS", "30"), ("ERROR_RATE", "0.1"), ("LATENCY_MS", "250")]).unwrap();
+        assert_eq!(f.startup_delay, Duration::from_millis(2500));
+        assert_eq!((f.never_ready, f.crash_after, f.error_rate), (true, Some(Duration::from_secs(30)), 0.1));
+        assert_eq!(f.latency, Duration::from_millis(250));
+
+        assert!(env(&[("ERROR_RATE", "1.5")]).is_err());
+        assert!(env(&[("NEVER_READY", "sim")]).is_err());
+        assert!(env(&[("CRASH_AFTER_SECONDS", "-1")]).is_err());
+        assert!(env(&[("STARTUP_DELAY_SECONDS", "1e20")]).is_err());
+        assert!(env(&[("CRASH_AFTER_SECONDS", "inf")]).is_err());
+        assert!(env(&[("LATENCY_MS", "0.5")]).is_err());
+    }
+}
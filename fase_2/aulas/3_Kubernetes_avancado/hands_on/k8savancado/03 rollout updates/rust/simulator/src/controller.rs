@@ -0,0 +1,589 @@
+//! Contas dos controllers portadas do Kubernetes. Deployment (`pkg/controller/deployment`):
+//! `ResolveFenceposts`, `MaxUnavailable`, `NewRSNewReplicas`, `cleanupUnhealthyReplicas` e
+//! `scaleDownOldReplicaSetsForRollingUpdate`, além da validação da API para `rollingUpdate`.
+//! Escala proporcional do Deployment durante um rollout (`scale`, `GetReplicaSetProportion`).
+//! StatefulSet (`pkg/controller/statefulset`) e DaemonSet (`pkg/controller/daemon`): quais pods
+//! criar e apagar em cada sync. PodDisruptionBudget (`pkg/controller/disruption`): `disruptionsAllowed`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// `intstr.IntOrString` de `maxSurge`/`maxUnavailable`: inteiro ou percentual inteiro ("25%").
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntOrPercent {
+    Int(u32),
+    Percent(u32),
+}
+
+impl FromStr for IntOrPercent {
+    type Err = String;
+
+    /// Como a API: percentual é só dígitos seguidos de '%' ("25.5%" e "-1" são inválidos).
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        let (digits, percent) = match s.strip_suffix('%') {
+            Some(d) => (d, true),
+            None => (s, false),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(if percent {
+                format!("\"{}\": um percentual precisa ser um número inteiro seguido de '%'", s)
+            } else {
+                format!("\"{}\": precisa ser um inteiro >= 0 ou um percentual (ex.: 25%)", s)
+            });
+        }
+        let n = digits.parse::<u32>().map_err(|e| format!("\"{}\": {}", s, e))?;
+        Ok(if percent { IntOrPercent::Percent(n) } else { IntOrPercent::Int(n) })
+    }
+}
+
+impl fmt::Display for IntOrPercent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntOrPercent::Int(n) => write!(f, "{}", n),
+            IntOrPercent::Percent(p) => write!(f, "{}%", p),
+        }
+    }
+}
+
+/// Como o `intstr` no JSON da API: número ou string com `%`.
+impl serde::Serialize for IntOrPercent {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            IntOrPercent::Int(n) => s.serialize_u32(*n),
+            IntOrPercent::Percent(_) => s.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+impl IntOrPercent {
+    /// `GetScaledValueFromIntOrPercent`: percentual de `total`, arredondado para cima ou para baixo.
+    pub fn scaled(self, total: u32, round_up: bool) -> u32 {
+        match self {
+            IntOrPercent::Int(n) => n,
+            IntOrPercent::Percent(p) => {
+                let raw = p as u64 * total as u64;
+                let v = if round_up { raw.div_ceil(100) } else { raw / 100 };
+                v as u32
+            }
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        matches!(self, IntOrPercent::Int(0) | IntOrPercent::Percent(0))
+    }
+}
+
+/// `ValidateRollingUpdateDeployment` (e a do DaemonSet, igual): o que o apiserver recusaria no `kubectl apply`.
+pub fn validate(max_surge: IntOrPercent, max_unavailable: IntOrPercent) -> Result<(), String> {
+    if let IntOrPercent::Percent(p) = max_unavailable {
+        if p > 100 {
+            return Err(format!("rollingUpdate.maxUnavailable: \"{}\" não pode passar de 100%", max_unavailable));
+        }
+    }
+    if max_surge.is_zero() && max_unavailable.is_zero() {
+        return Err("rollingUpdate.maxUnavailable: não pode ser 0 quando maxSurge é 0".to_string());
+    }
+    Ok(())
+}
+
+/// `ResolveFenceposts`: maxSurge arredonda para cima, maxUnavailable para baixo; se os dois
+/// resolvem para 0 (ex.: 1 réplica com 25%/0), maxUnavailable vira 1 para o rollout andar.
+pub fn resolve_fenceposts(max_surge: IntOrPercent, max_unavailable: IntOrPercent, desired: u32) -> (u32, u32) {
+    let surge = max_surge.scaled(desired, true);
+    let unavailable = max_unavailable.scaled(desired, false);
+    if surge == 0 && unavailable == 0 {
+        return (0, 1);
+    }
+    (surge, unavailable)
+}
+
+/// `MaxUnavailable`: nunca mais que as réplicas desejadas (e 0 com 0 réplicas).
+pub fn max_unavailable(desired: u32, resolved_unavailable: u32) -> u32 {
+    resolved_unavailable.min(desired)
+}
+
+/// `spec.replicas` e `status.availableReplicas` de um ReplicaSet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rs {
+    pub replicas: u32,
+    pub available: u32,
+}
+
+/// `NewRSNewReplicas` (RollingUpdate): cresce o ReplicaSet novo até `replicas + maxSurge` pods no
+/// total, sem passar de `replicas`.
+pub fn new_rs_new_replicas(desired: u32, max_surge: u32, old: Rs, new: Rs) -> u32 {
+    if new.replicas >= desired {
+        return desired;
+    }
+    let max_total = desired + max_surge;
+    let current = old.replicas + new.replicas;
+    if current >= max_total {
+        return new.replicas;
+    }
+    new.replicas + (max_total - current).min(desired - new.replicas)
+}
+
+/// `cleanupUnhealthyReplicas`: remove primeiro os pods antigos não disponíveis, até `max_cleanup`.
+pub fn cleanup_unhealthy(old: Rs, max_cleanup: u32) -> u32 {
+    if old.replicas == old.available {
+        return 0;
+    }
+    max_cleanup.min(old.replicas.saturating_sub(old.available))
+}
+
+/// `scaleDownOldReplicaSetsForRollingUpdate`: só remove antigos disponíveis acima do mínimo.
+pub fn scale_down_for_rolling_update(available: u32, min_available: u32, old: Rs) -> u32 {
+    if available <= min_available {
+        return 0;
+    }
+    (available - min_available).min(old.replicas)
+}
+
+/// Pods antigos que saem neste sync (`reconcileOldReplicaSets`): `(limpeza, redução)`.
+pub fn reconcile_old(desired: u32, max_unavailable: u32, old: Rs, new: Rs) -> (u32, u32) {
+    if old.replicas == 0 {
+        return (0, 0);
+    }
+    let min_available = desired - self::max_unavailable(desired, max_unavailable);
+    let new_unavailable = new.replicas.saturating_sub(new.available);
+    let max_scaled_down = (old.replicas + new.replicas) as i64 - min_available as i64 - new_unavailable as i64;
+    if max_scaled_down <= 0 {
+        return (0, 0);
+    }
+    let cleaned = cleanup_unhealthy(old, max_scaled_down as u32);
+    let old = Rs { replicas: old.replicas - cleaned, available: old.available };
+    (cleaned, scale_down_for_rolling_update(old.available + new.available, min_available, old))
+}
+
+/// Pod de um ordinal (StatefulSet) ou de um nó (DaemonSet).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SlotPod {
+    /// Na revisão nova.
+    pub new: bool,
+    pub ready: bool,
+    pub available: bool,
+}
+
+/// Ordinais ou nós em que um sync cria e apaga pods.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Step {
+    pub create: Vec<usize>,
+    pub delete: Vec<usize>,
+}
+
+/// `getStatefulSetMaxUnavailable`: arredonda para baixo, mínimo 1.
+pub fn statefulset_max_unavailable(max_unavailable: IntOrPercent, replicas: u32) -> u32 {
+    max_unavailable.scaled(replicas, false).max(1)
+}
+
+/// `updateStatefulSet`: cria os ordinais que faltam (com `OrderedReady`, um por vez e só depois
+/// que os anteriores estão Ready) e, em `RollingUpdate`, apaga pods antigos do maior ordinal até
+/// `partition`. Sem `maxUnavailable` é um por vez, esperando cada ordinal já atualizado ficar
+/// disponível; com ele, apaga até `maxUnavailable` menos os indisponíveis do conjunto todo.
+pub fn statefulset_step(
+    pods: &[Option<SlotPod>],
+    partition: u32,
+    max_unavailable: Option<u32>,
+    parallel: bool,
+    rolling: bool,
+) -> Step {
+    let mut step = Step::default();
+    for (i, pod) in pods.iter().enumerate() {
+        match pod {
+            None => {
+                step.create.push(i);
+                if !parallel {
+                    return step;
+                }
+            }
+            Some(p) if !p.ready && !parallel => return step,
+            Some(_) => {}
+        }
+    }
+    if !rolling || !step.create.is_empty() {
+        return step;
+    }
+
+    let pods: Vec<SlotPod> = pods.iter().flatten().copied().collect();
+    let update_min = (partition as usize).min(pods.len());
+    match max_unavailable {
+        None => {
+            for target in (update_min..pods.len()).rev() {
+                if !pods[target].new {
+                    step.delete.push(target);
+                    break;
+                }
+                if !pods[target].available {
+                    break;
+                }
+            }
+        }
+        Some(max) => {
+            let unavailable = pods.iter().filter(|p| !p.available).count();
+            let budget = (max as usize).saturating_sub(unavailable);
+            step.delete = (update_min..pods.len()).rev().filter(|&i| !pods[i].new).take(budget).collect();
+        }
+    }
+    step
+}
+
+/// `updatedDesiredNodeCounts`: no DaemonSet os dois percentuais arredondam para cima; se os dois
+/// dão 0, maxUnavailable vira 1.
+pub fn daemonset_fenceposts(max_surge: IntOrPercent, max_unavailable: IntOrPercent, nodes: u32) -> (u32, u32) {
+    let surge = max_surge.scaled(nodes, true);
+    let unavailable = max_unavailable.scaled(nodes, true);
+    if nodes > 0 && surge == 0 && unavailable == 0 {
+        return (0, 1);
+    }
+    (surge, unavailable)
+}
+
+/// `manage` + `rollingUpdate` do DaemonSet para os pods `(antigo, novo)` de cada nó. Nó sem pod
+/// ganha um pod novo. Sem maxSurge, apaga pods antigos (o `manage` recria) enquanto os nós
+/// indisponíveis couberem em maxUnavailable; com maxSurge, sobe o pod novo ao lado do antigo em até
+/// maxSurge nós e apaga o antigo quando o novo fica disponível. Pod antigo indisponível é sempre
+/// substituído.
+pub fn daemonset_step(nodes: &[(Option<SlotPod>, Option<SlotPod>)], max_surge: u32, max_unavailable: u32, rolling: bool) -> Step {
+    let empty = nodes.iter().enumerate().filter(|(_, n)| n.0.is_none() && n.1.is_none()).map(|(i, _)| i);
+    let mut step = Step { create: empty.collect(), delete: Vec::new() };
+    if !rolling {
+        return step;
+    }
+    let mut allowed = Vec::new();
+    let mut candidates = Vec::new();
+    if max_surge == 0 {
+        let mut unavailable = 0;
+        for (i, node) in nodes.iter().enumerate() {
+            match node {
+                (None, None) | (Some(_), Some(_)) => unavailable += 1,
+                (None, Some(new)) => unavailable += !new.available as u32,
+                (Some(old), None) if !old.available => allowed.push(i),
+                (Some(_), None) if unavailable >= max_unavailable => {}
+                (Some(_), None) => candidates.push(i),
+            }
+        }
+        let remaining = (max_unavailable.saturating_sub(unavailable + allowed.len() as u32) as usize).min(candidates.len());
+        step.delete = allowed;
+        step.delete.extend(&candidates[..remaining]);
+    } else {
+        let mut surge = 0;
+        for (i, node) in nodes.iter().enumerate() {
+            match node {
+                (None, _) => {}
+                (Some(old), None) if !old.available => allowed.push(i),
+                (Some(_), None) if surge >= max_surge => {}
+                (Some(_), None) => candidates.push(i),
+                (Some(_), Some(new)) if !new.available => surge += 1,
+                (Some(_), Some(_)) => step.delete.push(i),
+            }
+        }
+        let remaining = (max_surge.saturating_sub(surge) as usize).min(candidates.len());
+        step.create.extend(allowed);
+        step.create.extend(&candidates[..remaining]);
+    }
+    step
+}
+
+/// `scale` do Deployment quando `spec.replicas` muda (HPA, `kubectl scale`) no meio de um rollout.
+/// `sizes` são os `spec.replicas` dos ReplicaSets do mais antigo ao mais novo; `max_before` é a
+/// anotação `max-replicas` deles (réplicas + maxSurge de antes da mudança). Com um só ReplicaSet
+/// ativo, ele vai direto para `replicas`; senão a diferença até `replicas + maxSurge` é dividida
+/// proporcionalmente, do maior para o menor, e a sobra fica com o primeiro. (O caso do ReplicaSet
+/// novo saturado não acontece aqui: a anotação de réplicas desejadas ainda tem o valor antigo.)
+pub fn proportional_scale(replicas: u32, max_surge: u32, max_before: u32, sizes: &[u32]) -> Vec<u32> {
+    let mut out = sizes.to_vec();
+    let mut active: Vec<usize> = (0..sizes.len()).filter(|&i| sizes[i] > 0).collect();
+    if active.len() <= 1 {
+        // FindActiveOrLatest
+        if let Some(i) = active.first().copied().or(sizes.len().checked_sub(1)) {
+            out[i] = replicas;
+        }
+        return out;
+    }
+
+    let allowed = if replicas > 0 { (replicas + max_surge) as i64 } else { 0 };
+    let to_add = allowed - active.iter().map(|&i| sizes[i] as i64).sum::<i64>();
+    // ReplicaSetsBySizeNewer ao crescer, ReplicaSetsBySizeOlder ao diminuir
+    active.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]).then(if to_add > 0 { b.cmp(&a) } else { a.cmp(&b) }));
+    let mut added = 0i64;
+    let mut target: Vec<i64> = sizes.iter().map(|&s| s as i64).collect();
+    for &i in &active {
+        let size = sizes[i] as i64;
+        if to_add == 0 || to_add == added {
+            continue;
+        }
+        // getReplicaSetFraction
+        let fraction = if replicas == 0 {
+            -size
+        } else if max_before == 0 {
+            0
+        } else {
+            (size as f64 * (replicas + max_surge) as f64 / max_before as f64).round() as i64 - size
+        };
+        let left = to_add - added;
+        let proportion = if to_add > 0 { fraction.min(left) } else { fraction.max(left) };
+        target[i] += proportion;
+        added += proportion;
+    }
+    target[active[0]] += to_add - added;
+    for (o, t) in out.iter_mut().zip(target) {
+        *o = t.max(0) as u32;
+    }
+    out
+}
+
+/// `spec.minAvailable` ou `spec.maxUnavailable` de um PodDisruptionBudget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Pdb {
+    MinAvailable(IntOrPercent),
+    MaxUnavailable(IntOrPercent),
+}
+
+impl fmt::Display for Pdb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pdb::MinAvailable(v) => write!(f, "minAvailable={}", v),
+            Pdb::MaxUnavailable(v) => write!(f, "maxUnavailable={}", v),
+        }
+    }
+}
+
+/// `disruptionsAllowed` do controller de disruption: pods Ready acima dos saudáveis exigidos.
+/// `scale` é o `spec.replicas` do Deployment (percentuais arredondam para cima); `minAvailable`
+/// inteiro não olha a escala. Sem folga, `kubectl drain` e outras evictions ficam bloqueadas;
+/// o próprio rollout não passa pelo PDB.
+pub fn disruptions_allowed(pdb: Pdb, scale: u32, healthy: u32) -> u32 {
+    let desired = match pdb {
+        Pdb::MinAvailable(IntOrPercent::Int(n)) => n,
+        Pdb::MinAvailable(v) => v.scaled(scale, true),
+        Pdb::MaxUnavailable(v) => scale.saturating_sub(v.scaled(scale, true)),
+    };
+    healthy.saturating_sub(desired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iop(s: &str) -> IntOrPercent {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_like_intstr() {
+        assert_eq!(iop("3"), IntOrPercent::Int(3));
+        assert_eq!(iop("25%"), IntOrPercent::Percent(25));
+        for bad in ["", "%", "25.5%", "-1", "-10%", "oops", "1e2"] {
+            assert!(bad.parse::<IntOrPercent>().is_err(), "{bad:?} deveria ser inválido");
+        }
+    }
+
+    #[test]
+    fn resolve_fenceposts_cases() {
+        // (maxSurge, maxUnavailable, replicas) -> (surge, unavailable); casos do TestResolveFenceposts
+        let cases = [
+            ("0", "0", 0, (0, 1)),
+            ("39%", "39%", 10, (4, 3)),
+            ("0%", "39%", 10, (0, 3)),
+            ("39%", "0%", 10, (4, 0)),
+            ("0%", "0%", 10, (0, 1)),
+            // padrão da API e exemplos da documentação
+            ("25%", "25%", 10, (3, 2)),
+            ("25%", "25%", 4, (1, 1)),
+            ("25%", "25%", 1, (1, 0)),
+            ("0", "25%", 3, (0, 1)),
+            ("0", "10%", 5, (0, 1)),
+            ("100%", "0", 7, (7, 0)),
+            ("2", "150", 10, (2, 150)),
+        ];
+        for (surge, unavailable, replicas, expected) in cases {
+            assert_eq!(resolve_fenceposts(iop(surge), iop(unavailable), replicas), expected, "{surge}/{unavailable} com {replicas}");
+        }
+    }
+
+    #[test]
+    fn validation_matches_the_apiserver() {
+        assert!(validate(iop("0"), iop("0")).is_err());
+        assert!(validate(iop("0%"), iop("0")).is_err());
+        assert!(validate(iop("0"), iop("101%")).is_err());
+        assert!(validate(iop("200%"), iop("100%")).is_ok());
+        // só o valor literal é validado; 10% de 5 réplicas resolve para 0 e vira 1 depois
+        assert!(validate(iop("0"), iop("10%")).is_ok());
+    }
+
+    #[test]
+    fn max_unavailable_cases() {
+        // (replicas, maxUnavailable resolvido) -> esperado; casos do TestMaxUnavailable
+        for (replicas, unavailable, expected) in [(10, 5, 5), (10, 10, 10), (5, 10, 5), (0, 10, 0), (10, 0, 0)] {
+            assert_eq!(max_unavailable(replicas, unavailable), expected);
+        }
+    }
+
+    #[test]
+    fn new_replica_set_scaling_cases() {
+        // (replicas, maxSurge, antigos, novos) -> novos; casos do TestDeploymentController_reconcileNewReplicaSet
+        let cases = [
+            (10, 0, 10, 0, 0),
+            (10, 2, 10, 0, 2),
+            (10, 2, 5, 0, 7),
+            (10, 2, 10, 2, 2),
+            (10, 2, 2, 11, 10),
+            (1, 1, 1, 2, 1),
+        ];
+        for (replicas, surge, old, new, expected) in cases {
+            let rs = |n| Rs { replicas: n, available: n };
+            assert_eq!(new_rs_new_replicas(replicas, surge, rs(old), rs(new)), expected, "{replicas} {surge} {old} {new}");
+        }
+    }
+
+    #[test]
+    fn old_replica_set_scaling_cases() {
+        // (replicas, maxUnavailable, antigos, novos, antigos prontos, novos prontos) -> antigos restantes;
+        // casos do TestDeploymentController_reconcileOldReplicaSets
+        let cases = [
+            (10, 0, 10, 0, 10, 0, 10),
+            (10, 2, 10, 0, 10, 0, 8),
+            // os 2 não disponíveis saem na limpeza
+            (10, 2, 10, 0, 8, 0, 8),
+            // 1 não disponível na limpeza e 1 disponível na redução
+            (10, 2, 10, 0, 9, 0, 8),
+            // pods novos indisponíveis não liberam mais antigos
+            (10, 2, 8, 2, 8, 0, 8),
+        ];
+        for (replicas, unavailable, old, new, old_ready, new_ready, expected) in cases {
+            let (cleaned, scaled) = reconcile_old(
+                replicas,
+                unavailable,
+                Rs { replicas: old, available: old_ready },
+                Rs { replicas: new, available: new_ready },
+            );
+            assert_eq!(old - cleaned - scaled, expected, "{replicas} {unavailable} {old} {new} {old_ready} {new_ready}");
+        }
+    }
+
+    #[test]
+    fn cleanup_and_scale_down_cases() {
+        // (antigos, prontos, limite) -> removidos; casos do TestDeploymentController_cleanupUnhealthyReplicas
+        for (old, ready, max_cleanup, expected) in [(10, 8, 1, 1), (10, 8, 3, 2), (10, 8, 0, 0), (10, 10, 3, 0)] {
+            assert_eq!(cleanup_unhealthy(Rs { replicas: old, available: ready }, max_cleanup), expected);
+        }
+        // (disponíveis, mínimo, antigos) -> removidos; casos do TestDeploymentController_scaleDownOldReplicaSetsForRollingUpdate
+        for (available, min_available, old, expected) in [(10, 10, 10, 0), (10, 8, 10, 2), (8, 8, 10, 0), (0, 8, 10, 0), (12, 8, 3, 3)] {
+            assert_eq!(scale_down_for_rolling_update(available, min_available, Rs { replicas: old, available: old }), expected);
+        }
+    }
+
+    fn sts(pods: &[(bool, bool)]) -> Vec<Option<SlotPod>> {
+        pods.iter().map(|&(new, available)| Some(SlotPod { new, ready: available, available })).collect()
+    }
+
+    #[test]
+    fn statefulset_update_cases() {
+        let old = (false, true);
+        let fresh = (true, true);
+        let starting = (true, false);
+        // (pods por ordinal, partition, maxUnavailable, Parallel) -> apagados
+        let cases = [
+            // um por vez, do maior ordinal para o menor
+            (&[old, old, old][..], 0, None, false, &[2][..]),
+            (&[old, old, fresh][..], 0, None, false, &[1][..]),
+            // espera o ordinal recém-atualizado ficar disponível
+            (&[old, old, starting][..], 0, None, true, &[][..]),
+            // partition: ordinais abaixo dela ficam na versão antiga
+            (&[old, old, fresh, fresh][..], 2, None, false, &[][..]),
+            (&[old, old, old, old][..], 3, None, false, &[3][..]),
+            // maxUnavailable conta os indisponíveis de todos os ordinais
+            (&[old, old, old, old, old][..], 0, Some(2), false, &[4, 3][..]),
+            (&[old, old, old, starting, fresh][..], 0, Some(2), true, &[2][..]),
+            (&[old, old, old, fresh, fresh][..], 4, Some(3), false, &[][..]),
+        ];
+        for (pods, partition, max_unavailable, parallel, expected) in cases {
+            let step = statefulset_step(&sts(pods), partition, max_unavailable, parallel, true);
+            assert_eq!(step.delete, expected, "{pods:?} partition={partition} max={max_unavailable:?}");
+        }
+    }
+
+    #[test]
+    fn statefulset_ordered_ready_creates_one_ordinal_at_a_time() {
+        let ready = Some(SlotPod { new: true, ready: true, available: true });
+        let starting = Some(SlotPod { new: true, ready: false, available: false });
+        assert_eq!(statefulset_step(&[ready, None, None], 0, Some(2), false, true).create, [1]);
+        assert_eq!(statefulset_step(&[ready, None, None], 0, Some(2), true, true).create, [1, 2]);
+        // OrderedReady: um ordinal não Ready segura criação e atualização dos maiores
+        assert_eq!(statefulset_step(&[starting, None], 0, None, false, true), Step::default());
+        // OnDelete: só recria
+        assert_eq!(statefulset_step(&[ready, ready], 0, None, false, false), Step::default());
+        assert_eq!(statefulset_max_unavailable(IntOrPercent::Percent(10), 5), 1);
+        assert_eq!(statefulset_max_unavailable(IntOrPercent::Percent(50), 5), 2);
+    }
+
+    #[test]
+    fn daemonset_cases() {
+        assert_eq!(daemonset_fenceposts(IntOrPercent::Int(0), IntOrPercent::Percent(10), 5), (0, 1));
+        assert_eq!(daemonset_fenceposts(IntOrPercent::Percent(25), IntOrPercent::Int(0), 5), (2, 0));
+        assert_eq!(daemonset_fenceposts(IntOrPercent::Int(0), IntOrPercent::Int(0), 3), (0, 1));
+
+        let old = |available| Some(SlotPod { new: false, ready: available, available });
+        let new = |available| Some(SlotPod { new: true, ready: available, available });
+        // sem surge: apaga até maxUnavailable, contando nós com pod novo indisponível
+        let nodes = [(old(true), None), (old(true), None), (old(true), None), (None, new(false))];
+        assert_eq!(daemonset_step(&nodes, 0, 2, true).delete, [0]);
+        // pod antigo indisponível sai mesmo sem orçamento
+        let nodes = [(old(false), None), (old(true), None), (None, new(false))];
+        assert_eq!(daemonset_step(&nodes, 0, 1, true).delete, [0]);
+        // nó vazio: o manage cria
+        assert_eq!(daemonset_step(&[(None, None), (old(true), None)], 0, 1, true), Step { create: vec![0], delete: vec![] });
+        // com surge: novo ao lado do antigo; antigo sai quando o novo está disponível
+        let nodes = [(old(true), new(true)), (old(true), new(false)), (old(true), None), (old(true), None)];
+        assert_eq!(daemonset_step(&nodes, 2, 0, true), Step { create: vec![2], delete: vec![0] });
+        // OnDelete: só o manage
+        assert_eq!(daemonset_step(&[(old(true), None)], 1, 0, false), Step::default());
+    }
+
+    #[test]
+    fn proportional_scaling_cases() {
+        // (replicas, maxSurge, max-replicas antes, tamanhos do mais antigo ao mais novo) -> tamanhos;
+        // casos do TestScale
+        let cases = [
+            (12, 0, 10, &[0, 10][..], &[0, 12][..]),
+            (5, 0, 10, &[0, 10][..], &[0, 5][..]),
+            (10, 0, 5, &[3, 2][..], &[6, 4][..]),
+            (3, 0, 5, &[3, 2][..], &[2, 1][..]),
+            (4, 0, 9, &[1, 8][..], &[0, 4][..]),
+            (10, 0, 7, &[2, 3, 2][..], &[3, 4, 3][..]),
+            (8, 0, 13, &[3, 8, 2][..], &[2, 5, 1][..]),
+            // a sobra vai para o ReplicaSet mais novo ao crescer e para o mais antigo ao diminuir
+            (4, 0, 3, &[1, 1, 1][..], &[1, 1, 2][..]),
+            (2, 0, 3, &[1, 1, 1][..], &[0, 1, 1][..]),
+            (5, 0, 4, &[2, 2][..], &[2, 3][..]),
+            (0, 0, 6, &[3, 3][..], &[0, 0][..]),
+            (6, 0, 0, &[0, 0][..], &[0, 6][..]),
+            // com surge
+            (20, 2, 12, &[6, 6][..], &[11, 11][..]),
+            (50, 6, 13, &[8, 5][..], &[34, 22][..]),
+        ];
+        for (replicas, surge, before, sizes, expected) in cases {
+            assert_eq!(proportional_scale(replicas, surge, before, sizes), expected, "{before} -> {replicas} {sizes:?}");
+        }
+    }
+
+    #[test]
+    fn pdb_disruptions_allowed_cases() {
+        // (pdb, escala, pods Ready) -> evictions permitidas
+        let cases = [
+            (Pdb::MinAvailable(IntOrPercent::Int(3)), 3, 3, 0),
+            (Pdb::MinAvailable(IntOrPercent::Int(3)), 10, 4, 1),
+            (Pdb::MinAvailable(IntOrPercent::Percent(28)), 7, 3, 1),
+            (Pdb::MinAvailable(IntOrPercent::Percent(100)), 4, 4, 0),
+            (Pdb::MaxUnavailable(IntOrPercent::Int(1)), 6, 6, 1),
+            (Pdb::MaxUnavailable(IntOrPercent::Int(1)), 6, 5, 0),
+            (Pdb::MaxUnavailable(IntOrPercent::Percent(30)), 10, 10, 3),
+            (Pdb::MaxUnavailable(IntOrPercent::Percent(25)), 6, 8, 4),
+        ];
+        for (pdb, scale, healthy, expected) in cases {
+            assert_eq!(disruptions_allowed(pdb, scale, healthy), expected, "{pdb} escala={scale} ready={healthy}");
+        }
+    }
+}
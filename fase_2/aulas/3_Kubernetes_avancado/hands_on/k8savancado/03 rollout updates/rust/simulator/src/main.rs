@@ -1,103 +1,268 @@
-
 use clap::Parser;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+mod controller;
+mod manifest;
+mod model;
+mod output;
+
+use controller::{IntOrPercent, Pdb};
+use manifest::Manifest;
+use model::{Outcome, Params, PodManagement, ScaleEvent, Strategy, Workload};
+use output::Format;
 
-/// Simula RollingUpdate: dado N, maxUnavailable (n/%), maxSurge (n/%).
+/// Simula um rollout no tempo (Deployment, StatefulSet ou DaemonSet): dado N, maxUnavailable (n/%),
+/// maxSurge (n/%), startup dos pods, readiness probe, minReadySeconds, falhas e
+/// progressDeadlineSeconds. Com --file, lê tudo isso do manifesto em YAML; argumentos passados na
+/// linha de comando têm precedência. Opcionalmente, um PodDisruptionBudget e mudanças de escala do
+/// HPA no meio do rollout.
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    /// Réplicas desejadas (N)
-    replicas: u32,
-    /// maxUnavailable (ex.: 0, 2, 25%)
-    max_unavailable: String,
-    /// maxSurge (ex.: 0, 2, 25%)
-    max_surge: String,
+    /// Réplicas desejadas (N); no DaemonSet, número de nós
+    replicas: Option<u32>,
+    /// maxUnavailable (ex.: 0, 2, 25%; padrão 25% no Deployment, 1 no StatefulSet e no DaemonSet)
+    max_unavailable: Option<IntOrPercent>,
+    /// maxSurge (ex.: 0, 2, 25%; padrão 25% no Deployment, 0 no DaemonSet; StatefulSet não tem)
+    max_surge: Option<IntOrPercent>,
+    /// Deployment, StatefulSet ou DaemonSet em YAML ("-" lê da entrada padrão); repita para juntar
+    /// arquivos (ex.: o PodDisruptionBudget)
+    #[arg(short, long)]
+    file: Vec<PathBuf>,
+    /// Tipo de workload [padrão: Deployment]
+    #[arg(long, value_enum)]
+    kind: Option<Workload>,
+    /// spec.strategy.type (Deployment) ou spec.updateStrategy.type (StatefulSet, DaemonSet)
+    #[arg(long, value_enum)]
+    strategy: Option<Strategy>,
+    /// StatefulSet: updateStrategy.rollingUpdate.partition [padrão: 0]
+    #[arg(long)]
+    partition: Option<u32>,
+    /// StatefulSet: podManagementPolicy [padrão: OrderedReady]
+    #[arg(long, value_enum)]
+    pod_management_policy: Option<PodManagement>,
+    /// Formato da saída
+    #[arg(short, long, value_enum, default_value_t = Format::Table)]
+    output: Format,
+    /// Segundos até a aplicação de um pod novo responder à readiness probe [padrão: 5; 0 sem readinessProbe]
+    #[arg(long)]
+    startup: Option<f64>,
+    /// Variação aleatória (±s) do startup de cada pod
+    #[arg(long, default_value_t = 0.0)]
+    startup_jitter: f64,
+    /// initialDelaySeconds da readiness probe [padrão: 0]
+    #[arg(long)]
+    initial_delay: Option<f64>,
+    /// periodSeconds da readiness probe [padrão: 10]
+    #[arg(long)]
+    probe_period: Option<f64>,
+    /// minReadySeconds [padrão: 0]
+    #[arg(long)]
+    min_ready_seconds: Option<f64>,
+    /// Fração (0 a 1) dos pods novos que entram em CrashLoopBackOff
+    #[arg(long, default_value_t = 0.0)]
+    failure_rate: f64,
+    /// Pods quebrados passam na readiness e caem após N segundos (sem isso, nunca ficam Ready)
+    #[arg(long)]
+    crash_after: Option<f64>,
+    /// progressDeadlineSeconds do Deployment; nos outros, quanto tempo sem progresso até desistir [padrão: 600]
+    #[arg(long)]
+    progress_deadline: Option<f64>,
+    /// Semente do gerador aleatório (jitter e falhas)
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    /// PodDisruptionBudget com minAvailable (ex.: 5, 80%)
+    #[arg(long, conflicts_with = "pdb_max_unavailable")]
+    pdb_min_available: Option<IntOrPercent>,
+    /// PodDisruptionBudget com maxUnavailable (ex.: 1, 20%)
+    #[arg(long)]
+    pdb_max_unavailable: Option<IntOrPercent>,
+    /// Deployment RollingUpdate: o HPA muda as réplicas no meio do rollout, em SEGUNDOS:RÉPLICAS
+    /// (ex.: 20:10); pode repetir
+    #[arg(long, value_name = "SEGUNDOS:RÉPLICAS", value_parser = parse_hpa)]
+    hpa: Vec<(f64, u32)>,
 }
 
-fn parse_threshold(replica_count: u32, value: &str, round_up: bool) -> u32 {
-    if value.ends_with('%') {
-        let pct_str = &value[..value.len()-1];
-        if let Ok(pct) = pct_str.parse::<f32>() {
-            let raw = (pct / 100.0) * replica_count as f32;
-            if round_up { raw.ceil() as u32 } else { raw.floor() as u32 }
-        } else {
-            eprintln!("Valor percentual inválido: {}", value);
-            0
-        }
-    } else {
-        value.parse::<u32>().unwrap_or_else(|_| {
-            eprintln!("Valor numérico inválido: {}", value);
-            0
-        })
+fn parse_hpa(s: &str) -> Result<(f64, u32), String> {
+    let invalid = || format!("\"{}\" não é SEGUNDOS:RÉPLICAS (ex.: 20:10)", s);
+    let (at, replicas) = s.split_once(':').ok_or_else(invalid)?;
+    let at: f64 = at.parse().map_err(|_| invalid())?;
+    if !at.is_finite() || at < 0.0 {
+        return Err(invalid());
     }
+    Ok((at, replicas.parse().map_err(|_| invalid())?))
 }
 
-fn perform_rollout_step(
-    replica_count: u32,
-    old_running: u32,
-    new_running: u32,
-    max_unavail_count: u32,
-    max_surge_count: u32,
-) -> (u32, u32, u32, u32) {
-    let can_create = max_surge_count;
-    let can_delete = max_unavail_count.min(old_running);
-
-    let to_create = can_create.min(replica_count.saturating_sub(new_running));
-    let new_running_after = new_running + to_create;
-
-    let need_to_reduce_to = replica_count;
-    let excess_total = new_running_after + old_running;
-    let to_delete = if excess_total > need_to_reduce_to {
-        (excess_total - need_to_reduce_to).min(can_delete)
-    } else {
-        0
-    };
+fn read_manifest(paths: &[PathBuf]) -> Result<Manifest, String> {
+    let mut docs = Vec::new();
+    for path in paths {
+        let mut text = String::new();
+        let read = if path.as_os_str() == "-" {
+            std::io::stdin().read_to_string(&mut text).map(|_| ())
+        } else {
+            std::fs::read_to_string(path).map(|t| text = t)
+        };
+        read.map_err(|e| format!("Não foi possível ler {}: {}", path.display(), e))?;
+        docs.push(text);
+    }
+    let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    manifest::parse(&docs.join("\n---\n")).map_err(|e| format!("{}: {}", names.join(", "), e))
+}
 
-    let old_running_after = old_running.saturating_sub(to_delete);
-    (new_running_after, old_running_after, to_create, to_delete)
+/// `(maxSurge, maxUnavailable, maxUnavailable definido)` resolvidos como o controller de cada workload faz.
+fn fenceposts(
+    workload: Workload,
+    strategy: Strategy,
+    replicas: u32,
+    max_surge: Option<IntOrPercent>,
+    max_unavailable: Option<IntOrPercent>,
+) -> Result<(u32, u32, bool), String> {
+    if strategy != Strategy::RollingUpdate {
+        return Ok((0, replicas, false));
+    }
+    match workload {
+        Workload::Deployment => {
+            let default = IntOrPercent::Percent(25);
+            let (max_surge, max_unavailable) = (max_surge.unwrap_or(default), max_unavailable.unwrap_or(default));
+            controller::validate(max_surge, max_unavailable)?;
+            let (surge, unavailable) = controller::resolve_fenceposts(max_surge, max_unavailable, replicas);
+            if max_unavailable.scaled(replicas, false) == 0 && unavailable == 1 {
+                eprintln!(
+                    "maxSurge={} e maxUnavailable={} resolvem para 0 com {} réplica(s): o controller usa maxUnavailable=1.",
+                    max_surge, max_unavailable, replicas
+                );
+            }
+            Ok((surge, unavailable, true))
+        }
+        Workload::StatefulSet => {
+            if max_surge.is_some_and(|s| s.scaled(replicas, true) > 0) {
+                return Err("StatefulSet não tem maxSurge".to_string());
+            }
+            match max_unavailable {
+                None => Ok((0, 1, false)),
+                Some(IntOrPercent::Int(0) | IntOrPercent::Percent(0)) => Err("rollingUpdate.maxUnavailable precisa ser maior que 0".to_string()),
+                Some(v) => {
+                    controller::validate(IntOrPercent::Int(0), v)?;
+                    Ok((0, controller::statefulset_max_unavailable(v, replicas), true))
+                }
+            }
+        }
+        Workload::DaemonSet => {
+            let (max_surge, max_unavailable) = (max_surge.unwrap_or(IntOrPercent::Int(0)), max_unavailable.unwrap_or(IntOrPercent::Int(1)));
+            controller::validate(max_surge, max_unavailable)?;
+            let (surge, unavailable) = controller::daemonset_fenceposts(max_surge, max_unavailable, replicas);
+            Ok((surge, unavailable, true))
+        }
+    }
 }
 
-fn simulate_rolling_update(replica_count: u32, max_unavailable: &str, max_surge: &str) {
-    let max_unavail_count = parse_threshold(replica_count, max_unavailable, false);
-    let max_surge_count = parse_threshold(replica_count, max_surge, true);
-    println!(
-        "Iniciando rollout: replicas={} max_unavail={} max_surge={}",
-        replica_count, max_unavail_count, max_surge_count
-    );
-    if max_surge_count == 0 && max_unavail_count == 0 {
-        eprintln!("Sem progresso possível: maxSurge e maxUnavailable são 0. Abortando.");
-        return;
+fn params(args: &Args, manifest: Option<&Manifest>) -> Result<Params, String> {
+    let workload = args.kind.or(manifest.map(|m| m.workload)).unwrap_or(Workload::Deployment);
+    let replicas = args.replicas.or(manifest.and_then(|m| m.replicas)).ok_or(match workload {
+        Workload::DaemonSet => "DaemonSet: informe o número de nós (N).",
+        _ => "Informe as réplicas (N) ou um manifesto com --file.",
+    })?;
+    let strategy = args.strategy.or(manifest.map(|m| m.strategy)).unwrap_or(Strategy::RollingUpdate);
+    match (workload, strategy) {
+        (Workload::Deployment, Strategy::OnDelete) => return Err("OnDelete só existe em StatefulSet e DaemonSet.".to_string()),
+        (Workload::StatefulSet | Workload::DaemonSet, Strategy::Recreate) => {
+            return Err("Recreate só existe em Deployment.".to_string())
+        }
+        _ => {}
+    }
+    let partition = args.partition.or(manifest.map(|m| m.partition)).unwrap_or(0);
+    if partition > 0 && workload != Workload::StatefulSet {
+        return Err("--partition só existe em StatefulSet.".to_string());
+    }
+    let surge_spec = args.max_surge.or(manifest.and_then(|m| m.max_surge));
+    let unavailable_spec = args.max_unavailable.or(manifest.and_then(|m| m.max_unavailable));
+    let resolve = |replicas| {
+        fenceposts(workload, strategy, replicas, surge_spec, unavailable_spec).map_err(|e| format!("{:?} inválido: {}", workload, e))
+    };
+    let (max_surge, max_unavailable, max_unavailable_set) = resolve(replicas)?;
+    if !(0.0..=1.0).contains(&args.failure_rate) {
+        return Err(format!("--failure-rate deve estar entre 0 e 1: {}", args.failure_rate));
     }
-    let mut old_running = replica_count;
-    let mut new_running: u32 = 0;
-    let mut step: u32 = 0;
 
-    while new_running < replica_count {
-        step += 1;
-        let (nr, or_, created, deleted) = perform_rollout_step(
-            replica_count,
-            old_running,
-            new_running,
-            max_unavail_count,
-            max_surge_count,
-        );
-        if nr == new_running && or_ == old_running {
-            eprintln!("Nenhum progresso nesta etapa (criados=0, deletados=0). Abortando.");
-            break;
+    if !args.hpa.is_empty() && (workload, strategy) != (Workload::Deployment, Strategy::RollingUpdate) {
+        return Err("--hpa só é simulado em Deployment com RollingUpdate (escala proporcional).".to_string());
+    }
+    let mut scale_events = Vec::new();
+    for &(at, replicas) in &args.hpa {
+        let (max_surge, max_unavailable, _) = resolve(replicas)?;
+        scale_events.push(ScaleEvent { at, replicas, max_surge, max_unavailable });
+    }
+    let pdb = match (args.pdb_min_available, args.pdb_max_unavailable) {
+        (Some(v), _) => Some(Pdb::MinAvailable(v)),
+        (_, Some(v)) => Some(Pdb::MaxUnavailable(v)),
+        _ => manifest.and_then(|m| m.pdb),
+    };
+    match pdb {
+        Some(Pdb::MinAvailable(IntOrPercent::Percent(v)) | Pdb::MaxUnavailable(IntOrPercent::Percent(v))) if v > 100 => {
+            return Err(format!("PodDisruptionBudget inválido: {}% passa de 100%", v));
+        }
+        // o controller de disruption só acha a escala de Deployment, ReplicaSet e StatefulSet
+        Some(Pdb::MinAvailable(IntOrPercent::Percent(_)) | Pdb::MaxUnavailable(_)) if workload == Workload::DaemonSet => {
+            return Err("PodDisruptionBudget de DaemonSet só funciona com minAvailable inteiro.".to_string());
         }
-        new_running = nr;
-        old_running = or_;
-        println!(
-            "Step {}: +{} novos (total novos={}), -{} antigos (restam antigos={}) -> pods disponíveis agora: {}",
-            step, created, new_running, deleted, old_running, new_running + old_running
-        );
+        _ => {}
     }
-    println!(
-        "Rollout completo em {} etapas. Novos pods = {}, Antigos restantes = {}",
-        step, new_running, old_running
-    );
+
+    // sem readinessProbe o pod fica Ready assim que o container sobe
+    let readiness = match manifest {
+        Some(m) => m.readiness,
+        None => Some((0.0, 10.0)),
+    };
+    if readiness.is_none() && args.startup.is_none() {
+        eprintln!("Manifesto sem readinessProbe: os pods ficam Ready assim que o container sobe (--startup 0).");
+    }
+    let (probe_delay, probe_period) = readiness.unwrap_or((0.0, 0.0));
+    Ok(Params {
+        workload,
+        strategy,
+        replicas,
+        max_unavailable,
+        max_surge,
+        max_unavailable_set,
+        partition,
+        pod_management: args.pod_management_policy.or(manifest.map(|m| m.pod_management)).unwrap_or(PodManagement::OrderedReady),
+        startup: args.startup.unwrap_or(if readiness.is_some() { 5.0 } else { 0.0 }),
+        startup_jitter: args.startup_jitter,
+        initial_delay: args.initial_delay.unwrap_or(probe_delay),
+        probe_period: args.probe_period.unwrap_or(probe_period),
+        min_ready_seconds: args.min_ready_seconds.or(manifest.map(|m| m.min_ready_seconds)).unwrap_or(0.0),
+        failure_rate: args.failure_rate,
+        crash_after: args.crash_after,
+        progress_deadline: args.progress_deadline.or(manifest.and_then(|m| m.progress_deadline)).unwrap_or(600.0),
+        seed: args.seed,
+        scale_events,
+        pdb,
+    })
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
-    simulate_rolling_update(args.replicas, &args.max_unavailable, &args.max_surge);
+    let files = (!args.file.is_empty()).then_some(args.file.as_slice());
+    let manifest = match files.map(read_manifest).transpose() {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let params = match params(&args, manifest.as_ref()) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let report = model::simulate(params.clone());
+    print!("{}", output::render(args.output, &params, &report));
+    match report.outcome {
+        Outcome::Complete => ExitCode::SUCCESS,
+        Outcome::ProgressDeadlineExceeded | Outcome::Stalled => ExitCode::FAILURE,
+    }
 }
@@ -0,0 +1,207 @@
+//! Lê a estratégia de rollout de um Deployment, StatefulSet ou DaemonSet em YAML (multi-documento;
+//! Services e outros são ignorados), e o PodDisruptionBudget se vier junto.
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::controller::{IntOrPercent, Pdb};
+use crate::model::{PodManagement, Strategy, Workload};
+
+/// O que o simulador usa do workload, já com os padrões da API para o que faltar.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Manifest {
+    pub workload: Workload,
+    /// DaemonSet não tem: é um pod por nó.
+    pub replicas: Option<u32>,
+    pub strategy: Strategy,
+    /// Ausentes ficam com o padrão de cada tipo de workload.
+    pub max_surge: Option<IntOrPercent>,
+    pub max_unavailable: Option<IntOrPercent>,
+    pub partition: u32,
+    pub pod_management: PodManagement,
+    pub min_ready_seconds: f64,
+    /// Só Deployment tem `progressDeadlineSeconds`.
+    pub progress_deadline: Option<f64>,
+    /// `initialDelaySeconds`/`periodSeconds` da readinessProbe do primeiro container.
+    pub readiness: Option<(f64, f64)>,
+    /// Primeiro PodDisruptionBudget do YAML; o selector não é conferido.
+    pub pdb: Option<Pdb>,
+}
+
+/// Primeiro Deployment, StatefulSet ou DaemonSet de `yaml` (ou de um `kind: List`).
+pub fn parse(yaml: &str) -> Result<Manifest, String> {
+    let (mut manifest, mut pdb) = (None, None);
+    for doc in serde_yaml::Deserializer::from_str(yaml) {
+        let value = Value::deserialize(doc).map_err(|e| e.to_string())?;
+        for object in objects(&value) {
+            let workload = match object["kind"].as_str() {
+                Some("Deployment") => Workload::Deployment,
+                Some("StatefulSet") => Workload::StatefulSet,
+                Some("DaemonSet") => Workload::DaemonSet,
+                Some("PodDisruptionBudget") if pdb.is_none() => {
+                    pdb = Some(pdb_from_value(object)?);
+                    continue;
+                }
+                _ => continue,
+            };
+            if manifest.is_none() {
+                manifest = Some(from_value(workload, object)?);
+            }
+        }
+    }
+    let manifest = manifest.ok_or("nenhum Deployment, StatefulSet ou DaemonSet no YAML")?;
+    Ok(Manifest { pdb, ..manifest })
+}
+
+/// O próprio objeto, ou os itens de um `kind: List`.
+fn objects(value: &Value) -> Vec<&Value> {
+    match value["kind"].as_str() {
+        Some("List") => value["items"].as_sequence().map(|items| items.iter().flat_map(objects).collect()).unwrap_or_default(),
+        _ => vec![value],
+    }
+}
+
+fn pdb_from_value(d: &Value) -> Result<Pdb, String> {
+    let name = d["metadata"]["name"].as_str().unwrap_or_default();
+    let err = |e: String| format!("PodDisruptionBudget/{}: {}", name, e);
+    let spec = &d["spec"];
+    let min_available = int_or_percent(&spec["minAvailable"], "spec.minAvailable").map_err(err)?;
+    let max_unavailable = int_or_percent(&spec["maxUnavailable"], "spec.maxUnavailable").map_err(err)?;
+    match (min_available, max_unavailable) {
+        (Some(v), None) => Ok(Pdb::MinAvailable(v)),
+        (None, Some(v)) => Ok(Pdb::MaxUnavailable(v)),
+        (Some(_), Some(_)) => Err(err("minAvailable e maxUnavailable não podem estar juntos".to_string())),
+        (None, None) => Err(err("informe minAvailable ou maxUnavailable".to_string())),
+    }
+}
+
+fn from_value(workload: Workload, d: &Value) -> Result<Manifest, String> {
+    let name = d["metadata"]["name"].as_str().unwrap_or_default().to_string();
+    let err = |e: String| format!("{:?}/{}: {}", workload, name, e);
+    let spec = &d["spec"];
+    // Deployment usa spec.strategy; StatefulSet e DaemonSet, spec.updateStrategy
+    let (field, allowed) = match workload {
+        Workload::Deployment => ("strategy", [Strategy::RollingUpdate, Strategy::Recreate]),
+        _ => ("updateStrategy", [Strategy::RollingUpdate, Strategy::OnDelete]),
+    };
+    let strategy = match spec[field]["type"].as_str() {
+        None | Some("RollingUpdate") => Strategy::RollingUpdate,
+        Some("Recreate") => Strategy::Recreate,
+        Some("OnDelete") => Strategy::OnDelete,
+        Some(other) => return Err(err(format!("spec.{}.type \"{}\" desconhecido", field, other))),
+    };
+    if !allowed.contains(&strategy) {
+        return Err(err(format!("spec.{}.type {:?} não existe em {:?}", field, strategy, workload)));
+    }
+    let rolling = &spec[field]["rollingUpdate"];
+    if strategy != Strategy::RollingUpdate && !rolling.is_null() {
+        return Err(err(format!("spec.{}.rollingUpdate só vale com type RollingUpdate", field)));
+    }
+    if workload == Workload::StatefulSet && !rolling["maxSurge"].is_null() {
+        return Err(err("StatefulSet não tem maxSurge".to_string()));
+    }
+    let pod_management = match spec["podManagementPolicy"].as_str() {
+        None | Some("OrderedReady") => PodManagement::OrderedReady,
+        Some("Parallel") => PodManagement::Parallel,
+        Some(other) => return Err(err(format!("spec.podManagementPolicy \"{}\" desconhecido", other))),
+    };
+    let probe = &spec["template"]["spec"]["containers"][0]["readinessProbe"];
+    let optional = |v: &Value, field: &str| int_or_percent(v, field).map_err(err);
+    Ok(Manifest {
+        workload,
+        replicas: match workload {
+            Workload::DaemonSet => None,
+            _ => Some(u32_field(&spec["replicas"], "spec.replicas").map_err(err)?.unwrap_or(1)),
+        },
+        strategy,
+        max_surge: optional(&rolling["maxSurge"], "maxSurge")?,
+        max_unavailable: optional(&rolling["maxUnavailable"], "maxUnavailable")?,
+        partition: u32_field(&rolling["partition"], "partition").map_err(err)?.unwrap_or(0),
+        pod_management,
+        min_ready_seconds: u32_field(&spec["minReadySeconds"], "spec.minReadySeconds").map_err(err)?.unwrap_or(0) as f64,
+        progress_deadline: match workload {
+            Workload::Deployment => Some(
+                u32_field(&spec["progressDeadlineSeconds"], "spec.progressDeadlineSeconds").map_err(err)?.unwrap_or(600) as f64,
+            ),
+            _ => None,
+        },
+        readiness: if probe.is_null() {
+            None
+        } else {
+            Some((
+                u32_field(&probe["initialDelaySeconds"], "readinessProbe.initialDelaySeconds").map_err(err)?.unwrap_or(0) as f64,
+                u32_field(&probe["periodSeconds"], "readinessProbe.periodSeconds").map_err(err)?.unwrap_or(10) as f64,
+            ))
+        },
+        pdb: None,
+    })
+}
+
+fn u32_field(v: &Value, field: &str) -> Result<Option<u32>, String> {
+    match v {
+        Value::Null => Ok(None),
+        _ => v.as_u64().and_then(|n| u32::try_from(n).ok()).map(Some).ok_or_else(|| format!("{} inválido: {:?}", field, v)),
+    }
+}
+
+/// Como o `intstr` da API: número é inteiro; string precisa ser percentual.
+fn int_or_percent(v: &Value, field: &str) -> Result<Option<IntOrPercent>, String> {
+    match v {
+        Value::Null => Ok(None),
+        Value::Number(_) => u32_field(v, field).map(|n| n.map(IntOrPercent::Int)),
+        Value::String(s) if s.ends_with('%') => s.parse().map(Some).map_err(|e| format!("{}: {}", field, e)),
+        Value::String(s) => Err(format!("{}: \"{}\" é string mas não é percentual (use {} sem aspas)", field, s, s)),
+        _ => Err(format!("{} inválido: {:?}", field, v)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_lesson_manifests() {
+        let rolling = parse(include_str!("../../../k8s/deployment-rolling.yaml")).unwrap();
+        assert_eq!((rolling.workload, rolling.replicas), (Workload::Deployment, Some(6)));
+        assert_eq!(rolling.strategy, Strategy::RollingUpdate);
+        assert_eq!((rolling.max_surge, rolling.max_unavailable), (Some(IntOrPercent::Percent(25)), Some(IntOrPercent::Percent(25))));
+        assert_eq!((rolling.min_ready_seconds, rolling.progress_deadline), (10.0, Some(600.0)));
+        assert_eq!(rolling.readiness, Some((3.0, 5.0)));
+
+        let recreate = parse(include_str!("../../../k8s/deployment-recreate.yaml")).unwrap();
+        assert_eq!((recreate.replicas, recreate.strategy), (Some(4), Strategy::Recreate));
+
+        let sts = parse(include_str!("../../../k8s/statefulset-rolling.yaml")).unwrap();
+        assert_eq!((sts.workload, sts.replicas, sts.partition), (Workload::StatefulSet, Some(4), 2));
+        assert_eq!((sts.pod_management, sts.max_unavailable), (PodManagement::OrderedReady, None));
+
+        let ds = parse(include_str!("../../../k8s/daemonset-rolling.yaml")).unwrap();
+        assert_eq!((ds.workload, ds.replicas, ds.progress_deadline), (Workload::DaemonSet, None, None));
+        assert_eq!((ds.max_surge, ds.max_unavailable), (Some(IntOrPercent::Int(1)), Some(IntOrPercent::Int(0))));
+
+        let with_pdb = [include_str!("../../../k8s/pdb.yaml"), include_str!("../../../k8s/deployment-rolling.yaml")].join("\n---\n");
+        let with_pdb = parse(&with_pdb).unwrap();
+        assert_eq!((with_pdb.workload, with_pdb.pdb), (Workload::Deployment, Some(Pdb::MinAvailable(IntOrPercent::Int(5)))));
+    }
+
+    #[test]
+    fn reads_the_pdb_from_a_list() {
+        let list = "kind: List\nitems:\n  - kind: PodDisruptionBudget\n    spec: { maxUnavailable: \"50%\" }\n  - kind: StatefulSet\n";
+        assert_eq!(parse(list).unwrap().pdb, Some(Pdb::MaxUnavailable(IntOrPercent::Percent(50))));
+        assert!(parse("kind: PodDisruptionBudget\nspec: { minAvailable: 1, maxUnavailable: 1 }\n---\nkind: Deployment\n").is_err());
+        assert!(parse("kind: PodDisruptionBudget\n---\nkind: Deployment\n").is_err());
+    }
+
+    #[test]
+    fn applies_api_defaults_and_rejects_bad_intstr() {
+        let yaml = "kind: Service\n---\nkind: Deployment\nmetadata: { name: x }\nspec:\n  strategy: { rollingUpdate: { maxSurge: 2 } }\n";
+        let d = parse(yaml).unwrap();
+        assert_eq!((d.replicas, d.max_surge, d.max_unavailable), (Some(1), Some(IntOrPercent::Int(2)), None));
+        assert_eq!(d.readiness, None);
+
+        assert!(parse(&yaml.replace("maxSurge: 2", "maxSurge: \"2\"")).is_err());
+        assert!(parse("kind: Service\n").is_err());
+        assert!(parse("kind: StatefulSet\nspec: { updateStrategy: { type: Recreate } }\n").is_err());
+        assert!(parse("kind: Deployment\nspec: { strategy: { type: OnDelete } }\n").is_err());
+    }
+}
@@ -0,0 +1,709 @@
+//! Modelo de eventos discretos do rollout: o controller (Deployment, StatefulSet ou DaemonSet)
+//! reage a cada mudança de estado dos pods, e cada pod novo leva tempo para ficar Ready e depois Available.
+
+use crate::controller::{self, Pdb, Rs, SlotPod, Step};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Backoff do kubelet entre reinícios de um container em CrashLoopBackOff.
+const BACKOFF_INICIAL_MS: u64 = 10_000;
+const BACKOFF_MAX_MS: u64 = 300_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, clap::ValueEnum)]
+pub enum Workload {
+    #[value(name = "Deployment")]
+    Deployment,
+    #[value(name = "StatefulSet")]
+    StatefulSet,
+    /// Um pod por nó; `replicas` é o número de nós.
+    #[value(name = "DaemonSet")]
+    DaemonSet,
+}
+
+/// `spec.strategy.type` (Deployment) ou `spec.updateStrategy.type` (StatefulSet e DaemonSet).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, clap::ValueEnum)]
+pub enum Strategy {
+    #[value(name = "RollingUpdate")]
+    RollingUpdate,
+    /// Só Deployment: remove todos os pods antigos antes de criar os novos.
+    #[value(name = "Recreate")]
+    Recreate,
+    /// Só StatefulSet e DaemonSet: o controller só recria pods apagados; aqui um operador apaga um
+    /// pod antigo por vez (maior ordinal/último nó primeiro) quando todos estão disponíveis.
+    #[value(name = "OnDelete")]
+    OnDelete,
+}
+
+/// `spec.podManagementPolicy` do StatefulSet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, clap::ValueEnum)]
+pub enum PodManagement {
+    #[value(name = "OrderedReady")]
+    OrderedReady,
+    #[value(name = "Parallel")]
+    Parallel,
+}
+
+/// Mudança de `spec.replicas` no meio do rollout, como o HPA faz.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ScaleEvent {
+    /// Segundos desde o início do rollout.
+    pub at: f64,
+    pub replicas: u32,
+    /// maxSurge/maxUnavailable resolvidos de novo para a nova escala (percentuais mudam junto).
+    pub max_surge: u32,
+    pub max_unavailable: u32,
+}
+
+/// Parâmetros do rollout; tempos em segundos.
+#[derive(Clone, Debug, Serialize)]
+pub struct Params {
+    pub workload: Workload,
+    pub strategy: Strategy,
+    /// Réplicas, ou nós no DaemonSet.
+    pub replicas: u32,
+    /// Já resolvidos pelas contas de `controller` de cada tipo de workload.
+    pub max_unavailable: u32,
+    pub max_surge: u32,
+    /// StatefulSet: `maxUnavailable` definido (feature gate `MaxUnavailableStatefulSet`); sem ele,
+    /// um pod por vez.
+    pub max_unavailable_set: bool,
+    /// StatefulSet: ordinais abaixo de `partition` ficam na versão antiga.
+    pub partition: u32,
+    pub pod_management: PodManagement,
+    /// Tempo médio do container até a aplicação responder à readiness probe.
+    pub startup: f64,
+    /// Variação uniforme (±) do startup de cada pod.
+    pub startup_jitter: f64,
+    pub initial_delay: f64,
+    pub probe_period: f64,
+    pub min_ready_seconds: f64,
+    /// Fração dos pods novos que estão quebrados e entram em CrashLoopBackOff.
+    pub failure_rate: f64,
+    /// Pods quebrados passam na readiness e caem depois deste tempo; sem valor, nunca ficam Ready.
+    pub crash_after: Option<f64>,
+    pub progress_deadline: f64,
+    pub seed: u64,
+    /// Só Deployment RollingUpdate: o controller redistribui as réplicas entre os ReplicaSets.
+    pub scale_events: Vec<ScaleEvent>,
+    pub pdb: Option<Pdb>,
+}
+
+/// Estado do rollout logo depois de um instante com eventos.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Sample {
+    pub t_ms: u64,
+    /// `spec.replicas` neste instante (muda com o HPA).
+    pub replicas: u32,
+    pub min_available: u32,
+    pub old: u32,
+    pub new: u32,
+    pub ready: u32,
+    pub available: u32,
+    /// `replicas - available`, como em `status.unavailableReplicas`.
+    pub unavailable: u32,
+    /// O que aconteceu neste instante (pods criados, removidos, que ficaram Ready...).
+    pub events: Vec<String>,
+    /// Disponíveis abaixo de `replicas - maxUnavailable`.
+    pub below_min: bool,
+    /// `disruptionsAllowed` do PDB: com 0, `kubectl drain` e outras evictions ficam bloqueadas.
+    pub disruptions_allowed: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Outcome {
+    Complete,
+    ProgressDeadlineExceeded,
+    /// StatefulSet e DaemonSet não têm deadline: sem progresso, o rollout fica parado para sempre.
+    Stalled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Report {
+    pub min_available: u32,
+    pub timeline: Vec<Sample>,
+    pub outcome: Outcome,
+    pub t_end_ms: u64,
+    pub restarts: u32,
+    /// Menor número de pods disponíveis visto e quando.
+    pub lowest_available: (u32, u64),
+}
+
+struct Pod {
+    id: u32,
+    /// Ordinal no StatefulSet, nó no DaemonSet.
+    slot: u32,
+    new: bool,
+    ready: bool,
+    available: bool,
+    broken: bool,
+    startup_ms: u64,
+    restarts: u32,
+    /// Invalida eventos agendados para um pod que já mudou de estado (crash, remoção).
+    generation: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    Ready,
+    Available,
+    Crash,
+    /// `pod` é o índice em `Params::scale_events`.
+    Scale,
+    Deadline,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Event {
+    t_ms: u64,
+    kind: Kind,
+    pod: u32,
+    generation: u32,
+}
+
+fn ms(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 1000.0).round() as u64
+}
+
+struct Sim {
+    p: Params,
+    rng: StdRng,
+    pods: Vec<Pod>,
+    next_id: u32,
+    queue: BinaryHeap<Reverse<Event>>,
+    now: u64,
+    restarts: u32,
+    last_progress: u64,
+    /// Melhor estado já visto (novos, Ready, disponíveis, antigos), para medir progresso.
+    best: (u32, u32, u32, u32),
+    deadline_generation: u32,
+    /// Escala mudou e o controller ainda não redistribuiu: anotação `max-replicas` dos ReplicaSets.
+    scaling: Option<u32>,
+}
+
+impl Sim {
+    fn count(&self, f: impl Fn(&Pod) -> bool) -> u32 {
+        self.pods.iter().filter(|p| f(p)).count() as u32
+    }
+
+    fn name(&self, pod: &Pod) -> String {
+        match self.p.workload {
+            Workload::Deployment => format!("pod-{}", pod.id),
+            Workload::StatefulSet => format!("pod-{}", pod.slot),
+            Workload::DaemonSet => format!("pod@node-{}", pod.slot + 1),
+        }
+    }
+
+    fn names(&self, ids: &[u32]) -> String {
+        match (self.p.workload, ids) {
+            (Workload::Deployment, [first, .., last]) => format!("pod-{}..pod-{}", first, last),
+            _ => {
+                let pods = ids.iter().filter_map(|id| self.pods.iter().find(|p| p.id == *id));
+                pods.map(|p| self.name(p)).collect::<Vec<_>>().join(", ")
+            }
+        }
+    }
+
+    /// Instante da primeira readiness probe bem-sucedida para um container iniciado em `start`.
+    fn first_ready(&self, start: u64, startup_ms: u64) -> u64 {
+        let delay = ms(self.p.initial_delay);
+        let period = ms(self.p.probe_period).max(1);
+        let waiting = startup_ms.saturating_sub(delay);
+        start + delay + waiting.div_ceil(period) * period
+    }
+
+    fn schedule(&mut self, t_ms: u64, kind: Kind, pod: u32, generation: u32) {
+        self.queue.push(Reverse(Event { t_ms, kind, pod, generation }));
+    }
+
+    /// Agenda o próximo passo de um container que acabou de (re)iniciar.
+    fn start_container(&mut self, idx: usize, start: u64) {
+        let pod = &self.pods[idx];
+        let (id, generation, startup_ms, broken) = (pod.id, pod.generation, pod.startup_ms, pod.broken);
+        match (broken, self.p.crash_after) {
+            // quebrado sem crash_after: cai antes de responder à probe
+            (true, None) => self.schedule(start + startup_ms, Kind::Crash, id, generation),
+            _ => self.schedule(self.first_ready(start, startup_ms), Kind::Ready, id, generation),
+        }
+    }
+
+    fn create_pod(&mut self, slot: u32, new: bool) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        let jitter = if self.p.startup_jitter > 0.0 {
+            self.rng.gen_range(-self.p.startup_jitter..=self.p.startup_jitter)
+        } else {
+            0.0
+        };
+        // só a versão nova tem o defeito
+        let broken = new && self.p.failure_rate > 0.0 && self.rng.gen_bool(self.p.failure_rate.min(1.0));
+        self.pods.push(Pod {
+            id,
+            slot,
+            new,
+            ready: false,
+            available: false,
+            broken,
+            startup_ms: ms(self.p.startup + jitter),
+            restarts: 0,
+            generation: 0,
+        });
+        let idx = self.pods.len() - 1;
+        self.start_container(idx, self.now);
+        id
+    }
+
+    fn apply(&mut self, e: Event, log: &mut Vec<String>) {
+        match e.kind {
+            Kind::Deadline => return,
+            Kind::Scale => return self.scale(e.pod as usize, log),
+            _ => {}
+        }
+        let Some(idx) = self.pods.iter().position(|p| p.id == e.pod && p.generation == e.generation) else {
+            return;
+        };
+        let name = self.name(&self.pods[idx]);
+        match e.kind {
+            Kind::Ready => {
+                self.pods[idx].ready = true;
+                log.push(format!("{} Ready", name));
+                let min_ready = ms(self.p.min_ready_seconds);
+                self.schedule(self.now + min_ready, Kind::Available, e.pod, e.generation);
+                if let (true, Some(after)) = (self.pods[idx].broken, self.p.crash_after) {
+                    self.schedule(self.now + ms(after), Kind::Crash, e.pod, e.generation);
+                }
+            }
+            Kind::Available => {
+                if !self.pods[idx].available {
+                    self.pods[idx].available = true;
+                    log.push(format!("{} Available", name));
+                }
+            }
+            Kind::Crash => {
+                let pod = &mut self.pods[idx];
+                pod.ready = false;
+                pod.available = false;
+                pod.generation += 1;
+                pod.restarts += 1;
+                self.restarts += 1;
+                let backoff = (BACKOFF_INICIAL_MS << (pod.restarts - 1).min(5)).min(BACKOFF_MAX_MS);
+                log.push(format!("{} caiu (restart #{}, backoff {}s)", name, pod.restarts, backoff / 1000));
+                self.start_container(idx, self.now + backoff);
+            }
+            Kind::Scale | Kind::Deadline => unreachable!(),
+        }
+    }
+
+    /// HPA muda `spec.replicas`; o próximo sync é um scaling event.
+    fn scale(&mut self, i: usize, log: &mut Vec<String>) {
+        let e = self.p.scale_events[i];
+        self.scaling.get_or_insert(self.p.replicas + self.p.max_surge);
+        log.push(format!("HPA: replicas {} -> {}", self.p.replicas, e.replicas));
+        self.p.replicas = e.replicas;
+        self.p.max_surge = e.max_surge;
+        self.p.max_unavailable = e.max_unavailable;
+    }
+
+    /// Syncs até nada mudar: cada pod criado ou apagado recoloca o workload na fila do controller.
+    fn reconcile(&mut self, log: &mut Vec<String>) {
+        loop {
+            let before = log.len();
+            self.sync(log);
+            if log.len() == before {
+                break;
+            }
+        }
+    }
+
+    fn replica_sets(&self) -> (Rs, Rs) {
+        let rs = |new: bool| Rs {
+            replicas: self.count(|p| p.new == new),
+            available: self.count(|p| p.new == new && p.available),
+        };
+        (rs(false), rs(true))
+    }
+
+    fn min_available(&self) -> u32 {
+        if self.p.strategy != Strategy::RollingUpdate {
+            return 0;
+        }
+        self.p.replicas - controller::max_unavailable(self.p.replicas, self.p.max_unavailable)
+    }
+
+    fn sync(&mut self, log: &mut Vec<String>) {
+        match (self.p.workload, self.p.strategy) {
+            (Workload::Deployment, Strategy::Recreate) => self.sync_recreate(log),
+            (Workload::Deployment, _) => self.sync_rolling(log),
+            (Workload::StatefulSet, _) => self.sync_statefulset(log),
+            (Workload::DaemonSet, _) => self.sync_daemonset(log),
+        }
+    }
+
+    fn log_created(&self, ids: &[u32], log: &mut Vec<String>) {
+        if !ids.is_empty() {
+            log.push(format!("+{} novos ({})", ids.len(), self.names(ids)));
+        }
+    }
+
+    /// Um sync do controller de Deployment (`rolloutRolling`): escala o ReplicaSet novo dentro do
+    /// maxSurge e depois reduz os antigos sem deixar os disponíveis abaixo de `replicas - maxUnavailable`.
+    fn sync_rolling(&mut self, log: &mut Vec<String>) {
+        // isScalingEvent: este sync só redistribui as réplicas entre os ReplicaSets
+        if let Some(max_before) = self.scaling.take() {
+            let (old, new) = self.replica_sets();
+            let sizes = controller::proportional_scale(self.p.replicas, self.p.max_surge, max_before, &[old.replicas, new.replicas]);
+            log.push(format!("escala proporcional: antigos {} -> {}, novos {} -> {}", old.replicas, sizes[0], new.replicas, sizes[1]));
+            self.resize(false, old.replicas, sizes[0], log);
+            self.resize(true, new.replicas, sizes[1], log);
+            return;
+        }
+
+        // reconcileNewReplicaSet
+        let (old, new) = self.replica_sets();
+        let target = controller::new_rs_new_replicas(self.p.replicas, self.p.max_surge, old, new);
+        let ids: Vec<u32> = (new.replicas..target).map(|_| self.create_pod(self.next_id, true)).collect();
+        self.log_created(&ids, log);
+
+        // reconcileOldReplicaSets: primeiro os antigos que já não estão disponíveis, depois os
+        // disponíveis acima do mínimo
+        let (old, new) = self.replica_sets();
+        let (cleaned, scaled) = controller::reconcile_old(self.p.replicas, self.p.max_unavailable, old, new);
+        let down = cleaned + scaled;
+        if down > 0 {
+            self.remove(false, down);
+            log.push(format!("-{} antigos", down));
+        }
+    }
+
+    /// O ReplicaSet antigo ou o novo passa de `from` para `to` pods.
+    fn resize(&mut self, new: bool, from: u32, to: u32, log: &mut Vec<String>) {
+        let version = if new { "novos" } else { "antigos" };
+        if to > from {
+            let ids: Vec<u32> = (from..to).map(|_| self.create_pod(self.next_id, new)).collect();
+            log.push(format!("+{} {} ({})", ids.len(), version, self.names(&ids)));
+        } else if from > to {
+            self.remove(new, from - to);
+            log.push(format!("-{} {}", from - to, version));
+        }
+    }
+
+    /// `rolloutRecreate`: zera o ReplicaSet antigo e só cria os novos quando não sobra pod antigo.
+    fn sync_recreate(&mut self, log: &mut Vec<String>) {
+        let (old, new) = self.replica_sets();
+        if old.replicas > 0 {
+            self.remove(false, old.replicas);
+            log.push(format!("-{} antigos", old.replicas));
+            return;
+        }
+        let ids: Vec<u32> = (new.replicas..self.p.replicas).map(|_| self.create_pod(self.next_id, true)).collect();
+        self.log_created(&ids, log);
+    }
+
+    fn slot_pod(&self, slot: u32, new: bool) -> Option<SlotPod> {
+        let pod = self.pods.iter().find(|p| p.slot == slot && p.new == new)?;
+        Some(SlotPod { new: pod.new, ready: pod.ready, available: pod.available })
+    }
+
+    /// Executa um `Step` de StatefulSet/DaemonSet: apaga os pods antigos dos slots em `delete` e
+    /// cria pods novos nos slots em `create`.
+    fn apply_step(&mut self, step: Step, log: &mut Vec<String>) {
+        if !step.delete.is_empty() {
+            let deleted: Vec<String> = step
+                .delete
+                .iter()
+                .filter_map(|&slot| self.pods.iter().find(|p| p.slot == slot as u32 && !p.new))
+                .map(|p| self.name(p))
+                .collect();
+            self.pods.retain(|p| p.new || !step.delete.contains(&(p.slot as usize)));
+            log.push(format!("-{} antigos ({})", deleted.len(), deleted.join(", ")));
+        }
+        let ids: Vec<u32> = step.create.iter().map(|&slot| self.create_pod(slot as u32, true)).collect();
+        self.log_created(&ids, log);
+    }
+
+    /// OnDelete: com tudo no lugar e disponível, o operador apaga o pod antigo do maior slot.
+    fn operator_delete(&mut self, log: &mut Vec<String>) {
+        if self.count(|_| true) != self.p.replicas || self.count(|p| !p.available) > 0 {
+            return;
+        }
+        let Some(pod) = self.pods.iter().filter(|p| !p.new).max_by_key(|p| p.slot) else {
+            return;
+        };
+        let (id, name) = (pod.id, self.name(pod));
+        self.pods.retain(|p| p.id != id);
+        log.push(format!("kubectl delete pod {}", name));
+    }
+
+    /// Um sync do controller de StatefulSet (`updateStatefulSet`). Pod apagado volta com o mesmo
+    /// ordinal; acima de `partition`, na versão nova.
+    fn sync_statefulset(&mut self, log: &mut Vec<String>) {
+        let pods: Vec<Option<SlotPod>> = (0..self.p.replicas).map(|slot| self.slot_pod(slot, true).or(self.slot_pod(slot, false))).collect();
+        let max_unavailable = self.p.max_unavailable_set.then_some(self.p.max_unavailable);
+        let parallel = self.p.pod_management == PodManagement::Parallel;
+        let rolling = self.p.strategy == Strategy::RollingUpdate;
+        let step = controller::statefulset_step(&pods, self.p.partition, max_unavailable, parallel, rolling);
+        if step == Step::default() && !rolling {
+            return self.operator_delete(log);
+        }
+        self.apply_step(step, log);
+    }
+
+    /// Um sync do controller de DaemonSet (`manage` + `rollingUpdate`), com um slot por nó.
+    fn sync_daemonset(&mut self, log: &mut Vec<String>) {
+        let nodes: Vec<_> = (0..self.p.replicas).map(|slot| (self.slot_pod(slot, false), self.slot_pod(slot, true))).collect();
+        let rolling = self.p.strategy == Strategy::RollingUpdate;
+        let step = controller::daemonset_step(&nodes, self.p.max_surge, self.p.max_unavailable, rolling);
+        if step == Step::default() && !rolling {
+            return self.operator_delete(log);
+        }
+        self.apply_step(step, log);
+    }
+
+    fn remove(&mut self, new: bool, mut n: u32) {
+        // o ReplicaSet remove primeiro os pods não disponíveis
+        for unavailable_first in [true, false] {
+            self.pods.retain(|p| {
+                let pick = n > 0 && p.new == new && (p.available != unavailable_first);
+                n -= pick as u32;
+                !pick
+            });
+        }
+    }
+
+    fn sample(&self, events: Vec<String>) -> Sample {
+        let (ready, available) = (self.count(|p| p.ready), self.count(|p| p.available));
+        Sample {
+            t_ms: self.now,
+            replicas: self.p.replicas,
+            min_available: self.min_available(),
+            old: self.count(|p| !p.new),
+            new: self.count(|p| p.new),
+            ready,
+            available,
+            unavailable: self.p.replicas.saturating_sub(available),
+            events,
+            below_min: available < self.min_available(),
+            // o PDB conta pods Ready, sem esperar minReadySeconds
+            disruptions_allowed: self.p.pdb.map(|pdb| controller::disruptions_allowed(pdb, self.p.replicas, ready)),
+        }
+    }
+
+    fn complete(&self) -> bool {
+        let replicas = self.p.replicas;
+        // StatefulSet com partition: os ordinais abaixo dela continuam antigos
+        let kept = match self.p.workload {
+            Workload::StatefulSet if self.p.strategy == Strategy::RollingUpdate => self.p.partition.min(replicas),
+            _ => 0,
+        };
+        self.count(|p| !p.new) == kept && self.count(|p| p.new) == replicas - kept && self.count(|p| p.available) == replicas
+    }
+
+    fn stuck(&self) -> Outcome {
+        match self.p.workload {
+            Workload::Deployment => Outcome::ProgressDeadlineExceeded,
+            _ => Outcome::Stalled,
+        }
+    }
+
+    fn progress_counts(&self) -> (u32, u32, u32, u32) {
+        (self.count(|p| p.new), self.count(|p| p.ready), self.count(|p| p.available), self.count(|p| !p.new))
+    }
+
+    fn arm_deadline(&mut self) {
+        self.last_progress = self.now;
+        self.deadline_generation += 1;
+        let t = self.now + ms(self.p.progress_deadline);
+        self.schedule(t, Kind::Deadline, 0, self.deadline_generation);
+    }
+}
+
+/// Roda o rollout até completar ou ficar `progressDeadlineSeconds` sem progresso (em StatefulSet e
+/// DaemonSet, que não têm deadline, só a janela de observação da simulação).
+pub fn simulate(p: Params) -> Report {
+    let mut sim = Sim {
+        rng: StdRng::seed_from_u64(p.seed),
+        pods: Vec::new(),
+        next_id: 1,
+        queue: BinaryHeap::new(),
+        now: 0,
+        restarts: 0,
+        last_progress: 0,
+        best: (0, 0, 0, 0),
+        deadline_generation: 0,
+        scaling: None,
+        p,
+    };
+    // todos os pods da versão antiga começam disponíveis
+    for slot in 0..sim.p.replicas {
+        let id = sim.next_id;
+        sim.next_id += 1;
+        sim.pods.push(Pod { id, slot, new: false, ready: true, available: true, broken: false, startup_ms: 0, restarts: 0, generation: 0 });
+    }
+
+    for (i, e) in sim.p.scale_events.clone().iter().enumerate() {
+        sim.schedule(ms(e.at), Kind::Scale, i as u32, 0);
+    }
+
+    let mut log = Vec::new();
+    sim.reconcile(&mut log);
+    sim.best = sim.progress_counts();
+    sim.arm_deadline();
+    let first = sim.sample(log);
+    let mut lowest = (first.available, first.t_ms);
+    let mut timeline = vec![first];
+
+    let outcome = loop {
+        if sim.complete() && !sim.queue.iter().any(|e| e.0.kind == Kind::Scale) {
+            break Outcome::Complete;
+        }
+        let Some(Reverse(first)) = sim.queue.pop() else {
+            // nada mais vai acontecer: o rollout travou até o deadline
+            sim.now = sim.last_progress + ms(sim.p.progress_deadline);
+            break sim.stuck();
+        };
+        sim.now = first.t_ms;
+        // rollout completo esperando o HPA não tem deadline correndo
+        if first.kind == Kind::Deadline && first.generation == sim.deadline_generation && !sim.complete() {
+            break sim.stuck();
+        }
+
+        // todos os eventos do mesmo instante antes de um único sync do controller
+        let mut log = Vec::new();
+        sim.apply(first, &mut log);
+        while let Some(Reverse(e)) = sim.queue.peek().copied() {
+            if e.t_ms != sim.now || e.kind == Kind::Deadline {
+                break;
+            }
+            sim.queue.pop();
+            sim.apply(e, &mut log);
+        }
+        sim.reconcile(&mut log);
+        if log.is_empty() {
+            continue;
+        }
+        // progresso, como em DeploymentProgressing: mais pods novos, Ready ou Available, ou menos antigos.
+        // A comparação é com o melhor estado já visto: no cluster, um pod em CrashLoopBackOff que volta a
+        // ficar Ready renova o prazo a cada restart e o rollout nunca terminaria aqui.
+        let (after, best) = (sim.progress_counts(), sim.best);
+        if after.0 > best.0 || after.1 > best.1 || after.2 > best.2 || after.3 < best.3 {
+            sim.best = (after.0.max(best.0), after.1.max(best.1), after.2.max(best.2), after.3.min(best.3));
+            sim.arm_deadline();
+        }
+        let s = sim.sample(log);
+        if s.available < lowest.0 {
+            lowest = (s.available, s.t_ms);
+        }
+        timeline.push(s);
+    };
+
+    Report {
+        min_available: sim.min_available(),
+        timeline,
+        outcome,
+        t_end_ms: sim.now,
+        restarts: sim.restarts,
+        lowest_available: lowest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deployment RollingUpdate com os padrões da linha de comando.
+    fn deployment(replicas: u32, max_surge: u32, max_unavailable: u32) -> Params {
+        Params {
+            workload: Workload::Deployment,
+            strategy: Strategy::RollingUpdate,
+            replicas,
+            max_unavailable,
+            max_surge,
+            max_unavailable_set: false,
+            partition: 0,
+            pod_management: PodManagement::OrderedReady,
+            startup: 5.0,
+            startup_jitter: 0.0,
+            initial_delay: 0.0,
+            probe_period: 10.0,
+            min_ready_seconds: 0.0,
+            failure_rate: 0.0,
+            crash_after: None,
+            progress_deadline: 600.0,
+            seed: 1,
+            scale_events: Vec::new(),
+            pdb: None,
+        }
+    }
+
+    /// Instante (em ms) de cada amostra com um evento que contém `text`.
+    fn when(report: &Report, text: &str) -> Vec<u64> {
+        report.timeline.iter().filter(|s| s.events.iter().any(|e| e.contains(text))).map(|s| s.t_ms).collect()
+    }
+
+    #[test]
+    fn readiness_waits_for_the_first_probe_after_startup() {
+        // (startup, initialDelay, period) -> primeira probe bem-sucedida
+        let cases = [(8.0, 3.0, 5.0, 8_000), (9.0, 3.0, 5.0, 13_000), (1.0, 3.0, 5.0, 3_000), (5.0, 0.0, 10.0, 10_000), (0.0, 0.0, 10.0, 0)];
+        for (startup, initial_delay, probe_period, ready_ms) in cases {
+            let report = simulate(Params { startup, initial_delay, probe_period, ..deployment(1, 1, 0) });
+            assert_eq!(when(&report, "pod-2 Ready"), [ready_ms], "startup {startup}, delay {initial_delay}, period {probe_period}");
+            assert_eq!((report.outcome, report.t_end_ms), (Outcome::Complete, ready_ms));
+        }
+    }
+
+    #[test]
+    fn min_ready_seconds_gates_availability_and_the_next_batch() {
+        // primeiro exemplo do README: 6 25% 25% --startup 8 --initial-delay 3 --probe-period 5 --min-ready-seconds 10
+        let p = Params { startup: 8.0, initial_delay: 3.0, probe_period: 5.0, min_ready_seconds: 10.0, ..deployment(6, 2, 1) };
+        let report = simulate(p.clone());
+        assert_eq!(when(&report, "pod-7 Ready"), [8_000]);
+        assert_eq!(when(&report, "pod-7 Available"), [18_000]);
+        // os antigos só saem quando os novos ficam disponíveis
+        assert_eq!(when(&report, "antigos"), [0, 18_000, 36_000]);
+        assert_eq!((report.outcome, report.t_end_ms, report.restarts), (Outcome::Complete, 36_000, 0));
+        assert_eq!((report.min_available, report.lowest_available), (5, (5, 0)));
+        assert!(report.timeline.iter().all(|s| !s.below_min));
+
+        // sem minReadySeconds, Ready já é disponível
+        let fast = simulate(Params { min_ready_seconds: 0.0, ..p });
+        assert_eq!(when(&fast, "pod-7 Available"), [8_000]);
+        assert_eq!(fast.t_end_ms, 16_000);
+    }
+
+    #[test]
+    fn crash_loop_backs_off_until_progress_deadline_exceeded() {
+        // segundo exemplo do README: 4 1 1 --failure-rate 0.5 --progress-deadline 60
+        let p = Params { failure_rate: 0.5, progress_deadline: 60.0, ..deployment(4, 1, 1) };
+        let report = simulate(p.clone());
+        // cai no startup (5s) e reinicia depois de 10s, 20s, 40s de backoff
+        assert_eq!(when(&report, "caiu (restart #1, backoff 10s)"), [15_000]);
+        assert_eq!(when(&report, "caiu (restart #2, backoff 20s)"), [30_000]);
+        assert_eq!(when(&report, "caiu (restart #3, backoff 40s)"), [55_000]);
+        // último progresso em 10s, quando os primeiros novos ficaram disponíveis
+        assert_eq!((report.outcome, report.t_end_ms, report.restarts), (Outcome::ProgressDeadlineExceeded, 70_000, 6));
+        assert_eq!(report.lowest_available, (3, 0));
+
+        // mesma semente, mesma linha do tempo
+        assert_eq!(simulate(p.clone()).timeline, report.timeline);
+        assert_eq!(simulate(Params { failure_rate: 0.0, ..p }).outcome, Outcome::Complete);
+    }
+
+    #[test]
+    fn pods_crashing_after_ready_drop_below_min_unless_min_ready_seconds_holds_them() {
+        // terceiro e quarto exemplos do README: 4 1 0 --failure-rate 1 --crash-after 3 [--min-ready-seconds 10]
+        let p = Params { failure_rate: 1.0, crash_after: Some(3.0), ..deployment(4, 0, 1) };
+        let report = simulate(p.clone());
+        assert_eq!(when(&report, "pod-5 Available")[0], 10_000);
+        assert_eq!(when(&report, "pod-5 caiu (restart #1")[0], 13_000);
+        assert_eq!(report.lowest_available, (2, 13_000));
+        assert_eq!(report.timeline.iter().filter(|s| s.below_min).count(), 12);
+        // voltar a ficar Ready depois de cair não renova o prazo
+        assert_eq!((report.outcome, report.t_end_ms), (Outcome::ProgressDeadlineExceeded, 610_000));
+
+        let held = simulate(Params { min_ready_seconds: 10.0, ..p });
+        assert!(held.timeline.iter().all(|s| !s.below_min && s.old == 3));
+        assert!(when(&held, "Available").is_empty());
+        assert_eq!((held.outcome, held.lowest_available), (Outcome::ProgressDeadlineExceeded, (3, 0)));
+    }
+}
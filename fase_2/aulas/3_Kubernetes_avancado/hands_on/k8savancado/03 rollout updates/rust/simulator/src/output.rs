@@ -0,0 +1,244 @@
+//! Formatos de saída da linha do tempo: tabela (padrão), JSON, CSV e gráfico ASCII.
+
+use serde::Serialize;
+
+use crate::controller::Pdb;
+use crate::model::{Outcome, Params, PodManagement, Report, Sample, Strategy, Workload};
+
+/// Largura máxima das barras do gráfico; acima disso cada caractere vale mais de um pod.
+const CHART_WIDTH: u32 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+    Chart,
+}
+
+pub fn render(format: Format, p: &Params, report: &Report) -> String {
+    match format {
+        Format::Table => table(p, report),
+        Format::Json => to_json(p, report),
+        Format::Csv => csv(report),
+        Format::Chart => chart(p, report),
+    }
+}
+
+fn secs(t_ms: u64) -> String {
+    format!("{:.1}s", t_ms as f64 / 1000.0)
+}
+
+fn header(p: &Params, report: &Report) -> String {
+    // com HPA o mínimo muda; o cabeçalho mostra o do início
+    let min_available = report.timeline.first().map_or(report.min_available, |s| s.min_available);
+    let strategy = match (p.workload, p.strategy) {
+        (Workload::Deployment, Strategy::Recreate) => {
+            format!("Iniciando rollout Recreate: replicas={} (todos os antigos saem antes dos novos)", p.replicas)
+        }
+        (Workload::Deployment, _) => format!(
+            "Iniciando rollout: replicas={} max_unavail={} max_surge={} (mínimo disponível={}, máximo de pods={})",
+            p.replicas, p.max_unavailable.min(p.replicas), p.max_surge, min_available, p.replicas + p.max_surge
+        ),
+        (workload, Strategy::OnDelete) => format!(
+            "Iniciando rollout OnDelete de {:?}: {}={} (o controller só recria pods apagados; a simulação apaga um antigo por vez)",
+            workload,
+            if workload == Workload::DaemonSet { "nós" } else { "replicas" },
+            p.replicas
+        ),
+        (Workload::StatefulSet, _) => {
+            let max_unavailable = match p.max_unavailable_set {
+                true => format!("max_unavail={}", p.max_unavailable),
+                false => "um pod por vez".to_string(),
+            };
+            let policy = match p.pod_management {
+                PodManagement::OrderedReady => "OrderedReady",
+                PodManagement::Parallel => "Parallel",
+            };
+            format!(
+                "Iniciando rollout de StatefulSet: replicas={} partition={} {} podManagementPolicy={} (mínimo disponível={})",
+                p.replicas, p.partition, max_unavailable, policy, min_available
+            )
+        }
+        (Workload::DaemonSet, _) => format!(
+            "Iniciando rollout de DaemonSet: nós={} max_unavail={} max_surge={} (mínimo disponível={})",
+            p.replicas, p.max_unavailable.min(p.replicas), p.max_surge, min_available
+        ),
+    };
+    let crash = p.crash_after.map_or("antes da readiness".to_string(), |s| format!("{}s depois de Ready", s));
+    let mut out = format!(
+        "{}\nPods novos: startup {}s ±{}s, probe a cada {}s (initialDelay {}s), minReadySeconds {}s, falhas {:.0}% ({})\n",
+        strategy, p.startup, p.startup_jitter, p.probe_period, p.initial_delay, p.min_ready_seconds, p.failure_rate * 100.0, crash
+    );
+    for e in &p.scale_events {
+        out += &format!("HPA aos {}s: replicas={} max_unavail={} max_surge={}\n", e.at, e.replicas, e.max_unavailable.min(e.replicas), e.max_surge);
+    }
+    if let Some(pdb) = p.pdb {
+        out += &format!("PDB {}: conta pods Ready; o rollout não passa pelo PDB, só evictions (kubectl drain)\n", pdb);
+    }
+    out
+}
+
+fn summary(p: &Params, report: &Report) -> String {
+    let (lowest, at) = report.lowest_available;
+    let below = report.timeline.iter().filter(|s| s.below_min).count();
+    let mut out = match report.outcome {
+        Outcome::Complete if p.workload == Workload::StatefulSet && p.strategy == Strategy::RollingUpdate && p.partition > 0 => format!(
+            "Rollout completo em {} até a partition: ordinais 0..{} continuam na versão antiga.\n",
+            secs(report.t_end_ms),
+            p.partition.min(p.replicas) - 1
+        ),
+        Outcome::Complete => format!("Rollout completo em {}.\n", secs(report.t_end_ms)),
+        Outcome::ProgressDeadlineExceeded => format!(
+            "ProgressDeadlineExceeded em {}: nenhum progresso por {}s (kubectl rollout status sairia com erro).\n",
+            secs(report.t_end_ms), p.progress_deadline
+        ),
+        Outcome::Stalled => format!(
+            "Rollout parado em {}: nenhum progresso por {}s; {:?} não tem progressDeadlineSeconds, então kubectl rollout status esperaria para sempre.\n",
+            secs(report.t_end_ms), p.progress_deadline, p.workload
+        ),
+    };
+    out += &format!("Menor disponibilidade: {} pods em {}; restarts: {}.\n", lowest, secs(at), report.restarts);
+    if below > 0 && p.scale_events.is_empty() {
+        out += &format!(
+            "ATENÇÃO: disponibilidade abaixo do mínimo ({}) em {} instante(s).\n",
+            report.min_available, below
+        );
+    } else if below > 0 {
+        out += &format!("ATENÇÃO: disponibilidade abaixo do mínimo em {} instante(s) (o mínimo muda com o HPA).\n", below);
+    }
+    if let Some(pdb) = p.pdb {
+        out += &pdb_summary(pdb, report);
+    }
+    out
+}
+
+/// Quanto tempo do rollout um `kubectl drain` ficaria esperando o PDB.
+fn pdb_summary(pdb: Pdb, report: &Report) -> String {
+    let blocked = |s: &Sample| s.disruptions_allowed == Some(0);
+    let ends = report.timeline.iter().skip(1).map(|s| s.t_ms).chain([report.t_end_ms]);
+    let blocked_ms: u64 = report.timeline.iter().zip(ends).filter(|(s, _)| blocked(s)).map(|(s, end)| end - s.t_ms).sum();
+    let Some(first) = report.timeline.iter().find(|s| blocked(s)) else {
+        return format!("PDB {}: evictions permitidas durante todo o rollout.\n", pdb);
+    };
+    let mut out = format!(
+        "PDB {}: evictions bloqueadas (disruptionsAllowed=0) por {} do rollout, a partir de {}; kubectl drain ficaria esperando.\n",
+        pdb,
+        secs(blocked_ms),
+        secs(first.t_ms)
+    );
+    if report.timeline.last().is_some_and(blocked) {
+        out += "ATENÇÃO: o PDB continua bloqueando evictions depois do rollout (não sobra folga acima do mínimo).\n";
+    }
+    out
+}
+
+fn table(p: &Params, report: &Report) -> String {
+    // colunas de réplicas e do PDB só aparecem quando há HPA ou PDB
+    let hpa = !p.scale_events.is_empty();
+    let replicas = |v: String| if hpa { format!("{:>8} ", v) } else { String::new() };
+    let pdb = |v: String| if p.pdb.is_some() { format!(" {:>4}", v) } else { String::new() };
+    let mut out = header(p, report) + "\n";
+    out += &format!(
+        "{:>8}  {}{:>7} {:>5} {:>5} {:>11} {:>7}{}  eventos\n",
+        "t",
+        replicas("réplicas".to_string()),
+        "antigos",
+        "novos",
+        "ready",
+        "disponíveis",
+        "indisp.",
+        pdb("PDB".to_string())
+    );
+    for s in &report.timeline {
+        let alert = if s.below_min { format!("  << abaixo do mínimo ({})", s.min_available) } else { String::new() };
+        out += &format!(
+            "{:>8}  {}{:>7} {:>5} {:>5} {:>11} {:>7}{}  {}{}\n",
+            secs(s.t_ms),
+            replicas(s.replicas.to_string()),
+            s.old,
+            s.new,
+            s.ready,
+            s.available,
+            s.unavailable,
+            pdb(s.disruptions_allowed.map(|d| d.to_string()).unwrap_or_default()),
+            s.events.join(", "),
+            alert
+        );
+    }
+    out + "\n" + &summary(p, report)
+}
+
+fn to_json(p: &Params, report: &Report) -> String {
+    #[derive(Serialize)]
+    struct Out<'a> {
+        params: &'a Params,
+        report: &'a Report,
+    }
+    serde_json::to_string_pretty(&Out { params: p, report }).unwrap_or_default() + "\n"
+}
+
+fn csv(report: &Report) -> String {
+    let mut out = String::from("t_ms,replicas,min_available,old,new,ready,available,unavailable,below_min,disruptions_allowed,events\n");
+    for s in &report.timeline {
+        out += &format!(
+            "{},{},{},{},{},{},{},{},{},{},\"{}\"\n",
+            s.t_ms,
+            s.replicas,
+            s.min_available,
+            s.old,
+            s.new,
+            s.ready,
+            s.available,
+            s.unavailable,
+            s.below_min,
+            s.disruptions_allowed.map(|d| d.to_string()).unwrap_or_default(),
+            s.events.join("; ").replace('"', "\"\"")
+        );
+    }
+    out
+}
+
+/// Uma barra por instante: disponíveis primeiro (`O` antigo, `N` novo), depois os novos ainda
+/// indisponíveis (`n`); a `|` marca o mínimo disponível, então pod indisponível à esquerda dela
+/// é disponibilidade abaixo do mínimo.
+fn chart(p: &Params, report: &Report) -> String {
+    let max_pods = report.timeline.iter().map(|s| (s.old + s.new).max(s.replicas)).max().unwrap_or(0).max(p.replicas);
+    let unit = max_pods.div_ceil(CHART_WIDTH).max(1);
+    let chars = |pods: u32| ((pods + unit / 2) / unit) as usize;
+
+    let mut out = header(p, report);
+    out += "O = antigo disponível, N = novo disponível, n = novo indisponível, | = mínimo";
+    if p.scale_events.is_empty() {
+        out += &format!(" ({})", report.min_available);
+    }
+    if unit > 1 {
+        out += &format!("; cada caractere = {} pods", unit);
+    }
+    out += "\n\n";
+    for s in &report.timeline {
+        // pods antigos estão sempre disponíveis no modelo
+        let new_available = s.available.saturating_sub(s.old);
+        let mut bar = "O".repeat(chars(s.old)) + &"N".repeat(chars(new_available)) + &"n".repeat(chars(s.new - new_available));
+        let mark = chars(s.min_available);
+        if s.min_available > 0 {
+            if bar.len() < mark {
+                bar += &" ".repeat(mark - bar.len());
+            }
+            bar.insert(mark, '|');
+        }
+        let alert = if s.below_min { "  !" } else { "" };
+        let drain = if s.disruptions_allowed == Some(0) { "  drain bloqueado" } else { "" };
+        out += &format!(
+            "{:>8}  {:<width$}  {}/{}{}{}\n",
+            secs(s.t_ms),
+            bar,
+            s.available,
+            s.replicas,
+            alert,
+            drain,
+            width = chars(max_pods) + 1
+        );
+    }
+    out + "\n" + &summary(p, report)
+}
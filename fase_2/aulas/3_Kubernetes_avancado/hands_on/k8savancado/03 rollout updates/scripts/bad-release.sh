@@ -0,0 +1,19 @@
+#!/usr/bin/env bash
+set -euo pipefail
+# Release ruim com a mesma imagem: só o ambiente muda, o que já cria uma nova revisão do Deployment.
+# FAULT escolhe a falha (várias separadas por espaço); PROGRESS_DEADLINE encurta a espera do rollout.
+FAULT=${FAULT:-NEVER_READY=true}
+VERSION=${VERSION:-2.1.0-ruim}
+PROGRESS_DEADLINE=${PROGRESS_DEADLINE:-60}
+
+kubectl patch deployment myapp -p "{\"spec\":{\"progressDeadlineSeconds\":${PROGRESS_DEADLINE}}}"
+# shellcheck disable=SC2086 # FAULT pode ter várias variáveis
+kubectl set env deployment/myapp APP_VERSION="${VERSION}" ${FAULT}
+if kubectl rollout status deployment/myapp; then
+	echo "Rollout completou: a falha (${FAULT}) não afeta a readiness; compare as versões em /metrics."
+	exit 0
+fi
+
+kubectl get deployment myapp -o jsonpath='{.status.conditions[?(@.type=="Progressing")].reason}{"\n"}'
+kubectl rollout undo deployment/myapp
+kubectl rollout status deployment/myapp
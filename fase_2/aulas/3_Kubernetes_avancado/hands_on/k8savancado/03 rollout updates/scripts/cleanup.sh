@@ -2,6 +2,9 @@
 set -euo pipefail
 kubectl delete -f k8s/argo/rollout-canary.yaml --ignore-not-found
 kubectl delete -f k8s/argo/analysis-template.yaml --ignore-not-found
+kubectl delete -f k8s/daemonset-rolling.yaml --ignore-not-found
+kubectl delete -f k8s/statefulset-rolling.yaml --ignore-not-found
+kubectl delete -f k8s/pdb.yaml --ignore-not-found
 kubectl delete -f k8s/deployment-recreate.yaml --ignore-not-found
 kubectl delete -f k8s/deployment-rolling.yaml --ignore-not-found
 kubectl delete -f k8s/service.yaml --ignore-not-found